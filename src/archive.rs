@@ -0,0 +1,732 @@
+//! Module to deal with the .sb3 and .sprite3 archive
+//!
+//! An .sb3 file is a zip archive containing a `project.json` file
+//! and every costume and sound file the project uses, each named by its md5ext.
+//! A .sprite3 file is the same but for a single sprite, with `sprite.json` instead.
+
+use crate::asset::Asset;
+use crate::audio::{AudioError, SoundInfo};
+use crate::block::DataKind;
+use crate::prelude::*;
+use crate::project::Project;
+use crate::target::{Sprite, SpriteOrStage, Target};
+use crate::unused::PruneOptions;
+use crate::{builder, list::List, uid, variable::Variable};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::Path;
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+/// Files of an archive keyed by file name.
+pub type Files = BTreeMap<String, Vec<u8>>;
+
+/// Name of the project file inside an .sb3 archive.
+pub const PROJECT_JSON: &str = "project.json";
+
+/// Name of the sprite file inside a .sprite3 archive.
+pub const SPRITE_JSON: &str = "sprite.json";
+
+/// Whole .sb3 archive, the project and its asset files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sb3Archive {
+    /// The deserialized `project.json`.
+    pub project: Project,
+
+    /// Every other file in the archive, keyed by file name.
+    /// Asset files are named by [`Asset::file_name`], which is the md5ext.
+    pub files: Files,
+}
+
+/// Whole .sprite3 archive, a single sprite and its asset files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sprite3Archive {
+    /// The deserialized `sprite.json`.
+    pub sprite: Sprite,
+
+    /// Every other file in the archive, keyed by file name.
+    /// Asset files are named by [`Asset::file_name`], which is the md5ext.
+    pub files: Files,
+}
+
+/// Result of [`Sb3Archive::import_sprite`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteImport {
+    /// Name of the imported sprite in the project.
+    pub name: Name,
+
+    /// Ids of the sprite that conflicted with the project, mapped to their new Id.
+    pub remapped_ids: BTreeMap<Uid, Uid>,
+
+    /// See [`ImportWarning`]
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Something that had to change or could not be resolved when importing a sprite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportWarning {
+    /// The project already has a target with the sprite name, so the sprite was renamed.
+    RenamedSprite {
+        /// Name in the .sprite3
+        from: Name,
+        /// Name in the project
+        to: Name,
+    },
+
+    /// A local variable or list had the same name as a global one, so it was renamed.
+    RenamedLocal {
+        /// Variable or list
+        kind: DataKind,
+        /// Name in the .sprite3
+        from: Name,
+        /// Name in the project
+        to: Name,
+    },
+
+    /// Blocks referred to a variable or list that neither the sprite nor the stage declares,
+    /// so it was declared on the sprite.
+    DeclaredLocal {
+        /// Variable or list
+        kind: DataKind,
+        /// Name of the variable or list
+        name: Name,
+        /// Id of the variable or list
+        id: Uid,
+    },
+
+    /// Blocks use a broadcast that the stage does not declare.
+    UndeclaredBroadcast {
+        /// Name of the broadcast
+        name: Name,
+        /// Id of the broadcast
+        id: Uid,
+    },
+}
+
+/// Whether an asset is a costume or a sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    /// Costume or backdrop
+    Costume,
+
+    /// Sound
+    Sound,
+}
+
+/// A costume or sound referenced by a target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetReference {
+    /// Name of the target that owns the asset.
+    pub target_name: Name,
+
+    /// See [`AssetKind`]
+    pub kind: AssetKind,
+
+    /// Name of the costume or sound.
+    pub asset_name: Name,
+
+    /// The name of the asset file. See [`Asset::file_name`]
+    pub file_name: String,
+}
+
+/// A sound whose rate or sample count disagrees with its file.
+/// See [`Sb3Archive::sound_mismatches`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoundMismatch {
+    /// Name of the target that owns the sound.
+    pub target_name: Name,
+
+    /// Name of the sound.
+    pub sound_name: Name,
+
+    /// The name of the sound file. See [`Asset::file_name`]
+    pub file_name: String,
+
+    /// What the sound says, [`Sound::rate`](crate::asset::Sound::rate)
+    /// and [`Sound::sample_count`](crate::asset::Sound::sample_count).
+    pub declared: SoundInfo,
+
+    /// What the header of the file says, or why it could not be read.
+    pub actual: Result<SoundInfo, AudioError>,
+}
+
+/// Error when reading or writing an archive.
+#[derive(Debug)]
+pub enum Error {
+    /// IO error
+    Io(io::Error),
+
+    /// The file is not a valid zip archive
+    Zip(ZipError),
+
+    /// The json file inside the archive is not valid
+    Json(serde_json::Error),
+
+    /// The archive does not contain the json file, carries the expected file name
+    MissingJson(&'static str),
+
+    /// Two files of the archive have the same name, even in different folders,
+    /// or the files to write include the json file.
+    DuplicateFile(String),
+}
+
+impl Sb3Archive {
+    /// Create an archive from a project with no asset files,
+    /// but the [`EMPTY_SVG`](builder::EMPTY_SVG) of [`ProjectBuilder`](builder::ProjectBuilder) if the project uses it.
+    pub fn new(project: Project) -> Sb3Archive {
+        let mut archive = Sb3Archive {
+            project,
+            files: BTreeMap::new(),
+        };
+        let empty = builder::empty_costume("", 0, 0).asset;
+        if (archive.asset_references().iter()).any(|r| r.file_name == empty.file_name()) {
+            archive.insert_asset(&empty, builder::EMPTY_SVG.to_vec());
+        }
+        archive
+    }
+
+    /// Open an .sb3 file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Sb3Archive, Error> {
+        Sb3Archive::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Read an .sb3 archive.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Sb3Archive, Error> {
+        let (json, files) = read_zip(reader, PROJECT_JSON)?;
+        Ok(Sb3Archive {
+            project: serde_json::from_slice(&json)?,
+            files,
+        })
+    }
+
+    /// Read an .sb3 archive from memory.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Sb3Archive, Error> {
+        Sb3Archive::from_reader(Cursor::new(bytes))
+    }
+
+    /// Write the .sb3 archive.
+    pub fn write<W: Write + Seek>(&self, writer: W) -> Result<(), Error> {
+        let json = serde_json::to_vec(&self.project)?;
+        write_zip(writer, PROJECT_JSON, &json, &self.files)
+    }
+
+    /// Write the .sb3 archive into memory.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut cursor = Cursor::new(Vec::new());
+        self.write(&mut cursor)?;
+        Ok(cursor.into_inner())
+    }
+
+    /// Save the .sb3 archive to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    /// Content of the file of the asset, if the archive has it.
+    pub fn asset_data(&self, asset: &Asset) -> Option<&[u8]> {
+        self.files.get(&asset.file_name()).map(Vec::as_slice)
+    }
+
+    /// Add or replace the file of an asset.
+    pub fn insert_asset(&mut self, asset: &Asset, data: Vec<u8>) {
+        self.files.insert(asset.file_name(), data);
+    }
+
+    /// Every costume and sound of every target.
+    pub fn asset_references(&self) -> Vec<AssetReference> {
+        self.project
+            .targets
+            .iter()
+            .flat_map(|t| target_asset_references(t.target()))
+            .collect()
+    }
+
+    /// Costumes and sounds that are referenced but the archive does not have the file of.
+    pub fn missing_assets(&self) -> Vec<AssetReference> {
+        self.asset_references()
+            .into_iter()
+            .filter(|r| !self.files.contains_key(&r.file_name))
+            .collect()
+    }
+
+    /// Sounds whose rate or sample count disagrees with the header of their file,
+    /// which makes Scratch misreport their length. Sounds without a file are left out.
+    pub fn sound_mismatches(&self) -> Vec<SoundMismatch> {
+        (self.project.targets.iter())
+            .flat_map(|t| target_sound_mismatches(t.target(), &self.files))
+            .collect()
+    }
+
+    /// Files in the archive that no costume or sound references.
+    pub fn unreferenced_files(&self) -> Vec<&str> {
+        let referenced = self
+            .asset_references()
+            .into_iter()
+            .map(|r| r.file_name)
+            .collect::<std::collections::HashSet<_>>();
+        self.files
+            .keys()
+            .filter(|f| !referenced.contains(*f))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Prune the project, see [`Project::prune`],
+    /// and drop the files of the costumes and sounds it removed.
+    /// Files that were already unreferenced are kept.
+    pub fn prune(&mut self, options: PruneOptions) {
+        let file_names = |archive: &Sb3Archive| {
+            (archive.asset_references().into_iter())
+                .map(|r| r.file_name)
+                .collect::<HashSet<_>>()
+        };
+        let before = file_names(self);
+        self.project.prune(options);
+        let after = file_names(self);
+        self.files
+            .retain(|name, _| !before.contains(name) || after.contains(name));
+    }
+}
+
+impl Sb3Archive {
+    /// Export the sprite with the name into a .sprite3 archive, along with its asset files.
+    /// [`None`] if there is no such sprite.
+    pub fn export_sprite(&self, name: &str) -> Option<Sprite3Archive> {
+        let sprite = self.project.targets.iter().find_map(|t| match t {
+            SpriteOrStage::Sprite(s) if s.target.name == name => Some(s),
+            _ => None,
+        })?;
+        let files = target_asset_references(&sprite.target)
+            .into_iter()
+            .filter_map(|r| {
+                let data = self.files.get(&r.file_name)?;
+                Some((r.file_name, data.clone()))
+            })
+            .collect();
+        Some(Sprite3Archive {
+            sprite: sprite.clone(),
+            files,
+        })
+    }
+
+    /// Add the sprite of a .sprite3 archive to the project, along with its asset files.
+    ///
+    /// Like Scratch does when uploading a sprite:
+    ///  - the sprite is renamed if the name is taken,
+    ///  - Ids that conflict with the project are regenerated,
+    ///  - references to variables and lists the sprite does not declare are linked to the stage's by name,
+    ///    or declared on the sprite if the stage does not have them either,
+    ///  - local variables and lists that share a name with a global one are renamed.
+    ///
+    /// Broadcasts the stage does not declare are reported but left as is.
+    pub fn import_sprite(&mut self, sprite3: Sprite3Archive) -> SpriteImport {
+        let Sprite3Archive { mut sprite, files } = sprite3;
+        let mut warnings = vec![];
+
+        let target_names = self
+            .project
+            .targets
+            .iter()
+            .map(|t| t.target().name.clone())
+            .collect::<HashSet<_>>();
+        let name = utils::unused_name(&sprite.target.name, |n| target_names.contains(n));
+        if name != sprite.target.name {
+            warnings.push(ImportWarning::RenamedSprite {
+                from: std::mem::replace(&mut sprite.target.name, name.clone()),
+                to: name.clone(),
+            });
+        }
+
+        let project_ids = self
+            .project
+            .targets
+            .iter()
+            .flat_map(|t| target_ids(t.target()))
+            .cloned()
+            .collect::<HashSet<_>>();
+        let sprite_ids = target_ids(&sprite.target).cloned().collect::<Vec<_>>();
+        let mut used_ids = project_ids.clone();
+        used_ids.extend(sprite_ids.iter().cloned());
+        let mut remapped_ids = HashMap::new();
+        for id in sprite_ids {
+            if project_ids.contains(&id) {
+                let new_id = uid::generate_unused(|i| used_ids.contains(i));
+                used_ids.insert(new_id.clone());
+                remapped_ids.insert(id, new_id);
+            }
+        }
+        uid::remap_target(&mut sprite.target, &remapped_ids);
+
+        if let Some(stage) = self
+            .project
+            .targets
+            .iter()
+            .find(|t| t.is_stage())
+            .map(SpriteOrStage::target)
+        {
+            fix_up_data_references(&mut sprite.target, stage, &mut warnings);
+        }
+
+        sprite.target.layer_order = self
+            .project
+            .targets
+            .iter()
+            .map(|t| t.target().layer_order + 1)
+            .max()
+            .unwrap_or(0);
+        self.project.targets.push(SpriteOrStage::Sprite(sprite));
+        self.files.extend(files);
+
+        SpriteImport {
+            name,
+            remapped_ids: remapped_ids.into_iter().collect(),
+            warnings,
+        }
+    }
+}
+
+impl Sprite3Archive {
+    /// Create an archive from a sprite with no asset files.
+    pub fn new(sprite: Sprite) -> Sprite3Archive {
+        Sprite3Archive {
+            sprite,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Open a .sprite3 file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Sprite3Archive, Error> {
+        Sprite3Archive::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a .sprite3 archive.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Sprite3Archive, Error> {
+        let (json, files) = read_zip(reader, SPRITE_JSON)?;
+        Ok(Sprite3Archive {
+            sprite: serde_json::from_slice(&json)?,
+            files,
+        })
+    }
+
+    /// Read a .sprite3 archive from memory.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Sprite3Archive, Error> {
+        Sprite3Archive::from_reader(Cursor::new(bytes))
+    }
+
+    /// Write the .sprite3 archive.
+    pub fn write<W: Write + Seek>(&self, writer: W) -> Result<(), Error> {
+        let json = serde_json::to_vec(&self.sprite)?;
+        write_zip(writer, SPRITE_JSON, &json, &self.files)
+    }
+
+    /// Write the .sprite3 archive into memory.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut cursor = Cursor::new(Vec::new());
+        self.write(&mut cursor)?;
+        Ok(cursor.into_inner())
+    }
+
+    /// Save the .sprite3 archive to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+
+    /// Costumes and sounds that are referenced but the archive does not have the file of.
+    pub fn missing_assets(&self) -> Vec<AssetReference> {
+        target_asset_references(&self.sprite.target)
+            .into_iter()
+            .filter(|r| !self.files.contains_key(&r.file_name))
+            .collect()
+    }
+
+    /// See [`Sb3Archive::sound_mismatches`]
+    pub fn sound_mismatches(&self) -> Vec<SoundMismatch> {
+        target_sound_mismatches(&self.sprite.target, &self.files)
+    }
+}
+
+/// Ids of blocks, variables, lists, broadcasts and comments declared by the target.
+fn target_ids(target: &Target) -> impl Iterator<Item = &Uid> {
+    target
+        .blocks
+        .0
+        .keys()
+        .chain(target.variables.0.keys())
+        .chain(target.lists.0.keys())
+        .chain(target.broadcasts.0.keys())
+        .chain(target.comments.0.keys())
+}
+
+/// Link references of a newly imported sprite to the stage, see [`Sb3Archive::import_sprite`]
+fn fix_up_data_references(sprite: &mut Target, stage: &Target, warnings: &mut Vec<ImportWarning>) {
+    fn find_by_name<'a>(stage: &'a Target, kind: DataKind, name: &str) -> Option<&'a Uid> {
+        Some(match kind {
+            DataKind::Variable => stage.variables.0.iter().find(|(_, v)| v.name == name)?.0,
+            DataKind::List => stage.lists.0.iter().find(|(_, l)| l.name == name)?.0,
+            DataKind::Broadcast => stage.broadcasts.0.iter().find(|(_, b)| b.name == name)?.0,
+        })
+    }
+    fn declares(target: &Target, kind: DataKind, id: &str) -> bool {
+        match kind {
+            DataKind::Variable => target.variables.0.contains_key(id),
+            DataKind::List => target.lists.0.contains_key(id),
+            DataKind::Broadcast => target.broadcasts.0.contains_key(id),
+        }
+    }
+
+    // Local variables and lists that share a name with a global one
+    let mut renames = HashMap::new();
+    for (id, v) in &sprite.variables.0 {
+        if find_by_name(stage, DataKind::Variable, &v.name).is_some() {
+            renames.insert(id.clone(), (DataKind::Variable, v.name.clone()));
+        }
+    }
+    for (id, l) in &sprite.lists.0 {
+        if find_by_name(stage, DataKind::List, &l.name).is_some() {
+            renames.insert(id.clone(), (DataKind::List, l.name.clone()));
+        }
+    }
+    let renames = renames
+        .into_iter()
+        .map(|(id, (kind, from))| {
+            let to = utils::unused_name(&format!("{}: {from}", sprite.name), |n| {
+                find_by_name(stage, kind, n).is_some()
+                    || sprite.variables.0.values().any(|v| v.name == n)
+                    || sprite.lists.0.values().any(|l| l.name == n)
+            });
+            match kind {
+                DataKind::Variable => sprite.variables.0.get_mut(&id).unwrap().name = to.clone(),
+                _ => sprite.lists.0.get_mut(&id).unwrap().name = to.clone(),
+            }
+            warnings.push(ImportWarning::RenamedLocal {
+                kind,
+                from,
+                to: to.clone(),
+            });
+            (id, to)
+        })
+        .collect::<HashMap<_, _>>();
+
+    // References that are not declared locally
+    let mut relinks = HashMap::new();
+    let mut declares_local = vec![];
+    for block in sprite.blocks.0.values() {
+        for (kind, name, id) in block.data_references() {
+            if declares(sprite, kind, id)
+                || (kind != DataKind::Broadcast && declares(stage, kind, id))
+            {
+                continue;
+            }
+            if kind == DataKind::Broadcast
+                && stage.broadcasts.0.get(id).map(|b| &b.name) == Some(&name)
+            {
+                continue;
+            }
+            if let Some(stage_id) = find_by_name(stage, kind, &name) {
+                relinks.insert(id.clone(), stage_id.clone());
+            } else if kind == DataKind::Broadcast {
+                let warning = ImportWarning::UndeclaredBroadcast {
+                    name,
+                    id: id.clone(),
+                };
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            } else if !declares_local.iter().any(|(_, _, i)| i == id) {
+                declares_local.push((kind, name, id.clone()));
+            }
+        }
+    }
+    for (kind, name, id) in declares_local {
+        match kind {
+            DataKind::Variable => {
+                let variable = Variable {
+                    name: name.clone(),
+                    ..Default::default()
+                };
+                sprite.variables.0.insert(id.clone(), variable);
+            }
+            _ => {
+                let list = List {
+                    name: name.clone(),
+                    ..Default::default()
+                };
+                sprite.lists.0.insert(id.clone(), list);
+            }
+        }
+        warnings.push(ImportWarning::DeclaredLocal { kind, name, id });
+    }
+
+    uid::remap_target(sprite, &relinks);
+    if !renames.is_empty() {
+        for block in sprite.blocks.0.values_mut() {
+            block.for_each_data_reference_mut(|kind, name, id| {
+                if let (true, Some(to)) = (kind != DataKind::Broadcast, renames.get(id)) {
+                    *name = to.clone();
+                }
+            });
+        }
+    }
+}
+
+pub(crate) fn target_asset_references(target: &crate::target::Target) -> Vec<AssetReference> {
+    let costumes = target
+        .costumes
+        .iter()
+        .map(|c| (AssetKind::Costume, &c.asset));
+    let sounds = target.sounds.iter().map(|s| (AssetKind::Sound, &s.asset));
+    costumes
+        .chain(sounds)
+        .map(|(kind, asset)| AssetReference {
+            target_name: target.name.clone(),
+            kind,
+            asset_name: asset.name.clone(),
+            file_name: asset.file_name(),
+        })
+        .collect()
+}
+
+fn target_sound_mismatches(target: &Target, files: &Files) -> Vec<SoundMismatch> {
+    let mut mismatches = vec![];
+    for sound in &target.sounds {
+        let file_name = sound.asset.file_name();
+        let Some(data) = files.get(&file_name) else {
+            continue;
+        };
+        let declared = sound.info();
+        let actual = SoundInfo::from_bytes(data);
+        if actual.as_ref() != Ok(&declared) {
+            mismatches.push(SoundMismatch {
+                target_name: target.name.clone(),
+                sound_name: sound.asset.name.clone(),
+                file_name,
+                declared,
+                actual,
+            });
+        }
+    }
+    mismatches
+}
+
+/// Read the zip, returning the content of `json_name` and every other file.
+/// The json file may be inside a folder, other files are keyed by their name without the folder,
+/// so two files with the same name are an error.
+pub(crate) fn read_zip<R: Read + Seek>(
+    reader: R,
+    json_name: &'static str,
+) -> Result<(Vec<u8>, Files), Error> {
+    let mut zip = ZipArchive::new(reader)?;
+    let mut json = None;
+    let mut files = BTreeMap::new();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file
+            .name()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        // The size in the header may be a lie, let the data decide how much to allocate
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        if name == json_name && json.is_none() {
+            json = Some(data);
+        } else if name == json_name || files.contains_key(&name) {
+            return Err(Error::DuplicateFile(name));
+        } else {
+            files.insert(name, data);
+        }
+    }
+    let json = json.ok_or(Error::MissingJson(json_name))?;
+    Ok((json, files))
+}
+
+pub(crate) fn write_zip<W: Write + Seek>(
+    writer: W,
+    json_name: &str,
+    json: &[u8],
+    files: &Files,
+) -> Result<(), Error> {
+    if files.contains_key(json_name) {
+        return Err(Error::DuplicateFile(json_name.to_owned()));
+    }
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(writer);
+    zip.start_file(json_name, options)?;
+    zip.write_all(json)?;
+    for (name, data) in files {
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(data)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+// Display impl ================================================================
+
+impl fmt::Display for SoundMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let SoundMismatch {
+            target_name,
+            sound_name,
+            file_name,
+            declared,
+            actual,
+        } = self;
+        write!(f, "sound `{sound_name}` of `{target_name}`: ")?;
+        match actual {
+            Ok(actual) => write!(f, "says {declared} but file `{file_name}` has {actual}"),
+            Err(e) => write!(f, "file `{file_name}` could not be read: {e}"),
+        }
+    }
+}
+
+// Error impl ==================================================================
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {e}"),
+            Error::Zip(e) => write!(f, "zip error: {e}"),
+            Error::Json(e) => write!(f, "json error: {e}"),
+            Error::MissingJson(name) => write!(f, "archive does not contain `{name}`"),
+            Error::DuplicateFile(name) => {
+                write!(f, "archive contains `{name}` more than once")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Zip(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::MissingJson(_) | Error::DuplicateFile(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<ZipError> for Error {
+    fn from(e: ZipError) -> Self {
+        Error::Zip(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
@@ -1,6 +1,14 @@
 //! Module to deal with Scratch asset
+//!
+//! Asset files are named by the MD5 hash of their content and the extension of their format.
+//! [`Costume::from_bytes`] and [`Sound::from_bytes`] compute those from the file,
+//! [`Asset::verify`] checks that an existing asset still matches its file.
 
+use crate::archive::AssetKind;
+use crate::audio::AudioError;
+use crate::image::Centering;
 use crate::prelude::*;
+use std::fmt;
 
 /// Costume Asset.
 /// Is considered backdrop if stage.
@@ -21,6 +29,10 @@ pub struct Costume {
     /// See [`Asset`]
     #[serde(flatten)]
     pub asset: Asset,
+
+    /// Unknown keys, see [`StringHashMap`]
+    #[serde(flatten)]
+    pub extra: StringHashMap<Json>,
 }
 
 impl Default for Costume {
@@ -30,6 +42,7 @@ impl Default for Costume {
             rotation_center_y: Number::Int(0),
             bitmap_resolution: None,
             asset: Default::default(),
+            extra: Default::default(),
         }
     }
 }
@@ -52,6 +65,10 @@ pub struct Sound {
     /// See [`Asset`]
     #[serde(flatten)]
     pub asset: Asset,
+
+    /// Unknown keys, see [`StringHashMap`]
+    #[serde(flatten)]
+    pub extra: StringHashMap<Json>,
 }
 
 /// An asset is a costume or sound. (Backdrops are considered costumes.)
@@ -72,3 +89,308 @@ pub struct Asset {
     /// The name of the format of the asset file.
     pub data_format: String,
 }
+
+impl Asset {
+    /// The name of the asset file inside an .sb3 or .sprite3 archive.
+    /// Falls back to `<assetId>.<dataFormat>` when [`Asset::md5ext`] is absent, like Scratch does.
+    pub fn file_name(&self) -> String {
+        match &self.md5ext {
+            Some(md5ext) => md5ext.clone(),
+            None => format!("{}.{}", self.asset_id, self.data_format),
+        }
+    }
+}
+
+/// Format of an asset file, the [`Asset::data_format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataFormat {
+    /// Vector costume, `svg`
+    Svg,
+    /// Bitmap costume, `png`
+    Png,
+    /// Bitmap costume, `jpg`
+    Jpg,
+    /// Bitmap costume, `gif`. Scratch converts it to png when uploaded.
+    Gif,
+    /// Sound, `wav`
+    Wav,
+    /// Sound, `mp3`
+    Mp3,
+}
+
+impl DataFormat {
+    /// The name of the format, as it is in [`Asset::data_format`] and in the extension of the file.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DataFormat::Svg => "svg",
+            DataFormat::Png => "png",
+            DataFormat::Jpg => "jpg",
+            DataFormat::Gif => "gif",
+            DataFormat::Wav => "wav",
+            DataFormat::Mp3 => "mp3",
+        }
+    }
+
+    /// Whether the format is for costumes or sounds.
+    pub fn kind(self) -> AssetKind {
+        match self {
+            DataFormat::Svg | DataFormat::Png | DataFormat::Jpg | DataFormat::Gif => {
+                AssetKind::Costume
+            }
+            DataFormat::Wav | DataFormat::Mp3 => AssetKind::Sound,
+        }
+    }
+
+    /// Detect the format of a file from its magic bytes.
+    /// [`None`] if the file is in none of the formats.
+    pub fn detect(bytes: &[u8]) -> Option<DataFormat> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(DataFormat::Png)
+        } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(DataFormat::Jpg)
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some(DataFormat::Gif)
+        } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WAVE" {
+            Some(DataFormat::Wav)
+        } else if bytes.starts_with(b"ID3")
+            || (bytes.len() >= 2 && bytes[0] == 0xff && bytes[1] & 0xe0 == 0xe0)
+        {
+            // Either an ID3 tag or straight to the sync bits of the first frame
+            Some(DataFormat::Mp3)
+        } else if is_svg(bytes) {
+            Some(DataFormat::Svg)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::str::FromStr for DataFormat {
+    type Err = AssetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "svg" => Ok(DataFormat::Svg),
+            "png" => Ok(DataFormat::Png),
+            "jpg" | "jpeg" => Ok(DataFormat::Jpg),
+            "gif" => Ok(DataFormat::Gif),
+            "wav" => Ok(DataFormat::Wav),
+            "mp3" => Ok(DataFormat::Mp3),
+            _ => Err(AssetError::UnknownFormat),
+        }
+    }
+}
+
+/// SVG is text, it may start with a byte order mark, an xml declaration, comments or a doctype
+/// before the `<svg` tag, so look for the tag in the beginning of the file.
+fn is_svg(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(1024)];
+    let head = String::from_utf8_lossy(head);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    head.starts_with('<') && head.contains("<svg")
+}
+
+/// Error when creating or checking an asset from its file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetError {
+    /// The format of the file could not be detected, or the given format is not one Scratch uses.
+    UnknownFormat,
+
+    /// The format of the file is not the given one.
+    FormatMismatch {
+        /// The given format
+        expected: DataFormat,
+        /// The format the file is in
+        found: DataFormat,
+    },
+
+    /// A sound file given for a costume or the other way around.
+    WrongKind {
+        /// The format of the file
+        format: DataFormat,
+        /// What the asset was created as
+        kind: AssetKind,
+    },
+
+    /// The file does not hash to [`Asset::asset_id`].
+    HashMismatch {
+        /// The Id of the asset
+        expected: Uid,
+        /// The MD5 hash of the file
+        found: Uid,
+    },
+
+    /// The header of the sound file could not be read.
+    Audio(AudioError),
+
+    /// [`Asset::md5ext`] is not `<assetId>.<dataFormat>`.
+    Md5extMismatch {
+        /// What it should be
+        expected: String,
+        /// What it is
+        found: String,
+    },
+}
+
+/// The MD5 hash of the file in lowercase hex, which is the [`Asset::asset_id`].
+pub fn md5_hex(bytes: &[u8]) -> Uid {
+    format!("{:x}", md5::compute(bytes))
+}
+
+impl Asset {
+    /// Create an asset for the file, named `name`.
+    /// The format is detected from the file when `format` is [`None`].
+    /// Otherwise the file is checked to be in `format`,
+    /// unless it is in no format that can be detected.
+    pub fn from_bytes(
+        name: impl Into<Name>,
+        bytes: &[u8],
+        format: Option<DataFormat>,
+    ) -> Result<Asset, AssetError> {
+        let format = match (format, DataFormat::detect(bytes)) {
+            (Some(expected), Some(found)) if expected != found => {
+                return Err(AssetError::FormatMismatch { expected, found })
+            }
+            (Some(format), _) | (None, Some(format)) => format,
+            (None, None) => return Err(AssetError::UnknownFormat),
+        };
+        let asset_id = md5_hex(bytes);
+        Ok(Asset {
+            md5ext: Some(format!("{asset_id}.{}", format.as_str())),
+            asset_id,
+            name: name.into(),
+            data_format: format.as_str().to_owned(),
+        })
+    }
+
+    /// Check that the file hashes to [`Asset::asset_id`] and that [`Asset::md5ext`], if any,
+    /// is named after it.
+    pub fn verify(&self, bytes: &[u8]) -> Result<(), AssetError> {
+        let found = md5_hex(bytes);
+        if !found.eq_ignore_ascii_case(&self.asset_id) {
+            return Err(AssetError::HashMismatch {
+                expected: self.asset_id.clone(),
+                found,
+            });
+        }
+        if let Some(md5ext) = &self.md5ext {
+            let expected = format!("{}.{}", self.asset_id, self.data_format);
+            if *md5ext != expected {
+                return Err(AssetError::Md5extMismatch {
+                    expected,
+                    found: md5ext.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn from_bytes_of_kind(
+        name: Name,
+        bytes: &[u8],
+        format: Option<DataFormat>,
+        kind: AssetKind,
+    ) -> Result<Asset, AssetError> {
+        let asset = Asset::from_bytes(name, bytes, format)?;
+        let format: DataFormat = asset.data_format.parse()?;
+        if format.kind() != kind {
+            return Err(AssetError::WrongKind { format, kind });
+        }
+        Ok(asset)
+    }
+}
+
+impl Costume {
+    /// Create a costume for the image file, see [`Asset::from_bytes`].
+    /// The rotation center is in the middle of the image,
+    /// see [`Costume::center_from_bytes`] and [`Centering::Image`].
+    /// It is left at the top left corner when the size of the image cannot be read.
+    pub fn from_bytes(
+        name: impl Into<Name>,
+        bytes: &[u8],
+        format: Option<DataFormat>,
+    ) -> Result<Costume, AssetError> {
+        let asset = Asset::from_bytes_of_kind(name.into(), bytes, format, AssetKind::Costume)?;
+        let is_svg = asset.data_format == DataFormat::Svg.as_str();
+        let mut costume = Costume {
+            asset,
+            ..Default::default()
+        };
+        if costume.center_from_bytes(bytes, Centering::Image).is_err() {
+            costume.bitmap_resolution = Some(if is_svg { 1 } else { 2 });
+        }
+        Ok(costume)
+    }
+}
+
+impl Sound {
+    /// Create a sound for the audio file, see [`Asset::from_bytes`].
+    /// [`Sound::rate`] and [`Sound::sample_count`] are read from the file,
+    /// see [`SoundInfo::from_bytes`](crate::audio::SoundInfo::from_bytes).
+    pub fn from_bytes(
+        name: impl Into<Name>,
+        bytes: &[u8],
+        format: Option<DataFormat>,
+    ) -> Result<Sound, AssetError> {
+        let asset = Asset::from_bytes_of_kind(name.into(), bytes, format, AssetKind::Sound)?;
+        let mut sound = Sound {
+            asset,
+            ..Default::default()
+        };
+        sound.set_info_from_bytes(bytes)?;
+        Ok(sound)
+    }
+}
+
+// Display impl ================================================================
+
+impl fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Error impl ==================================================================
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::UnknownFormat => write!(f, "unknown asset format"),
+            AssetError::FormatMismatch { expected, found } => {
+                write!(f, "expected a {expected} file but found a {found} file")
+            }
+            AssetError::WrongKind { format, kind } => {
+                let kind = match kind {
+                    AssetKind::Costume => "costume",
+                    AssetKind::Sound => "sound",
+                };
+                write!(f, "a {format} file cannot be a {kind}")
+            }
+            AssetError::HashMismatch { expected, found } => {
+                write!(
+                    f,
+                    "expected the file to hash to `{expected}` but it hashes to `{found}`"
+                )
+            }
+            AssetError::Audio(e) => write!(f, "audio error: {e}"),
+            AssetError::Md5extMismatch { expected, found } => {
+                write!(f, "expected md5ext `{expected}` but found `{found}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::Audio(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<AudioError> for AssetError {
+    fn from(e: AudioError) -> Self {
+        AssetError::Audio(e)
+    }
+}
@@ -0,0 +1,304 @@
+//! Module to read the sampling rate and length of sound files
+//!
+//! [`Sound::rate`] and [`Sound::sample_count`] must agree with the sound file,
+//! or Scratch misreports the length of the sound.
+//! Only the headers are read: the `fmt ` and `data` chunks of WAV files (PCM and ADPCM)
+//! and the frame headers of MP3 files, nothing is decoded.
+
+use crate::asset::{DataFormat, Sound};
+use std::fmt;
+
+/// Sampling rate and length of a sound file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoundInfo {
+    /// The sampling rate in Hertz.
+    pub rate: u64,
+
+    /// The number of samples per channel.
+    pub sample_count: u64,
+}
+
+/// Error when reading the header of a sound file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioError {
+    /// The file is neither WAV nor MP3.
+    UnknownFormat,
+
+    /// The file ends in the middle of a header.
+    Truncated,
+
+    /// A chunk the WAV file must have is missing.
+    MissingChunk {
+        /// The Id of the chunk, such as `fmt `
+        chunk: &'static str,
+    },
+
+    /// The WAV file is encoded in a way Scratch cannot play.
+    UnsupportedEncoding {
+        /// The `wFormatTag` of the `fmt ` chunk
+        format_tag: u16,
+    },
+
+    /// A value of the header makes no sense, such as a rate of 0.
+    InvalidHeader,
+
+    /// The MP3 file has no frame.
+    NoFrames,
+}
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_MS_ADPCM: u16 = 0x0002;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_ALAW: u16 = 0x0006;
+const WAVE_FORMAT_MULAW: u16 = 0x0007;
+const WAVE_FORMAT_IMA_ADPCM: u16 = 0x0011;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+impl SoundInfo {
+    /// Read the header of a WAV or MP3 file, detected from its magic bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<SoundInfo, AudioError> {
+        match DataFormat::detect(bytes) {
+            Some(DataFormat::Wav) => SoundInfo::from_wav(bytes),
+            Some(DataFormat::Mp3) => SoundInfo::from_mp3(bytes),
+            _ => Err(AudioError::UnknownFormat),
+        }
+    }
+
+    /// Read the `fmt `, `fact` and `data` chunks of a WAV file.
+    /// For ADPCM the length comes from the `fact` chunk, or from the number of blocks without it.
+    pub fn from_wav(bytes: &[u8]) -> Result<SoundInfo, AudioError> {
+        if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(AudioError::UnknownFormat);
+        }
+        let mut fmt = None;
+        let mut fact = None;
+        let mut data_len = None;
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let size = u32_le(bytes, offset + 4) as usize;
+            let start = offset + 8;
+            // Streaming encoders leave the size of the last chunk unknown, use what is there
+            let end = start.saturating_add(size).min(bytes.len());
+            match id {
+                b"fmt " => fmt = Some(&bytes[start..end]),
+                b"fact" if end - start >= 4 => fact = Some(u32_le(bytes, start) as u64),
+                b"data" => data_len = Some((end - start) as u64),
+                _ => {}
+            }
+            // Chunks are padded to an even size
+            offset = start.saturating_add(size).saturating_add(size & 1);
+        }
+        let fmt = fmt.ok_or(AudioError::MissingChunk { chunk: "fmt " })?;
+        let data_len = data_len.ok_or(AudioError::MissingChunk { chunk: "data" })?;
+        if fmt.len() < 16 {
+            return Err(AudioError::Truncated);
+        }
+
+        let mut format_tag = u16_le(fmt, 0);
+        let channels = u16_le(fmt, 2) as u64;
+        let rate = u32_le(fmt, 4) as u64;
+        let block_align = u16_le(fmt, 12) as u64;
+        if format_tag == WAVE_FORMAT_EXTENSIBLE {
+            // The actual format is the start of the sub format GUID
+            if fmt.len() < 26 {
+                return Err(AudioError::Truncated);
+            }
+            format_tag = u16_le(fmt, 24);
+        }
+        if channels == 0 || rate == 0 || block_align == 0 {
+            return Err(AudioError::InvalidHeader);
+        }
+
+        let sample_count = match format_tag {
+            WAVE_FORMAT_PCM | WAVE_FORMAT_IEEE_FLOAT | WAVE_FORMAT_ALAW | WAVE_FORMAT_MULAW => {
+                data_len / block_align
+            }
+            WAVE_FORMAT_IMA_ADPCM | WAVE_FORMAT_MS_ADPCM => match fact {
+                Some(fact) => fact,
+                None => {
+                    if fmt.len() < 20 {
+                        return Err(AudioError::Truncated);
+                    }
+                    let samples_per_block = u16_le(fmt, 18) as u64;
+                    // A block starts with a header per channel holding the first samples,
+                    // every byte after it holds 2 samples
+                    let (header, header_samples) = if format_tag == WAVE_FORMAT_IMA_ADPCM {
+                        (4 * channels, 1)
+                    } else {
+                        (7 * channels, 2)
+                    };
+                    let last = data_len % block_align;
+                    let last_samples = match last.checked_sub(header) {
+                        Some(rest) => header_samples + rest * 2 / channels,
+                        None => 0,
+                    };
+                    data_len / block_align * samples_per_block + last_samples
+                }
+            },
+            format_tag => return Err(AudioError::UnsupportedEncoding { format_tag }),
+        };
+        Ok(SoundInfo { rate, sample_count })
+    }
+
+    /// Walk the frame headers of an MP3 file, after its ID3v2 tag if any.
+    /// The rate is the one of the first frame and a Xing, Info or VBRI frame is not counted.
+    pub fn from_mp3(bytes: &[u8]) -> Result<SoundInfo, AudioError> {
+        let mut offset = 0;
+        if bytes.starts_with(b"ID3") {
+            if bytes.len() < 10 {
+                return Err(AudioError::Truncated);
+            }
+            // The size is 4 bytes of 7 bits, not counting the 10 bytes header and the footer
+            let size = bytes[6..10]
+                .iter()
+                .fold(0, |size, &b| (size << 7) | (b & 0x7f) as usize);
+            let footer = if bytes[5] & 0x10 != 0 { 10 } else { 0 };
+            offset = 10 + size + footer;
+        }
+
+        let mut rate = None;
+        let mut sample_count = 0;
+        let mut first = true;
+        while offset + 4 <= bytes.len() {
+            let Some(frame) = Mp3Frame::parse(&bytes[offset..offset + 4]) else {
+                // Trailing ID3v1 tag or garbage
+                break;
+            };
+            // The tag comes right after the side information, within the first 40 bytes
+            let end = (offset + 40).min(bytes.len());
+            let is_info = first
+                && [&b"Xing"[..], b"Info", b"VBRI"]
+                    .iter()
+                    .any(|tag| bytes[offset..end].windows(4).any(|w| w == *tag));
+            if !is_info {
+                sample_count += frame.samples;
+            }
+            rate.get_or_insert(frame.rate);
+            first = false;
+            offset += frame.len;
+        }
+        let rate = rate.ok_or(AudioError::NoFrames)?;
+        Ok(SoundInfo { rate, sample_count })
+    }
+}
+
+/// What a MP3 frame header says.
+struct Mp3Frame {
+    rate: u64,
+    samples: u64,
+    len: usize,
+}
+
+impl Mp3Frame {
+    /// [`None`] if the bytes are not a valid frame header.
+    fn parse(header: &[u8]) -> Option<Mp3Frame> {
+        if header[0] != 0xff || header[1] & 0xe0 != 0xe0 {
+            return None;
+        }
+        // 0 is MPEG 2.5, 1 reserved, 2 MPEG 2, 3 MPEG 1
+        let version = (header[1] >> 3) & 0b11;
+        // 1 is layer III, 2 layer II, 3 layer I
+        let layer = (header[1] >> 1) & 0b11;
+        let bitrate_index = (header[2] >> 4) as usize;
+        let rate_index = ((header[2] >> 2) & 0b11) as usize;
+        let padding = ((header[2] >> 1) & 1) as u64;
+        if version == 1
+            || layer == 0
+            || bitrate_index == 0
+            || bitrate_index == 15
+            || rate_index == 3
+        {
+            return None;
+        }
+
+        let mpeg1 = version == 3;
+        let kbps: u64 = match (mpeg1, layer) {
+            (true, 3) => MPEG1_LAYER1[bitrate_index],
+            (true, 2) => MPEG1_LAYER2[bitrate_index],
+            (true, _) => MPEG1_LAYER3[bitrate_index],
+            (false, 3) => MPEG2_LAYER1[bitrate_index],
+            (false, _) => MPEG2_LAYER2_3[bitrate_index],
+        };
+        let rate = [44100, 48000, 32000][rate_index] >> (3 - version).min(2);
+        let bitrate = kbps * 1000;
+        let (samples, len) = match layer {
+            3 => (384, (12 * bitrate / rate + padding) * 4),
+            2 => (1152, 144 * bitrate / rate + padding),
+            _ if mpeg1 => (1152, 144 * bitrate / rate + padding),
+            _ => (576, 72 * bitrate / rate + padding),
+        };
+        Some(Mp3Frame {
+            rate,
+            samples,
+            len: len as usize,
+        })
+    }
+}
+
+const MPEG1_LAYER1: [u64; 15] = [
+    0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+];
+const MPEG1_LAYER2: [u64; 15] = [
+    0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+];
+const MPEG1_LAYER3: [u64; 15] = [
+    0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+];
+const MPEG2_LAYER1: [u64; 15] = [
+    0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+];
+const MPEG2_LAYER2_3: [u64; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+fn u16_le(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_le(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+impl Sound {
+    /// The rate and length the sound says its file has.
+    pub fn info(&self) -> SoundInfo {
+        SoundInfo {
+            rate: self.rate,
+            sample_count: self.sample_count,
+        }
+    }
+
+    /// Set [`Sound::rate`] and [`Sound::sample_count`] from the header of the sound file.
+    pub fn set_info_from_bytes(&mut self, bytes: &[u8]) -> Result<(), AudioError> {
+        let info = SoundInfo::from_bytes(bytes)?;
+        self.rate = info.rate;
+        self.sample_count = info.sample_count;
+        Ok(())
+    }
+}
+
+// Display impl ================================================================
+
+impl fmt::Display for SoundInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} samples at {} Hz", self.sample_count, self.rate)
+    }
+}
+
+// Error impl ==================================================================
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::UnknownFormat => write!(f, "not a WAV or MP3 file"),
+            AudioError::Truncated => write!(f, "the file ends in the middle of a header"),
+            AudioError::MissingChunk { chunk } => write!(f, "no `{chunk}` chunk"),
+            AudioError::UnsupportedEncoding { format_tag } => {
+                write!(f, "unsupported WAV encoding {format_tag:#06x}")
+            }
+            AudioError::InvalidHeader => write!(f, "invalid header"),
+            AudioError::NoFrames => write!(f, "no MP3 frame"),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
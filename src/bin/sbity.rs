@@ -0,0 +1,418 @@
+//! `sbity`, command line tool to inspect and transform Scratch 3 projects
+//!
+//! Every command takes either a raw project.json or an .sb3 file, `-` reads from stdin.
+//! Exits with 0 on success, 1 when `validate` finds problems or `diff` finds differences,
+//! and 2 on errors.
+
+use clap::{Parser, Subcommand};
+use sb_sbity::archive::{AssetKind, Sb3Archive, PROJECT_JSON};
+use sb_sbity::diff;
+use sb_sbity::project::Project;
+use serde_json::Value as Json;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Inspect and transform Scratch 3 projects, given as project.json or .sb3 files.
+///
+/// Exits with 0 on success, 1 when `validate` finds problems or `diff` finds differences,
+/// and 2 on errors.
+#[derive(Debug, Parser)]
+#[command(name = "sbity", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print the targets with their blocks, data and assets
+    Info {
+        /// project.json or .sb3 file, `-` for stdin
+        input: PathBuf,
+    },
+
+    /// Check the project for problems, and for missing asset files if it's an .sb3
+    Validate {
+        /// project.json or .sb3 file, `-` for stdin
+        input: PathBuf,
+    },
+
+    /// Write project.json as canonical pretty JSON, with every map sorted by key
+    Fmt {
+        /// project.json or .sb3 file, `-` for stdin
+        input: PathBuf,
+
+        /// Where to write the JSON, stdout if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Pull project.json out of an .sb3
+    Extract {
+        /// .sb3 file, `-` for stdin
+        input: PathBuf,
+
+        /// Where to write project.json, stdout if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Directory to also write the asset files to
+        #[arg(long)]
+        assets: Option<PathBuf>,
+    },
+
+    /// Pack a project.json and the asset files it uses into an .sb3
+    Pack {
+        /// project.json or .sb3 file, `-` for stdin
+        input: PathBuf,
+
+        /// Where to write the .sb3
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Directory to take the asset files from, the directory of the input if not given
+        #[arg(long)]
+        assets: Option<PathBuf>,
+    },
+
+    /// Show what differs between two projects, sprite by sprite and script by script
+    Diff {
+        /// project.json or .sb3 file
+        old: PathBuf,
+
+        /// project.json or .sb3 file
+        new: PathBuf,
+
+        /// Show every JSON pointer that differs and the asset files instead,
+        /// Ids and the order of keys included
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// A project read from a project.json or an .sb3.
+struct Input {
+    /// Asset files are empty for a project.json
+    archive: Sb3Archive,
+    is_archive: bool,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Info { input } => info(&input),
+        Command::Validate { input } => validate(&input),
+        Command::Fmt { input, output } => fmt(&input, output.as_deref()),
+        Command::Extract {
+            input,
+            output,
+            assets,
+        } => extract(&input, output.as_deref(), assets.as_deref()),
+        Command::Pack {
+            input,
+            output,
+            assets,
+        } => pack(&input, &output, assets.as_deref()),
+        Command::Diff { old, new, json } => diff(&old, &new, json),
+    };
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("sbity: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn info(input: &Path) -> Result<ExitCode, String> {
+    let Input {
+        archive,
+        is_archive,
+    } = load(input)?;
+    let project = &archive.project;
+
+    for target in &project.targets {
+        let kind = if target.is_stage() { "stage" } else { "sprite" };
+        let target = target.target();
+        println!("{} ({kind})", target.name);
+        println!(
+            "  {} blocks in {} scripts",
+            target.blocks.len(),
+            target.scripts().len()
+        );
+        println!(
+            "  {} variables, {} lists, {} broadcasts, {} comments",
+            target.variables.len(),
+            target.lists.len(),
+            target.broadcasts.len(),
+            target.comments.len()
+        );
+        println!(
+            "  {} costumes, {} sounds",
+            target.costumes.len(),
+            target.sounds.len()
+        );
+    }
+    println!("{} monitors", project.monitors.len());
+    if !project.extensions.is_empty() {
+        let extensions: Vec<&str> = project.extensions.iter().map(|e| e.as_str()).collect();
+        println!("extensions: {}", extensions.join(", "));
+    }
+
+    let references = archive.asset_references();
+    println!("{} assets", references.len());
+    if is_archive {
+        println!("{} files", archive.files.len());
+        for reference in archive.missing_assets() {
+            println!(
+                "  missing {} `{}` of `{}`: {}",
+                asset_kind(reference.kind),
+                reference.asset_name,
+                reference.target_name,
+                reference.file_name
+            );
+        }
+        for file in archive.unreferenced_files() {
+            println!("  unreferenced: {file}");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn validate(input: &Path) -> Result<ExitCode, String> {
+    let Input {
+        archive,
+        is_archive,
+    } = load(input)?;
+
+    let mut problems: Vec<String> = archive
+        .project
+        .validate()
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+    if is_archive {
+        for reference in archive.missing_assets() {
+            problems.push(format!(
+                "{} `{}` of `{}`: file `{}` is not in the archive",
+                asset_kind(reference.kind),
+                reference.asset_name,
+                reference.target_name,
+                reference.file_name
+            ));
+        }
+        for mismatch in archive.sound_mismatches() {
+            problems.push(mismatch.to_string());
+        }
+    }
+
+    for problem in &problems {
+        println!("{problem}");
+    }
+    if problems.is_empty() {
+        eprintln!("no problems found");
+        Ok(ExitCode::SUCCESS)
+    } else {
+        eprintln!("{} problems found", problems.len());
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn fmt(input: &Path, output: Option<&Path>) -> Result<ExitCode, String> {
+    let mut project = load(input)?.archive.project;
+    project.sort_keys();
+    let mut json = serde_json::to_vec_pretty(&project).map_err(|e| e.to_string())?;
+    json.push(b'\n');
+    write_output(output, &json)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn extract(input: &Path, output: Option<&Path>, assets: Option<&Path>) -> Result<ExitCode, String> {
+    let Input {
+        archive,
+        is_archive,
+    } = load(input)?;
+    if !is_archive {
+        return Err(format!("{}: not an .sb3 archive", input.display()));
+    }
+
+    let json = serde_json::to_vec(&archive.project).map_err(|e| e.to_string())?;
+    write_output(output, &json)?;
+    if let Some(dir) = assets {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        for (name, data) in &archive.files {
+            // Asset files are flat, don't let a crafted name write outside of the directory
+            if Path::new(name).file_name() != Some(name.as_ref()) {
+                return Err(format!("{}: bad file name `{name}`", input.display()));
+            }
+            let path = dir.join(name);
+            fs::write(&path, data).map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn pack(input: &Path, output: &Path, assets: Option<&Path>) -> Result<ExitCode, String> {
+    let mut archive = load(input)?.archive;
+    let dir = match assets {
+        Some(dir) => dir.to_owned(),
+        None => input.parent().unwrap_or(Path::new(".")).to_owned(),
+    };
+
+    let mut missing = vec![];
+    for reference in archive.missing_assets() {
+        let path = dir.join(&reference.file_name);
+        match fs::read(&path) {
+            Ok(data) => {
+                archive.files.insert(reference.file_name, data);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if !missing.contains(&reference.file_name) {
+                    missing.push(reference.file_name);
+                }
+            }
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(format!(
+            "asset files not found in {}: {}",
+            dir.display(),
+            missing.join(", ")
+        ));
+    }
+
+    archive
+        .save(output)
+        .map_err(|e| format!("{}: {e}", output.display()))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn diff(old: &Path, new: &Path, json: bool) -> Result<ExitCode, String> {
+    let old = load(old)?.archive;
+    let new = load(new)?.archive;
+    if !json {
+        let diff = diff::diff(&old.project, &new.project);
+        print!("{diff}");
+        return Ok(if diff.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+
+    let to_json = |p: &Project| serde_json::to_value(p).map_err(|e| e.to_string());
+    let mut lines = vec![];
+    diff_json(
+        "",
+        &to_json(&old.project)?,
+        &to_json(&new.project)?,
+        &mut lines,
+    );
+    for (name, data) in &old.files {
+        match new.files.get(name) {
+            None => lines.push(format!("- file {name}")),
+            Some(new_data) if new_data != data => lines.push(format!("~ file {name}")),
+            Some(_) => {}
+        }
+    }
+    for name in new.files.keys() {
+        if !old.files.contains_key(name) {
+            lines.push(format!("+ file {name}"));
+        }
+    }
+
+    for line in &lines {
+        println!("{line}");
+    }
+    if lines.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Differences between two JSON values, one line per JSON pointer.
+fn diff_json(path: &str, old: &Json, new: &Json, lines: &mut Vec<String>) {
+    match (old, new) {
+        (Json::Object(old), Json::Object(new)) => {
+            for (key, old_value) in old {
+                let path = format!("{path}/{}", escape(key));
+                match new.get(key) {
+                    Some(new_value) => diff_json(&path, old_value, new_value, lines),
+                    None => lines.push(format!("- {path}: {old_value}")),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    lines.push(format!("+ {path}/{}: {new_value}", escape(key)));
+                }
+            }
+        }
+        (Json::Array(old), Json::Array(new)) => {
+            for (i, old_value) in old.iter().enumerate() {
+                match new.get(i) {
+                    Some(new_value) => {
+                        diff_json(&format!("{path}/{i}"), old_value, new_value, lines)
+                    }
+                    None => lines.push(format!("- {path}/{i}: {old_value}")),
+                }
+            }
+            for (i, new_value) in new.iter().enumerate().skip(old.len()) {
+                lines.push(format!("+ {path}/{i}: {new_value}"));
+            }
+        }
+        (old, new) if old != new => lines.push(format!("~ {path}: {old} -> {new}")),
+        _ => {}
+    }
+}
+
+/// Escape a JSON pointer reference token.
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn load(path: &Path) -> Result<Input, String> {
+    let context = |e: &dyn std::fmt::Display| format!("{}: {e}", path.display());
+    let bytes = if path == Path::new("-") {
+        let mut bytes = vec![];
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| context(&e))?;
+        bytes
+    } else {
+        fs::read(path).map_err(|e| context(&e))?
+    };
+
+    // Zip archives start with a local file header
+    if bytes.starts_with(b"PK\x03\x04") {
+        let archive = Sb3Archive::from_bytes(&bytes).map_err(|e| context(&e))?;
+        Ok(Input {
+            archive,
+            is_archive: true,
+        })
+    } else {
+        let project: Project = serde_json::from_slice(&bytes)
+            .map_err(|e| context(&format!("{PROJECT_JSON} is not valid: {e}")))?;
+        Ok(Input {
+            archive: Sb3Archive::new(project),
+            is_archive: false,
+        })
+    }
+}
+
+fn write_output(output: Option<&Path>, data: &[u8]) -> Result<(), String> {
+    match output {
+        Some(path) => fs::write(path, data).map_err(|e| format!("{}: {e}", path.display())),
+        None => io::stdout().write_all(data).map_err(|e| e.to_string()),
+    }
+}
+
+fn asset_kind(kind: AssetKind) -> &'static str {
+    match kind {
+        AssetKind::Costume => "costume",
+        AssetKind::Sound => "sound",
+    }
+}
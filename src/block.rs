@@ -1,5 +1,6 @@
 //! Module to deal with Scratch block
 
+use crate::opcode::KnownOpcode;
 use crate::prelude::*;
 use serde_tuple::{Deserialize_tuple, Serialize_tuple};
 use utils::{deserialize_json_str, serialize_json_str};
@@ -48,6 +49,10 @@ pub struct BlockNormal {
     /// Y Position of the top level block.
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub y: Option<Number>,
+
+    /// Unknown keys, see [`StringHashMap`]
+    #[serde(flatten)]
+    pub extra: StringHashMap<Json>,
 }
 
 /// This is a reporter of list or variable when it's at the top.
@@ -71,6 +76,7 @@ pub enum ListOrVariable {
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[allow(clippy::large_enum_variant)]
 #[serde(untagged)]
 pub enum Block {
     Normal(BlockNormal),
@@ -296,6 +302,8 @@ pub enum BlockInputValue {
         name: Name,
         /// Id of the variable
         id: Uid,
+        /// X and Y position of the reporter, present when it's at the top level.
+        position: Option<(Number, Number)>,
     },
 
     /// List input
@@ -304,9 +312,18 @@ pub enum BlockInputValue {
         name: Name,
         /// Id of the list
         id: Uid,
+        /// X and Y position of the reporter, present when it's at the top level.
+        position: Option<(Number, Number)>,
     },
 }
 
+impl BlockNormal {
+    /// The opcode in the catalog of known opcodes. See [`KnownOpcode`]
+    pub fn known_opcode(&self) -> KnownOpcode {
+        KnownOpcode::from(&self.opcode)
+    }
+}
+
 impl Default for BlockNormal {
     /// This create new block that act like it's a top most block
     fn default() -> Self {
@@ -322,10 +339,175 @@ impl Default for BlockNormal {
             mutation: None,
             x: Some(0.into()),
             y: Some(0.into()),
+            extra: StringHashMap::default(),
+        }
+    }
+}
+
+/// Kind of data a block can refer to by Id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataKind {
+    /// See [`crate::variable::Variable`]
+    Variable,
+
+    /// See [`crate::list::List`]
+    List,
+
+    /// See [`crate::broadcast::Broadcast`]
+    Broadcast,
+}
+
+impl DataKind {
+    /// The kind of data a field refers to, by the name of the field.
+    pub fn from_field_name(field_name: &str) -> Option<DataKind> {
+        match field_name {
+            "VARIABLE" => Some(DataKind::Variable),
+            "LIST" => Some(DataKind::List),
+            "BROADCAST_OPTION" => Some(DataKind::Broadcast),
+            _ => None,
+        }
+    }
+
+    /// Name of the field that refers to this kind of data. See [`DataKind::from_field_name`]
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            DataKind::Variable => "VARIABLE",
+            DataKind::List => "LIST",
+            DataKind::Broadcast => "BROADCAST_OPTION",
         }
     }
 }
 
+impl Block {
+    /// Every variable, list and broadcast this block refers to, as kind, name and Id.
+    /// Found in [`BlockField::WithId`], [`BlockInputValue`] and [`BlockVarListReporterTop`].
+    pub fn data_references(&self) -> Vec<(DataKind, Name, &Uid)> {
+        self.data_reference_paths()
+            .into_iter()
+            .map(|(_, kind, name, id)| (kind, name, id))
+            .collect()
+    }
+
+    /// See [`Block::data_references`], with the JSON pointer to the reference inside the block,
+    /// such as `/fields/VARIABLE` or `/inputs/MESSAGE`.
+    pub(crate) fn data_reference_paths(&self) -> Vec<(String, DataKind, Name, &Uid)> {
+        let mut refs = vec![];
+        match self {
+            Block::Normal(b) => {
+                for (field_name, field) in &b.fields.0 {
+                    if let (
+                        Some(kind),
+                        BlockField::WithId {
+                            value,
+                            id: Some(id),
+                        },
+                    ) = (DataKind::from_field_name(field_name), field)
+                    {
+                        let path = format!("/fields/{}", utils::escape_pointer(field_name));
+                        refs.push((path, kind, value_to_name(value), id));
+                    }
+                }
+                for (input_name, input) in &b.inputs.0 {
+                    for value in input.inputs.iter().flatten() {
+                        if let UidOrValue::Value(v) = value {
+                            if let Some((kind, name, id)) = v.data_reference() {
+                                let path = format!("/inputs/{}", utils::escape_pointer(input_name));
+                                refs.push((path, kind, name.clone(), id));
+                            }
+                        }
+                    }
+                }
+            }
+            // `[12, name, id, x, y]`
+            Block::VarList(b) => {
+                refs.push(("/2".to_owned(), b.kind.data_kind(), b.name.clone(), &b.id))
+            }
+        }
+        refs
+    }
+
+    /// Call `f` with the kind, name and Id of every variable, list and broadcast this block refers to.
+    /// See [`Block::data_references`]
+    pub fn for_each_data_reference_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(DataKind, &mut Name, &mut Uid),
+    {
+        match self {
+            Block::Normal(b) => {
+                for (field_name, field) in b.fields.0.iter_mut() {
+                    if let (
+                        Some(kind),
+                        BlockField::WithId {
+                            value,
+                            id: Some(id),
+                        },
+                    ) = (DataKind::from_field_name(field_name), field)
+                    {
+                        let old_name = value_to_name(value);
+                        let mut name = old_name.clone();
+                        f(kind, &mut name, id);
+                        // Numeric names stay numbers unless renamed
+                        if name != old_name {
+                            *value = Value::Text(name);
+                        }
+                    }
+                }
+                for input in b.inputs.0.values_mut() {
+                    for value in input.inputs.iter_mut().flatten() {
+                        if let UidOrValue::Value(v) = value {
+                            if let Some((kind, name, id)) = v.data_reference_mut() {
+                                f(kind, name, id);
+                            }
+                        }
+                    }
+                }
+            }
+            Block::VarList(b) => f(b.kind.data_kind(), &mut b.name, &mut b.id),
+        }
+    }
+}
+
+impl ListOrVariable {
+    /// See [`DataKind`]
+    pub fn data_kind(&self) -> DataKind {
+        match self {
+            ListOrVariable::Variable => DataKind::Variable,
+            ListOrVariable::List => DataKind::List,
+        }
+    }
+}
+
+impl BlockInputValue {
+    /// Kind, name and Id of the variable, list or broadcast this input refers to.
+    pub fn data_reference(&self) -> Option<(DataKind, &Name, &Uid)> {
+        match self {
+            BlockInputValue::Broadcast { name, id } => Some((DataKind::Broadcast, name, id)),
+            BlockInputValue::Variable { name, id, .. } => Some((DataKind::Variable, name, id)),
+            BlockInputValue::List { name, id, .. } => Some((DataKind::List, name, id)),
+            _ => None,
+        }
+    }
+
+    /// See [`BlockInputValue::data_reference`]
+    pub fn data_reference_mut(&mut self) -> Option<(DataKind, &mut Name, &mut Uid)> {
+        match self {
+            BlockInputValue::Broadcast { name, id } => Some((DataKind::Broadcast, name, id)),
+            BlockInputValue::Variable { name, id, .. } => Some((DataKind::Variable, name, id)),
+            BlockInputValue::List { name, id, .. } => Some((DataKind::List, name, id)),
+            _ => None,
+        }
+    }
+}
+
+fn value_to_name(value: &Value) -> Name {
+    match value {
+        Value::Text(t) => t.clone(),
+        Value::Number(Number::Int(n)) => n.to_string(),
+        Value::Number(Number::Float(n)) => n.to_string(),
+    }
+}
+
 // Serde impl ==================================================================
 
 macro_rules! list_or_variable_vistor_types {
@@ -463,8 +645,8 @@ impl BlockInputValue {
             Color { value: _ } => 9,
             String { value: _ } => 10,
             Broadcast { name: _, id: _ } => 11,
-            Variable { name: _, id: _ } => 12,
-            List { name: _, id: _ } => 13,
+            Variable { .. } => 12,
+            List { .. } => 13,
         }
     }
 
@@ -480,8 +662,10 @@ impl BlockInputValue {
             Color { value: _ } => 1,
             String { value: _ } => 1,
             Broadcast { name: _, id: _ } => 2,
-            Variable { name: _, id: _ } => 2,
-            List { name: _, id: _ } => 2,
+            Variable { position, .. } | List { position, .. } => match position {
+                Some(_) => 4,
+                None => 2,
+            },
         }
     }
 }
@@ -518,6 +702,17 @@ impl<'de> Visitor<'de> for BlockInputValueVisitor {
                 .ok_or_else(|| A::Error::invalid_length(len, &error))
         }
 
+        fn seq_next_position<'de, A>(seq: &mut A) -> Result<Option<(Number, Number)>, A::Error>
+        where
+            A: serde::de::SeqAccess<'de>,
+        {
+            let Some(x) = seq.next_element::<Number>()? else {
+                return Ok(None);
+            };
+            let y = seq_next_element_error(seq, 4, "Expecting both x and y of the reporter")?;
+            Ok(Some((x, y)))
+        }
+
         let vtype: u8 = seq_next_element_error(
             &mut seq,
             0,
@@ -562,8 +757,7 @@ impl<'de> Visitor<'de> for BlockInputValueVisitor {
                     &mut seq, 3,
                     "Expecting 3 or 5 or more elements for block input value with Id 12 - 13 inclusive"
                 )?;
-                let _x = seq.next_element::<Number>()?;
-                let _y = seq.next_element::<Number>()?;
+                let position = seq_next_position(&mut seq)?;
                 let name = match value {
                     Value::Text(s) => s,
                     Value::Number(_) => {
@@ -573,15 +767,14 @@ impl<'de> Visitor<'de> for BlockInputValueVisitor {
                         ))
                     }
                 };
-                Variable { name, id }
+                Variable { name, id, position }
             }
             13 => {
                 let id = seq_next_element_error(
                     &mut seq, 3,
                     "Expecting 3 or 5 or more elements for block input value with Id 12 - 13 inclusive"
                 )?;
-                let _x = seq.next_element::<Number>()?;
-                let _y = seq.next_element::<Number>()?;
+                let position = seq_next_position(&mut seq)?;
                 let name = match value {
                     Value::Text(s) => s,
                     Value::Number(_) => {
@@ -591,7 +784,7 @@ impl<'de> Visitor<'de> for BlockInputValueVisitor {
                         ))
                     }
                 };
-                List { name, id }
+                List { name, id, position }
             }
             v => {
                 return Err(A::Error::invalid_value(
@@ -638,13 +831,13 @@ impl Serialize for BlockInputValue {
                 s.serialize_element(name)?;
                 s.serialize_element(id)?;
             }
-            Variable { name, id } => {
-                s.serialize_element(name)?;
-                s.serialize_element(id)?;
-            },
-            List { name, id } => {
+            Variable { name, id, position } | List { name, id, position } => {
                 s.serialize_element(name)?;
                 s.serialize_element(id)?;
+                if let Some((x, y)) = position {
+                    s.serialize_element(x)?;
+                    s.serialize_element(y)?;
+                }
             }
         }
         s.end()
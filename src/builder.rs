@@ -0,0 +1,1534 @@
+//! Module to build projects from Rust
+//!
+//! [`ProjectBuilder`] builds a [`Project`] out of sprites and scripts.
+//! Blocks are made with the functions in [`motion`], [`looks`], [`sound`], [`events`],
+//! [`control`], [`sensing`], [`operator`] and [`data`], or with [`BlockBuilder`] for anything else.
+//! Ids, `next`, `parent`, shadows and the variables, lists and broadcasts the scripts refer to
+//! are all taken care of when the project is built.
+//!
+//! ```
+//! use sb_sbity::builder::{control, motion, ProjectBuilder};
+//!
+//! let mut project = ProjectBuilder::new();
+//! project
+//!     .sprite("Cat")
+//!     .when_flag_clicked()
+//!     .then(motion::goto_xy(0, 0))
+//!     .then(control::forever(|s| s.then(motion::move_steps(10))));
+//! let project = project.build();
+//! assert!(project.validate().is_empty());
+//! ```
+
+use crate::asset::{Asset, Costume, Sound};
+use crate::block::{
+    Block, BlockField, BlockInput, BlockInputValue, BlockMutation, BlockNormal, DataKind,
+    ShadowInputType, UidOrValue,
+};
+use crate::broadcast::Broadcast;
+use crate::list::List;
+use crate::prelude::*;
+use crate::project::{Meta, Project};
+use crate::target::{RotationStyle, Sprite, SpriteOrStage, Stage, Target, VideoState};
+use crate::uid;
+use crate::variable::Variable;
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+
+/// Builds a [`Project`]. Has a stage and any number of sprites.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectBuilder {
+    stage: TargetBuilder,
+    sprites: Vec<SpriteBuilder>,
+}
+
+/// Builds a [`Target`], the stage or the part of a sprite that both have.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetBuilder {
+    name: Name,
+    variables: Vec<Variable>,
+    lists: Vec<List>,
+    costumes: Vec<Costume>,
+    sounds: Vec<Sound>,
+    scripts: Vec<ScriptEntry>,
+}
+
+/// Builds a [`Sprite`]. Dereferences to [`TargetBuilder`] for scripts, data and assets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteBuilder {
+    target: TargetBuilder,
+    visible: bool,
+    x: Number,
+    y: Number,
+    size: Number,
+    direction: Number,
+    draggable: bool,
+    rotation_style: RotationStyle,
+}
+
+/// Adds blocks to a script of a target. See [`TargetBuilder::script`]
+#[derive(Debug)]
+pub struct ScriptBuilder<'a> {
+    script: &'a mut ScriptEntry,
+}
+
+/// A stack of blocks, such as what goes in a C mouth.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StackBuilder {
+    blocks: Vec<BlockBuilder>,
+}
+
+/// Builds a [`BlockNormal`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockBuilder {
+    pub(crate) opcode: OpCode,
+    inputs: StringHashMap<InputBuilder>,
+    fields: StringHashMap<FieldBuilder>,
+    mutation: Option<BlockMutation>,
+    shadow: bool,
+}
+
+/// What is put in an input of a block.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Arg {
+    /// A value typed in the input
+    Value(Value),
+
+    /// A reporter dropped in the input
+    Block(BlockBuilder),
+
+    /// A variable reporter dropped in the input, by name
+    Variable(Name),
+
+    /// A list reporter dropped in the input, by name
+    List(Name),
+}
+
+/// Kind of value a typed in input takes. See [`BlockInputValue`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiteralKind {
+    /// [`BlockInputValue::Number`], such as the steps of `move (10) steps`
+    Number,
+
+    /// [`BlockInputValue::PositiveNumber`], such as the seconds of `wait (1) seconds`
+    PositiveNumber,
+
+    /// [`BlockInputValue::PositiveInteger`], such as the letter of `letter (1) of [apple]`
+    PositiveInteger,
+
+    /// [`BlockInputValue::Integer`], such as the index of `item (1) of [list v]`
+    Integer,
+
+    /// [`BlockInputValue::Angle`], such as the direction of `point in direction (90)`
+    Angle,
+
+    /// [`BlockInputValue::Color`], such as the color of `<touching color [#ff0000]?>`
+    Color,
+
+    /// [`BlockInputValue::String`], such as the message of `say [Hello!]`
+    String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ScriptEntry {
+    x: Number,
+    y: Number,
+    stack: StackBuilder,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct InputBuilder {
+    top: Option<Content>,
+    shadow: Option<Content>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Content {
+    Literal(BlockInputValue),
+    Data(DataKind, Name),
+    Stack(StackBuilder),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldBuilder {
+    Value(Value),
+    Data(DataKind, Name),
+}
+
+impl ProjectBuilder {
+    /// Project with only the stage.
+    pub fn new() -> ProjectBuilder {
+        ProjectBuilder {
+            stage: TargetBuilder::new("Stage"),
+            sprites: vec![],
+        }
+    }
+
+    /// The stage.
+    pub fn stage(&mut self) -> &mut TargetBuilder {
+        &mut self.stage
+    }
+
+    /// The sprite with the name, added on top of the other sprites if there is none yet.
+    pub fn sprite(&mut self, name: &str) -> &mut SpriteBuilder {
+        let i = match self.sprites.iter().position(|s| s.name == name) {
+            Some(i) => i,
+            None => {
+                self.sprites.push(SpriteBuilder::new(name));
+                self.sprites.len() - 1
+            }
+        };
+        &mut self.sprites[i]
+    }
+
+    /// Build the project.
+    ///
+    /// Variables and lists a script refers to by name are looked up in its sprite first, then in the stage.
+    /// Those that are declared nowhere are declared in the stage, like broadcasts.
+    /// Targets without costumes get an empty SVG one, see [`EMPTY_SVG`].
+    /// Extensions of the blocks used are added to [`Project::extensions`].
+    pub fn build(self) -> Project {
+        let mut used = HashSet::new();
+        let (mut stage, stage_scripts) = self.stage.declare(&mut used);
+        let mut sprites = vec![];
+        for (i, sprite) in self.sprites.into_iter().enumerate() {
+            let (mut target, scripts) = sprite.target.declare(&mut used);
+            target.layer_order = (i + 1) as Int;
+            if target.costumes.is_empty() {
+                target.costumes.push(empty_costume("costume1", 0, 0));
+            }
+            target.blocks = Scope {
+                stage: &mut stage,
+                local: Some(&target),
+                used: &mut used,
+            }
+            .add_scripts(scripts);
+            sprites.push(Sprite {
+                target,
+                visible: sprite.visible,
+                x: sprite.x,
+                y: sprite.y,
+                size: sprite.size,
+                direction: sprite.direction,
+                draggable: sprite.draggable,
+                rotation_style: sprite.rotation_style,
+                is_stage: false,
+            });
+        }
+        stage.blocks = Scope {
+            stage: &mut stage,
+            local: None,
+            used: &mut used,
+        }
+        .add_scripts(stage_scripts);
+        if stage.costumes.is_empty() {
+            stage.costumes.push(empty_costume("backdrop1", 240, 180));
+        }
+
+        let mut targets = vec![SpriteOrStage::Stage(Stage {
+            target: stage,
+            tempo: 60.into(),
+            video_state: VideoState::On,
+            video_transparency: 50.into(),
+            text_to_speech_language: None,
+            is_stage: true,
+        })];
+        targets.extend(sprites.into_iter().map(SpriteOrStage::Sprite));
+
+        let mut project = Project {
+            meta: Meta {
+                semver: "3.0.0".to_owned(),
+                vm: "0.2.0".to_owned(),
+                agent: String::new(),
+                extra: StringHashMap::default(),
+            },
+            extensions: vec![],
+            monitors: vec![],
+            targets,
+            extra: StringHashMap::default(),
+        };
+        project.add_missing_extensions();
+        project
+    }
+}
+
+impl Default for ProjectBuilder {
+    fn default() -> Self {
+        ProjectBuilder::new()
+    }
+}
+
+impl TargetBuilder {
+    fn new(name: &str) -> TargetBuilder {
+        TargetBuilder {
+            name: name.to_owned(),
+            variables: vec![],
+            lists: vec![],
+            costumes: vec![],
+            sounds: vec![],
+            scripts: vec![],
+        }
+    }
+
+    /// Name of the target.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Declare a variable in this target.
+    pub fn variable(&mut self, name: &str, value: impl Into<Value>) -> &mut Self {
+        self.variables.push(Variable {
+            name: name.to_owned(),
+            value: value.into().into(),
+            is_cloud_variable: false,
+        });
+        self
+    }
+
+    /// Declare a list in this target.
+    pub fn list<V>(&mut self, name: &str, values: impl IntoIterator<Item = V>) -> &mut Self
+    where
+        V: Into<Value>,
+    {
+        self.lists.push(List {
+            name: name.to_owned(),
+            values: values.into_iter().map(|v| v.into().into()).collect(),
+        });
+        self
+    }
+
+    /// Add a costume, a backdrop for the stage.
+    pub fn costume(&mut self, costume: Costume) -> &mut Self {
+        self.costumes.push(costume);
+        self
+    }
+
+    /// Add a sound.
+    pub fn sound(&mut self, sound: Sound) -> &mut Self {
+        self.sounds.push(sound);
+        self
+    }
+
+    /// Start a new script with the block, usually a hat.
+    /// Scripts are laid out in a column, see [`ScriptBuilder::at`] to place it elsewhere.
+    pub fn script(&mut self, top: BlockBuilder) -> ScriptBuilder<'_> {
+        let y = 200 * self.scripts.len() as Int;
+        self.scripts.push(ScriptEntry {
+            x: 0.into(),
+            y: y.into(),
+            stack: StackBuilder::new().then(top),
+        });
+        ScriptBuilder {
+            script: self.scripts.last_mut().unwrap(),
+        }
+    }
+
+    /// Start a new script with [`events::when_flag_clicked`]
+    pub fn when_flag_clicked(&mut self) -> ScriptBuilder<'_> {
+        self.script(events::when_flag_clicked())
+    }
+
+    /// Start a new script with [`events::when_key_pressed`]
+    pub fn when_key_pressed(&mut self, key: &str) -> ScriptBuilder<'_> {
+        self.script(events::when_key_pressed(key))
+    }
+
+    /// Start a new script with [`events::when_broadcast_received`]
+    pub fn when_broadcast_received(&mut self, broadcast: &str) -> ScriptBuilder<'_> {
+        self.script(events::when_broadcast_received(broadcast))
+    }
+
+    /// Start a new script with [`events::when_this_sprite_clicked`]
+    pub fn when_this_sprite_clicked(&mut self) -> ScriptBuilder<'_> {
+        self.script(events::when_this_sprite_clicked())
+    }
+
+    /// Start a new script with [`control::start_as_clone`]
+    pub fn when_i_start_as_a_clone(&mut self) -> ScriptBuilder<'_> {
+        self.script(control::start_as_clone())
+    }
+
+    /// The target with its data declared and Ids generated for them, and the scripts left to add.
+    fn declare(self, used: &mut HashSet<Uid>) -> (Target, Vec<ScriptEntry>) {
+        let mut new_id = || {
+            let id = uid::generate_unused(|id| used.contains(id));
+            used.insert(id.clone());
+            id
+        };
+        let target = Target {
+            name: self.name,
+            variables: self.variables.into_iter().map(|v| (new_id(), v)).collect(),
+            lists: self.lists.into_iter().map(|l| (new_id(), l)).collect(),
+            broadcasts: StringHashMap::default(),
+            blocks: StringHashMap::default(),
+            comments: StringHashMap::default(),
+            current_costume: 0,
+            costumes: self.costumes,
+            sounds: self.sounds,
+            layer_order: 0,
+            volume: 100.into(),
+            extra: StringHashMap::default(),
+        };
+        (target, self.scripts)
+    }
+}
+
+impl SpriteBuilder {
+    fn new(name: &str) -> SpriteBuilder {
+        SpriteBuilder {
+            target: TargetBuilder::new(name),
+            visible: true,
+            x: 0.into(),
+            y: 0.into(),
+            size: 100.into(),
+            direction: 90.into(),
+            draggable: false,
+            rotation_style: RotationStyle::AllAround,
+        }
+    }
+
+    /// Position of the sprite on the stage.
+    pub fn position(&mut self, x: impl Into<Number>, y: impl Into<Number>) -> &mut Self {
+        self.x = x.into();
+        self.y = y.into();
+        self
+    }
+
+    /// Size as a percentage.
+    pub fn size(&mut self, size: impl Into<Number>) -> &mut Self {
+        self.size = size.into();
+        self
+    }
+
+    /// Direction in degrees clockwise from up.
+    pub fn direction(&mut self, direction: impl Into<Number>) -> &mut Self {
+        self.direction = direction.into();
+        self
+    }
+
+    /// Whether the sprite is shown.
+    pub fn visible(&mut self, visible: bool) -> &mut Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Whether the sprite can be dragged in the player.
+    pub fn draggable(&mut self, draggable: bool) -> &mut Self {
+        self.draggable = draggable;
+        self
+    }
+
+    /// See [`RotationStyle`]
+    pub fn rotation_style(&mut self, rotation_style: RotationStyle) -> &mut Self {
+        self.rotation_style = rotation_style;
+        self
+    }
+}
+
+impl Deref for SpriteBuilder {
+    type Target = TargetBuilder;
+
+    fn deref(&self) -> &Self::Target {
+        &self.target
+    }
+}
+
+impl DerefMut for SpriteBuilder {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.target
+    }
+}
+
+impl ScriptBuilder<'_> {
+    /// Add the block at the bottom of the script.
+    pub fn then(self, block: BlockBuilder) -> Self {
+        self.script.stack.blocks.push(block);
+        self
+    }
+
+    /// Place the script at the position in the code area.
+    pub fn at(self, x: impl Into<Number>, y: impl Into<Number>) -> Self {
+        self.script.x = x.into();
+        self.script.y = y.into();
+        self
+    }
+}
+
+impl StackBuilder {
+    /// An empty stack.
+    pub fn new() -> StackBuilder {
+        StackBuilder::default()
+    }
+
+    /// Add the block at the bottom of the stack.
+    pub fn then(mut self, block: BlockBuilder) -> Self {
+        self.blocks.push(block);
+        self
+    }
+}
+
+impl From<BlockBuilder> for StackBuilder {
+    fn from(block: BlockBuilder) -> Self {
+        StackBuilder::new().then(block)
+    }
+}
+
+impl BlockBuilder {
+    /// Block with the opcode and nothing in it.
+    pub fn new(opcode: impl Into<OpCode>) -> BlockBuilder {
+        BlockBuilder {
+            opcode: opcode.into(),
+            inputs: StringHashMap::default(),
+            fields: StringHashMap::default(),
+            mutation: None,
+            shadow: false,
+        }
+    }
+
+    /// Input that takes a typed in value of the kind, or a reporter.
+    /// When a reporter is put in, the typed in value is left empty under it.
+    pub fn input(mut self, name: &str, kind: LiteralKind, arg: impl Into<Arg>) -> Self {
+        let (top, shadow) = match arg.into() {
+            Arg::Value(v) => (None, kind.value(v)),
+            arg => (arg.into_content(), kind.value(kind.empty())),
+        };
+        self.inputs.0.insert(
+            name.to_owned(),
+            InputBuilder {
+                top,
+                shadow: Some(Content::Literal(shadow)),
+            },
+        );
+        self
+    }
+
+    /// Input that takes an option of a menu, or a reporter.
+    /// The menu is a shadow block with the opcode that has the option in the field.
+    /// When a reporter is put in, the menu is left empty under it.
+    pub fn menu(mut self, name: &str, opcode: &str, field: &str, arg: impl Into<Arg>) -> Self {
+        let (top, option) = match arg.into() {
+            Arg::Value(v) => (None, v),
+            arg => (arg.into_content(), Value::Text(String::new())),
+        };
+        let mut menu = BlockBuilder::new(opcode).field(field, option);
+        menu.shadow = true;
+        self.inputs.0.insert(
+            name.to_owned(),
+            InputBuilder {
+                top,
+                shadow: Some(Content::Stack(menu.into())),
+            },
+        );
+        self
+    }
+
+    /// Input that takes a broadcast by name, or a reporter.
+    /// When a reporter is put in, the broadcast `message1` is left under it.
+    pub fn broadcast_input(mut self, name: &str, arg: impl Into<Arg>) -> Self {
+        let (top, broadcast) = match arg.into() {
+            Arg::Value(v) => (None, value_to_text(v)),
+            arg => (arg.into_content(), "message1".to_owned()),
+        };
+        self.inputs.0.insert(
+            name.to_owned(),
+            InputBuilder {
+                top,
+                shadow: Some(Content::Data(DataKind::Broadcast, broadcast)),
+            },
+        );
+        self
+    }
+
+    /// Input that only takes a reporter, such as the condition of an if.
+    pub fn boolean_input(mut self, name: &str, block: BlockBuilder) -> Self {
+        self.inputs.0.insert(
+            name.to_owned(),
+            InputBuilder {
+                top: Some(Content::Stack(block.into())),
+                shadow: None,
+            },
+        );
+        self
+    }
+
+    /// Input that has the block as its shadow, such as the prototype in a custom block definition.
+    pub fn shadow_input(mut self, name: &str, mut block: BlockBuilder) -> Self {
+        block.shadow = true;
+        self.inputs.0.insert(
+            name.to_owned(),
+            InputBuilder {
+                top: None,
+                shadow: Some(Content::Stack(block.into())),
+            },
+        );
+        self
+    }
+
+    /// C mouth with the stack in it.
+    pub fn substack(mut self, name: &str, stack: StackBuilder) -> Self {
+        self.inputs.0.insert(
+            name.to_owned(),
+            InputBuilder {
+                top: Some(Content::Stack(stack)),
+                shadow: None,
+            },
+        );
+        self
+    }
+
+    /// Field with the value, such as the option of a dropdown.
+    pub fn field(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.fields
+            .0
+            .insert(name.to_owned(), FieldBuilder::Value(value.into()));
+        self
+    }
+
+    /// Field that refers to a variable, list or broadcast by name.
+    /// The name of the field is [`DataKind::field_name`]
+    pub fn data_field(mut self, kind: DataKind, name: &str) -> Self {
+        self.fields.0.insert(
+            kind.field_name().to_owned(),
+            FieldBuilder::Data(kind, name.to_owned()),
+        );
+        self
+    }
+
+    /// See [`BlockMutation`]
+    pub fn mutation(mut self, mutation: BlockMutation) -> Self {
+        self.mutation = Some(mutation);
+        self
+    }
+}
+
+impl Arg {
+    fn into_content(self) -> Option<Content> {
+        match self {
+            Arg::Value(v) => Some(Content::Literal(BlockInputValue::String { value: v })),
+            Arg::Block(b) => Some(Content::Stack(b.into())),
+            Arg::Variable(name) => Some(Content::Data(DataKind::Variable, name)),
+            Arg::List(name) => Some(Content::Data(DataKind::List, name)),
+        }
+    }
+}
+
+macro_rules! arg_from_value {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Arg {
+                fn from(v: $ty) -> Self {
+                    Arg::Value(v.into())
+                }
+            }
+        )*
+    }
+}
+
+arg_from_value! {
+    i32 Int Float &str Text Number Value
+}
+
+impl From<BlockBuilder> for Arg {
+    fn from(b: BlockBuilder) -> Self {
+        Arg::Block(b)
+    }
+}
+
+impl LiteralKind {
+    /// [`BlockInputValue`] of this kind with the value.
+    pub fn value(&self, value: Value) -> BlockInputValue {
+        match self {
+            LiteralKind::Number => BlockInputValue::Number { value },
+            LiteralKind::PositiveNumber => BlockInputValue::PositiveNumber { value },
+            LiteralKind::PositiveInteger => BlockInputValue::PositiveInteger { value },
+            LiteralKind::Integer => BlockInputValue::Integer { value },
+            LiteralKind::Angle => BlockInputValue::Angle { value },
+            LiteralKind::Color => BlockInputValue::Color { value },
+            LiteralKind::String => BlockInputValue::String { value },
+        }
+    }
+
+    fn empty(&self) -> Value {
+        match self {
+            LiteralKind::Color => Value::Text("#000000".to_owned()),
+            _ => Value::Text(String::new()),
+        }
+    }
+}
+
+/// Turns the builders of a target into blocks.
+struct Scope<'a> {
+    stage: &'a mut Target,
+    /// The sprite, [`None`] when adding the scripts of the stage.
+    local: Option<&'a Target>,
+    used: &'a mut HashSet<Uid>,
+}
+
+impl Scope<'_> {
+    fn add_scripts(&mut self, scripts: Vec<ScriptEntry>) -> StringHashMap<Block> {
+        let mut blocks = StringHashMap::default();
+        for script in scripts {
+            self.add_stack(&mut blocks, script.stack, None, Some((script.x, script.y)));
+        }
+        blocks
+    }
+
+    /// Add the stack, returns the Id of its first block.
+    fn add_stack(
+        &mut self,
+        blocks: &mut StringHashMap<Block>,
+        stack: StackBuilder,
+        parent: Option<&Uid>,
+        position: Option<(Number, Number)>,
+    ) -> Option<Uid> {
+        let ids: Vec<Uid> = stack.blocks.iter().map(|_| self.new_id()).collect();
+        let mut parent = parent.cloned();
+        for (i, block) in stack.blocks.into_iter().enumerate() {
+            let id = ids[i].clone();
+            let position = if i == 0 { position } else { None };
+            self.add_block(
+                blocks,
+                block,
+                &id,
+                parent,
+                ids.get(i + 1).cloned(),
+                position,
+            );
+            parent = Some(id);
+        }
+        ids.into_iter().next()
+    }
+
+    fn add_block(
+        &mut self,
+        blocks: &mut StringHashMap<Block>,
+        block: BlockBuilder,
+        id: &Uid,
+        parent: Option<Uid>,
+        next: Option<Uid>,
+        position: Option<(Number, Number)>,
+    ) {
+        // Reserve the place of the block so it comes before the blocks in its inputs, like in Scratch.
+        blocks
+            .0
+            .insert(id.clone(), Block::Normal(BlockNormal::default()));
+
+        let mut fields = StringHashMap::default();
+        for (name, field) in block.fields {
+            let field = match field {
+                FieldBuilder::Value(value) => BlockField::WithId { value, id: None },
+                FieldBuilder::Data(kind, data_name) => BlockField::WithId {
+                    id: Some(self.resolve(kind, &data_name)),
+                    value: Value::Text(data_name),
+                },
+            };
+            fields.0.insert(name, field);
+        }
+
+        let mut inputs = StringHashMap::default();
+        for (name, input) in block.inputs {
+            let top = input.top.and_then(|c| self.add_content(blocks, c, id));
+            let shadow = input.shadow.and_then(|c| self.add_content(blocks, c, id));
+            let input = match (top, shadow) {
+                (Some(top), Some(shadow)) => BlockInput {
+                    shadow: ShadowInputType::ShadowObscured,
+                    inputs: vec![Some(top), Some(shadow)],
+                },
+                (Some(top), None) => BlockInput {
+                    shadow: ShadowInputType::NoShadow,
+                    inputs: vec![Some(top)],
+                },
+                (None, Some(shadow)) => BlockInput {
+                    shadow: ShadowInputType::Shadow,
+                    inputs: vec![Some(shadow)],
+                },
+                (None, None) => continue,
+            };
+            inputs.0.insert(name, input);
+        }
+
+        let (x, y) = match position {
+            Some((x, y)) => (Some(x), Some(y)),
+            None => (None, None),
+        };
+        let block = BlockNormal {
+            opcode: block.opcode,
+            comment: None,
+            next,
+            top_level: parent.is_none(),
+            parent,
+            inputs,
+            fields,
+            shadow: block.shadow,
+            mutation: block.mutation,
+            x,
+            y,
+            extra: StringHashMap::default(),
+        };
+        blocks.0.insert(id.clone(), Block::Normal(block));
+    }
+
+    fn add_content(
+        &mut self,
+        blocks: &mut StringHashMap<Block>,
+        content: Content,
+        parent: &Uid,
+    ) -> Option<UidOrValue> {
+        match content {
+            Content::Literal(v) => Some(UidOrValue::Value(v)),
+            Content::Data(kind, name) => {
+                let id = self.resolve(kind, &name);
+                Some(UidOrValue::Value(match kind {
+                    DataKind::Variable => BlockInputValue::Variable {
+                        name,
+                        id,
+                        position: None,
+                    },
+                    DataKind::List => BlockInputValue::List {
+                        name,
+                        id,
+                        position: None,
+                    },
+                    DataKind::Broadcast => BlockInputValue::Broadcast { name, id },
+                }))
+            }
+            Content::Stack(stack) => self
+                .add_stack(blocks, stack, Some(parent), None)
+                .map(UidOrValue::Uid),
+        }
+    }
+
+    /// Id of the variable, list or broadcast with the name, declared in the stage if it's nowhere.
+    fn resolve(&mut self, kind: DataKind, name: &str) -> Uid {
+        let find = |target: &Target| match kind {
+            DataKind::Variable => target
+                .variables
+                .0
+                .iter()
+                .find(|(_, v)| v.name == name)
+                .map(|(id, _)| id.clone()),
+            DataKind::List => target
+                .lists
+                .0
+                .iter()
+                .find(|(_, l)| l.name == name)
+                .map(|(id, _)| id.clone()),
+            DataKind::Broadcast => target
+                .broadcasts
+                .0
+                .iter()
+                .find(|(_, b)| b.name == name)
+                .map(|(id, _)| id.clone()),
+        };
+        if let Some(id) = self.local.and_then(find).or_else(|| find(self.stage)) {
+            return id;
+        }
+
+        let id = self.new_id();
+        let name = name.to_owned();
+        match kind {
+            DataKind::Variable => {
+                self.stage.variables.0.insert(
+                    id.clone(),
+                    Variable {
+                        name,
+                        ..Default::default()
+                    },
+                );
+            }
+            DataKind::List => {
+                self.stage.lists.0.insert(
+                    id.clone(),
+                    List {
+                        name,
+                        ..Default::default()
+                    },
+                );
+            }
+            DataKind::Broadcast => {
+                self.stage
+                    .broadcasts
+                    .0
+                    .insert(id.clone(), Broadcast { name });
+            }
+        }
+        id
+    }
+
+    fn new_id(&mut self) -> Uid {
+        let id = uid::generate_unused(|id| self.used.contains(id));
+        self.used.insert(id.clone());
+        id
+    }
+}
+
+/// Add the stacks to the blocks of the target at the index, laid out in a column below its scripts.
+/// Variables and lists are looked up in the target then in the stage, like [`ProjectBuilder::build`] does.
+pub(crate) fn add_stacks(project: &mut Project, target: usize, stacks: Vec<StackBuilder>) {
+    let mut used: HashSet<Uid> = (project.targets.iter())
+        .flat_map(|t| {
+            let t = t.target();
+            (t.blocks.0.keys())
+                .chain(t.variables.0.keys())
+                .chain(t.lists.0.keys())
+                .chain(t.broadcasts.0.keys())
+        })
+        .cloned()
+        .collect();
+    let top_level = project.targets[target]
+        .target()
+        .blocks
+        .0
+        .values()
+        .filter(|b| matches!(b, Block::Normal(b) if b.top_level))
+        .count();
+    let scripts = stacks
+        .into_iter()
+        .enumerate()
+        .map(|(i, stack)| ScriptEntry {
+            x: 0.into(),
+            y: (200 * (top_level + i) as Int).into(),
+            stack,
+        })
+        .collect();
+    // Without a stage the target declares what it refers to itself
+    let (stage, local) = match project.targets.iter().position(|t| t.is_stage()) {
+        Some(stage) if stage != target => (stage, Some(project.targets[target].target().clone())),
+        _ => (target, None),
+    };
+    let blocks = Scope {
+        stage: project.targets[stage].target_mut(),
+        local: local.as_ref(),
+        used: &mut used,
+    }
+    .add_scripts(scripts);
+    project.targets[target]
+        .target_mut()
+        .blocks
+        .0
+        .extend(blocks.0);
+}
+
+/// The file of the costume [`ProjectBuilder::build`] gives targets without costumes,
+/// the same empty SVG as the Scratch editor's.
+/// [`Sb3Archive::new`](crate::archive::Sb3Archive::new) adds it when the project uses it.
+pub const EMPTY_SVG: &[u8] =
+    b"<svg version=\"1.1\" width=\"2\" height=\"2\" viewBox=\"-1 -1 2 2\" \
+xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\">
+  <!-- Exported by Scratch - http://scratch.mit.edu/ -->
+</svg>";
+
+pub(crate) fn empty_costume(name: &str, rotation_center_x: Int, rotation_center_y: Int) -> Costume {
+    Costume {
+        rotation_center_x: rotation_center_x.into(),
+        rotation_center_y: rotation_center_y.into(),
+        bitmap_resolution: None,
+        asset: Asset {
+            asset_id: "cd21514d0531fdffb22204e0ec5ed84a".to_owned(),
+            name: name.to_owned(),
+            md5ext: Some("cd21514d0531fdffb22204e0ec5ed84a.svg".to_owned()),
+            data_format: "svg".to_owned(),
+        },
+        extra: StringHashMap::default(),
+    }
+}
+
+fn value_to_text(value: Value) -> Text {
+    match value {
+        Value::Text(t) => t,
+        Value::Number(Number::Int(n)) => n.to_string(),
+        Value::Number(Number::Float(n)) => n.to_string(),
+    }
+}
+
+fn stack_with<F>(f: F) -> StackBuilder
+where
+    F: FnOnce(StackBuilder) -> StackBuilder,
+{
+    f(StackBuilder::new())
+}
+
+// Blocks ======================================================================
+
+/// Motion blocks
+pub mod motion {
+    use super::{Arg, BlockBuilder, LiteralKind};
+
+    /// `move (steps) steps`
+    pub fn move_steps(steps: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_movesteps").input("STEPS", LiteralKind::Number, steps)
+    }
+
+    /// `turn right (degrees) degrees`
+    pub fn turn_right(degrees: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_turnright").input("DEGREES", LiteralKind::Number, degrees)
+    }
+
+    /// `turn left (degrees) degrees`
+    pub fn turn_left(degrees: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_turnleft").input("DEGREES", LiteralKind::Number, degrees)
+    }
+
+    /// `go to (to)`, where `to` is `_random_`, `_mouse_` or the name of a sprite
+    pub fn goto(to: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_goto").menu("TO", "motion_goto_menu", "TO", to)
+    }
+
+    /// `go to x: (x) y: (y)`
+    pub fn goto_xy(x: impl Into<Arg>, y: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_gotoxy")
+            .input("X", LiteralKind::Number, x)
+            .input("Y", LiteralKind::Number, y)
+    }
+
+    /// `glide (secs) secs to x: (x) y: (y)`
+    pub fn glide_to_xy(secs: impl Into<Arg>, x: impl Into<Arg>, y: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_glidesecstoxy")
+            .input("SECS", LiteralKind::Number, secs)
+            .input("X", LiteralKind::Number, x)
+            .input("Y", LiteralKind::Number, y)
+    }
+
+    /// `point in direction (direction)`
+    pub fn point_in_direction(direction: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_pointindirection").input(
+            "DIRECTION",
+            LiteralKind::Angle,
+            direction,
+        )
+    }
+
+    /// `change x by (dx)`
+    pub fn change_x_by(dx: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_changexby").input("DX", LiteralKind::Number, dx)
+    }
+
+    /// `set x to (x)`
+    pub fn set_x(x: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_setx").input("X", LiteralKind::Number, x)
+    }
+
+    /// `change y by (dy)`
+    pub fn change_y_by(dy: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_changeyby").input("DY", LiteralKind::Number, dy)
+    }
+
+    /// `set y to (y)`
+    pub fn set_y(y: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("motion_sety").input("Y", LiteralKind::Number, y)
+    }
+
+    /// `if on edge, bounce`
+    pub fn if_on_edge_bounce() -> BlockBuilder {
+        BlockBuilder::new("motion_ifonedgebounce")
+    }
+
+    /// `(x position)`
+    pub fn x_position() -> BlockBuilder {
+        BlockBuilder::new("motion_xposition")
+    }
+
+    /// `(y position)`
+    pub fn y_position() -> BlockBuilder {
+        BlockBuilder::new("motion_yposition")
+    }
+
+    /// `(direction)`
+    pub fn direction() -> BlockBuilder {
+        BlockBuilder::new("motion_direction")
+    }
+}
+
+/// Looks blocks
+pub mod looks {
+    use super::{Arg, BlockBuilder, LiteralKind};
+
+    /// `say (message)`
+    pub fn say(message: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("looks_say").input("MESSAGE", LiteralKind::String, message)
+    }
+
+    /// `say (message) for (secs) seconds`
+    pub fn say_for_secs(message: impl Into<Arg>, secs: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("looks_sayforsecs")
+            .input("MESSAGE", LiteralKind::String, message)
+            .input("SECS", LiteralKind::Number, secs)
+    }
+
+    /// `think (message)`
+    pub fn think(message: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("looks_think").input("MESSAGE", LiteralKind::String, message)
+    }
+
+    /// `switch costume to (costume)`
+    pub fn switch_costume_to(costume: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("looks_switchcostumeto").menu(
+            "COSTUME",
+            "looks_costume",
+            "COSTUME",
+            costume,
+        )
+    }
+
+    /// `next costume`
+    pub fn next_costume() -> BlockBuilder {
+        BlockBuilder::new("looks_nextcostume")
+    }
+
+    /// `change size by (change)`
+    pub fn change_size_by(change: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("looks_changesizeby").input("CHANGE", LiteralKind::Number, change)
+    }
+
+    /// `set size to (size) %`
+    pub fn set_size_to(size: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("looks_setsizeto").input("SIZE", LiteralKind::Number, size)
+    }
+
+    /// `show`
+    pub fn show() -> BlockBuilder {
+        BlockBuilder::new("looks_show")
+    }
+
+    /// `hide`
+    pub fn hide() -> BlockBuilder {
+        BlockBuilder::new("looks_hide")
+    }
+}
+
+/// Sound blocks
+pub mod sound {
+    use super::{Arg, BlockBuilder};
+
+    /// `play sound (sound) until done`
+    pub fn play_until_done(sound: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("sound_playuntildone").menu(
+            "SOUND_MENU",
+            "sound_sounds_menu",
+            "SOUND_MENU",
+            sound,
+        )
+    }
+
+    /// `start sound (sound)`
+    pub fn start(sound: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("sound_play").menu("SOUND_MENU", "sound_sounds_menu", "SOUND_MENU", sound)
+    }
+
+    /// `stop all sounds`
+    pub fn stop_all() -> BlockBuilder {
+        BlockBuilder::new("sound_stopallsounds")
+    }
+}
+
+/// Events blocks
+pub mod events {
+    use super::{Arg, BlockBuilder, DataKind};
+
+    /// `when green flag clicked`
+    pub fn when_flag_clicked() -> BlockBuilder {
+        BlockBuilder::new("event_whenflagclicked")
+    }
+
+    /// `when (key) key pressed`, where `key` is `space`, `a`, `left arrow`...
+    pub fn when_key_pressed(key: &str) -> BlockBuilder {
+        BlockBuilder::new("event_whenkeypressed").field("KEY_OPTION", key)
+    }
+
+    /// `when this sprite clicked`
+    pub fn when_this_sprite_clicked() -> BlockBuilder {
+        BlockBuilder::new("event_whenthisspriteclicked")
+    }
+
+    /// `when I receive (broadcast)`
+    pub fn when_broadcast_received(broadcast: &str) -> BlockBuilder {
+        BlockBuilder::new("event_whenbroadcastreceived").data_field(DataKind::Broadcast, broadcast)
+    }
+
+    /// `broadcast (broadcast)`
+    pub fn broadcast(broadcast: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("event_broadcast").broadcast_input("BROADCAST_INPUT", broadcast)
+    }
+
+    /// `broadcast (broadcast) and wait`
+    pub fn broadcast_and_wait(broadcast: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("event_broadcastandwait").broadcast_input("BROADCAST_INPUT", broadcast)
+    }
+}
+
+/// Control blocks
+pub mod control {
+    use super::{stack_with, Arg, BlockBuilder, LiteralKind, StackBuilder};
+    use crate::block::{BlockMutation, BlockMutationEnum};
+
+    /// `wait (secs) seconds`
+    pub fn wait(secs: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("control_wait").input("DURATION", LiteralKind::PositiveNumber, secs)
+    }
+
+    /// `repeat (times)` with the stack made by `f` inside
+    pub fn repeat<F>(times: impl Into<Arg>, f: F) -> BlockBuilder
+    where
+        F: FnOnce(StackBuilder) -> StackBuilder,
+    {
+        BlockBuilder::new("control_repeat")
+            .input("TIMES", LiteralKind::PositiveInteger, times)
+            .substack("SUBSTACK", stack_with(f))
+    }
+
+    /// `forever` with the stack made by `f` inside
+    pub fn forever<F>(f: F) -> BlockBuilder
+    where
+        F: FnOnce(StackBuilder) -> StackBuilder,
+    {
+        BlockBuilder::new("control_forever").substack("SUBSTACK", stack_with(f))
+    }
+
+    /// `if <condition> then` with the stack made by `f` inside
+    pub fn if_then<F>(condition: BlockBuilder, f: F) -> BlockBuilder
+    where
+        F: FnOnce(StackBuilder) -> StackBuilder,
+    {
+        BlockBuilder::new("control_if")
+            .boolean_input("CONDITION", condition)
+            .substack("SUBSTACK", stack_with(f))
+    }
+
+    /// `if <condition> then` with the stack made by `then` inside and `else` with the stack made by `otherwise`
+    pub fn if_else<F, G>(condition: BlockBuilder, then: F, otherwise: G) -> BlockBuilder
+    where
+        F: FnOnce(StackBuilder) -> StackBuilder,
+        G: FnOnce(StackBuilder) -> StackBuilder,
+    {
+        BlockBuilder::new("control_if_else")
+            .boolean_input("CONDITION", condition)
+            .substack("SUBSTACK", stack_with(then))
+            .substack("SUBSTACK2", stack_with(otherwise))
+    }
+
+    /// `wait until <condition>`
+    pub fn wait_until(condition: BlockBuilder) -> BlockBuilder {
+        BlockBuilder::new("control_wait_until").boolean_input("CONDITION", condition)
+    }
+
+    /// `repeat until <condition>` with the stack made by `f` inside
+    pub fn repeat_until<F>(condition: BlockBuilder, f: F) -> BlockBuilder
+    where
+        F: FnOnce(StackBuilder) -> StackBuilder,
+    {
+        BlockBuilder::new("control_repeat_until")
+            .boolean_input("CONDITION", condition)
+            .substack("SUBSTACK", stack_with(f))
+    }
+
+    /// `stop (option)`, where `option` is `all`, `this script`, `other scripts in sprite`
+    /// or `other scripts in stage`
+    pub fn stop(option: &str) -> BlockBuilder {
+        BlockBuilder::new("control_stop")
+            .field("STOP_OPTION", option)
+            .mutation(BlockMutation {
+                tag_name: "mutation".to_owned(),
+                children: vec![],
+                mutation_enum: BlockMutationEnum::ControlStop {
+                    hasnext: option.starts_with("other scripts"),
+                },
+            })
+    }
+
+    /// `when I start as a clone`
+    pub fn start_as_clone() -> BlockBuilder {
+        BlockBuilder::new("control_start_as_clone")
+    }
+
+    /// `create clone of (clone)`, where `clone` is `_myself_` or the name of a sprite
+    pub fn create_clone_of(clone: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("control_create_clone_of").menu(
+            "CLONE_OPTION",
+            "control_create_clone_of_menu",
+            "CLONE_OPTION",
+            clone,
+        )
+    }
+
+    /// `delete this clone`
+    pub fn delete_this_clone() -> BlockBuilder {
+        BlockBuilder::new("control_delete_this_clone")
+    }
+}
+
+/// Sensing blocks
+pub mod sensing {
+    use super::{Arg, BlockBuilder, LiteralKind};
+
+    /// `<touching (object)?>`, where `object` is `_mouse_`, `_edge_` or the name of a sprite
+    pub fn touching(object: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("sensing_touchingobject").menu(
+            "TOUCHINGOBJECTMENU",
+            "sensing_touchingobjectmenu",
+            "TOUCHINGOBJECTMENU",
+            object,
+        )
+    }
+
+    /// `<key (key) pressed?>`
+    pub fn key_pressed(key: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("sensing_keypressed").menu(
+            "KEY_OPTION",
+            "sensing_keyoptions",
+            "KEY_OPTION",
+            key,
+        )
+    }
+
+    /// `ask (question) and wait`
+    pub fn ask_and_wait(question: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("sensing_askandwait").input("QUESTION", LiteralKind::String, question)
+    }
+
+    /// `(answer)`
+    pub fn answer() -> BlockBuilder {
+        BlockBuilder::new("sensing_answer")
+    }
+
+    /// `(mouse x)`
+    pub fn mouse_x() -> BlockBuilder {
+        BlockBuilder::new("sensing_mousex")
+    }
+
+    /// `(mouse y)`
+    pub fn mouse_y() -> BlockBuilder {
+        BlockBuilder::new("sensing_mousey")
+    }
+
+    /// `(timer)`
+    pub fn timer() -> BlockBuilder {
+        BlockBuilder::new("sensing_timer")
+    }
+
+    /// `reset timer`
+    pub fn reset_timer() -> BlockBuilder {
+        BlockBuilder::new("sensing_resettimer")
+    }
+}
+
+/// Operators blocks
+pub mod operator {
+    use super::{Arg, BlockBuilder, LiteralKind};
+
+    /// `((a) + (b))`
+    pub fn add(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_add")
+            .input("NUM1", LiteralKind::Number, a)
+            .input("NUM2", LiteralKind::Number, b)
+    }
+
+    /// `((a) - (b))`
+    pub fn subtract(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_subtract")
+            .input("NUM1", LiteralKind::Number, a)
+            .input("NUM2", LiteralKind::Number, b)
+    }
+
+    /// `((a) * (b))`
+    pub fn multiply(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_multiply")
+            .input("NUM1", LiteralKind::Number, a)
+            .input("NUM2", LiteralKind::Number, b)
+    }
+
+    /// `((a) / (b))`
+    pub fn divide(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_divide")
+            .input("NUM1", LiteralKind::Number, a)
+            .input("NUM2", LiteralKind::Number, b)
+    }
+
+    /// `((a) mod (b))`
+    pub fn modulo(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_mod")
+            .input("NUM1", LiteralKind::Number, a)
+            .input("NUM2", LiteralKind::Number, b)
+    }
+
+    /// `(pick random (from) to (to))`
+    pub fn random(from: impl Into<Arg>, to: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_random")
+            .input("FROM", LiteralKind::Number, from)
+            .input("TO", LiteralKind::Number, to)
+    }
+
+    /// `<(a) > (b)>`
+    pub fn gt(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_gt")
+            .input("OPERAND1", LiteralKind::String, a)
+            .input("OPERAND2", LiteralKind::String, b)
+    }
+
+    /// `<(a) < (b)>`
+    pub fn lt(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_lt")
+            .input("OPERAND1", LiteralKind::String, a)
+            .input("OPERAND2", LiteralKind::String, b)
+    }
+
+    /// `<(a) = (b)>`
+    pub fn equals(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_equals")
+            .input("OPERAND1", LiteralKind::String, a)
+            .input("OPERAND2", LiteralKind::String, b)
+    }
+
+    /// `<<a> and <b>>`
+    pub fn and(a: BlockBuilder, b: BlockBuilder) -> BlockBuilder {
+        BlockBuilder::new("operator_and")
+            .boolean_input("OPERAND1", a)
+            .boolean_input("OPERAND2", b)
+    }
+
+    /// `<<a> or <b>>`
+    pub fn or(a: BlockBuilder, b: BlockBuilder) -> BlockBuilder {
+        BlockBuilder::new("operator_or")
+            .boolean_input("OPERAND1", a)
+            .boolean_input("OPERAND2", b)
+    }
+
+    /// `<not <a>>`
+    pub fn not(a: BlockBuilder) -> BlockBuilder {
+        BlockBuilder::new("operator_not").boolean_input("OPERAND", a)
+    }
+
+    /// `(join (a) (b))`
+    pub fn join(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_join")
+            .input("STRING1", LiteralKind::String, a)
+            .input("STRING2", LiteralKind::String, b)
+    }
+
+    /// `(letter (letter) of (string))`
+    pub fn letter_of(letter: impl Into<Arg>, string: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_letter_of")
+            .input("LETTER", LiteralKind::PositiveInteger, letter)
+            .input("STRING", LiteralKind::String, string)
+    }
+
+    /// `(length of (string))`
+    pub fn length(string: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_length").input("STRING", LiteralKind::String, string)
+    }
+
+    /// `<(a) contains (b)?>`
+    pub fn contains(a: impl Into<Arg>, b: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_contains")
+            .input("STRING1", LiteralKind::String, a)
+            .input("STRING2", LiteralKind::String, b)
+    }
+
+    /// `(round (num))`
+    pub fn round(num: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_round").input("NUM", LiteralKind::Number, num)
+    }
+
+    /// `((operator) of (num))`, where `operator` is `abs`, `sqrt`, `sin`...
+    pub fn mathop(operator: &str, num: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("operator_mathop")
+            .field("OPERATOR", operator)
+            .input("NUM", LiteralKind::Number, num)
+    }
+}
+
+/// Variables and lists blocks
+pub mod data {
+    use super::{Arg, BlockBuilder, DataKind, LiteralKind};
+
+    /// `(variable)` reporter, to put in an input
+    pub fn variable(name: &str) -> Arg {
+        Arg::Variable(name.to_owned())
+    }
+
+    /// `(list)` reporter, to put in an input
+    pub fn list(name: &str) -> Arg {
+        Arg::List(name.to_owned())
+    }
+
+    /// `set [variable] to (value)`
+    pub fn set_variable_to(variable: &str, value: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("data_setvariableto")
+            .input("VALUE", LiteralKind::String, value)
+            .data_field(DataKind::Variable, variable)
+    }
+
+    /// `change [variable] by (value)`
+    pub fn change_variable_by(variable: &str, value: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("data_changevariableby")
+            .input("VALUE", LiteralKind::Number, value)
+            .data_field(DataKind::Variable, variable)
+    }
+
+    /// `show variable [variable]`
+    pub fn show_variable(variable: &str) -> BlockBuilder {
+        BlockBuilder::new("data_showvariable").data_field(DataKind::Variable, variable)
+    }
+
+    /// `hide variable [variable]`
+    pub fn hide_variable(variable: &str) -> BlockBuilder {
+        BlockBuilder::new("data_hidevariable").data_field(DataKind::Variable, variable)
+    }
+
+    /// `add (item) to [list]`
+    pub fn add_to_list(item: impl Into<Arg>, list: &str) -> BlockBuilder {
+        BlockBuilder::new("data_addtolist")
+            .input("ITEM", LiteralKind::String, item)
+            .data_field(DataKind::List, list)
+    }
+
+    /// `delete (index) of [list]`
+    pub fn delete_of_list(index: impl Into<Arg>, list: &str) -> BlockBuilder {
+        BlockBuilder::new("data_deleteoflist")
+            .input("INDEX", LiteralKind::Integer, index)
+            .data_field(DataKind::List, list)
+    }
+
+    /// `delete all of [list]`
+    pub fn delete_all_of_list(list: &str) -> BlockBuilder {
+        BlockBuilder::new("data_deletealloflist").data_field(DataKind::List, list)
+    }
+
+    /// `insert (item) at (index) of [list]`
+    pub fn insert_at_list(item: impl Into<Arg>, index: impl Into<Arg>, list: &str) -> BlockBuilder {
+        BlockBuilder::new("data_insertatlist")
+            .input("ITEM", LiteralKind::String, item)
+            .input("INDEX", LiteralKind::Integer, index)
+            .data_field(DataKind::List, list)
+    }
+
+    /// `replace item (index) of [list] with (item)`
+    pub fn replace_item_of_list(
+        index: impl Into<Arg>,
+        list: &str,
+        item: impl Into<Arg>,
+    ) -> BlockBuilder {
+        BlockBuilder::new("data_replaceitemoflist")
+            .input("INDEX", LiteralKind::Integer, index)
+            .input("ITEM", LiteralKind::String, item)
+            .data_field(DataKind::List, list)
+    }
+
+    /// `(item (index) of [list])`
+    pub fn item_of_list(index: impl Into<Arg>, list: &str) -> BlockBuilder {
+        BlockBuilder::new("data_itemoflist")
+            .input("INDEX", LiteralKind::Integer, index)
+            .data_field(DataKind::List, list)
+    }
+
+    /// `(length of [list])`
+    pub fn length_of_list(list: &str) -> BlockBuilder {
+        BlockBuilder::new("data_lengthoflist").data_field(DataKind::List, list)
+    }
+
+    /// `<[list] contains (item)?>`
+    pub fn list_contains_item(list: &str, item: impl Into<Arg>) -> BlockBuilder {
+        BlockBuilder::new("data_listcontainsitem")
+            .input("ITEM", LiteralKind::String, item)
+            .data_field(DataKind::List, list)
+    }
+}
@@ -26,4 +26,8 @@ pub struct Comment {
 
     /// The text.
     pub text: Text,
+
+    /// Unknown keys, see [`StringHashMap`]
+    #[serde(flatten)]
+    pub extra: StringHashMap<Json>,
 }
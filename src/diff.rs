@@ -0,0 +1,552 @@
+//! Module to compare projects by meaning
+//!
+//! Ids and the order of maps change every time Scratch saves a project, so a diff of project.json
+//! is mostly noise. [`diff`] matches what is in both projects by what it is instead:
+//! targets by name, scripts by their [scratchblocks](crate::scratchblocks) text,
+//! variables, lists and broadcasts by Id then by name, costumes and sounds by name then by asset Id,
+//! and monitors by Id.
+//!
+//! [`Diff`] serializes with serde, and displays as text:
+//!
+//! ```text
+//! + sprite Cat
+//! Stage: ~ variable score renamed to points
+//! Stage: ~ script
+//!     when green flag clicked
+//!   - move (10) steps
+//!   + move (20) steps
+//! ```
+
+use crate::archive::AssetKind;
+use crate::block::DataKind;
+use crate::prelude::*;
+use crate::project::Project;
+use crate::scratchblocks::render_scripts;
+use crate::target::Target;
+use std::fmt;
+
+/// Every change from one project to another, see [`diff`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Diff {
+    /// Changes in the order of the targets, sprites added and monitors come last
+    pub changes: Vec<Change>,
+}
+
+/// A change from one project to another.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Change {
+    /// Name of the target the change is in, the sprite itself for sprites added or removed.
+    /// [`None`] for monitors of the stage.
+    pub target: Option<Name>,
+
+    /// See [`ChangeKind`]
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+/// What changed.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// The sprite is only in the new project.
+    SpriteAdded,
+
+    /// The sprite is only in the old project.
+    SpriteRemoved,
+
+    /// A variable, list or broadcast is only in the new project.
+    DataAdded {
+        /// Variable, list or broadcast
+        kind: DataKind,
+        /// Its name
+        name: Name,
+    },
+
+    /// A variable, list or broadcast is only in the old project.
+    DataRemoved {
+        /// Variable, list or broadcast
+        kind: DataKind,
+        /// Its name
+        name: Name,
+    },
+
+    /// A variable, list or broadcast kept its Id but not its name.
+    DataRenamed {
+        /// Variable, list or broadcast
+        kind: DataKind,
+        /// Name in the old project
+        old: Name,
+        /// Name in the new project
+        new: Name,
+    },
+
+    /// A script is only in the new project.
+    ScriptAdded {
+        /// The script as scratchblocks
+        script: String,
+    },
+
+    /// A script is only in the old project.
+    ScriptRemoved {
+        /// The script as scratchblocks
+        script: String,
+    },
+
+    /// A script starts with the same hat but has other blocks under it.
+    ScriptModified {
+        /// The script in the old project as scratchblocks
+        old: String,
+        /// The script in the new project as scratchblocks
+        new: String,
+    },
+
+    /// A costume or sound is only in the new project.
+    AssetAdded {
+        /// Costume or sound
+        kind: AssetKind,
+        /// Its name
+        name: Name,
+        /// Its asset Id, the MD5 of the file
+        asset_id: Uid,
+    },
+
+    /// A costume or sound is only in the old project.
+    AssetRemoved {
+        /// Costume or sound
+        kind: AssetKind,
+        /// Its name
+        name: Name,
+        /// Its asset Id, the MD5 of the file
+        asset_id: Uid,
+    },
+
+    /// A costume or sound kept its file but not its name.
+    AssetRenamed {
+        /// Costume or sound
+        kind: AssetKind,
+        /// Asset Id of the file
+        asset_id: Uid,
+        /// Name in the old project
+        old: Name,
+        /// Name in the new project
+        new: Name,
+    },
+
+    /// A costume or sound kept its name but has another file.
+    AssetChanged {
+        /// Costume or sound
+        kind: AssetKind,
+        /// Its name
+        name: Name,
+        /// Asset Id in the old project
+        old: Uid,
+        /// Asset Id in the new project
+        new: Uid,
+    },
+
+    /// A monitor is only in the new project.
+    MonitorAdded {
+        /// Id of the monitor
+        id: Uid,
+    },
+
+    /// A monitor is only in the old project.
+    MonitorRemoved {
+        /// Id of the monitor
+        id: Uid,
+    },
+
+    /// A monitor is somewhere else on the stage.
+    MonitorMoved {
+        /// Id of the monitor
+        id: Uid,
+        /// Position in the old project
+        old: (i64, i64),
+        /// Position in the new project
+        new: (i64, i64),
+    },
+}
+
+/// Changes from the old project to the new one.
+///
+/// Values of variables and lists, and where scripts are in the code area, are not compared.
+pub fn diff(old: &Project, new: &Project) -> Diff {
+    let mut changes = vec![];
+
+    let old_targets: Vec<&Target> = old.targets.iter().map(|t| t.target()).collect();
+    let new_targets: Vec<&Target> = new.targets.iter().map(|t| t.target()).collect();
+    let is_stage = |project: &Project, i: usize| project.targets[i].is_stage();
+    let mut pairs = Pairs::new(old_targets.len(), new_targets.len());
+    pairs.pair(|o, n| is_stage(old, o) && is_stage(new, n));
+    pairs.pair(|o, n| {
+        !is_stage(old, o) && !is_stage(new, n) && old_targets[o].name == new_targets[n].name
+    });
+    for (o, n) in pairs.matched.clone() {
+        let target = Some(new_targets[n].name.clone());
+        for kind in diff_target(old_targets[o], new_targets[n]) {
+            changes.push(Change {
+                target: target.clone(),
+                kind,
+            });
+        }
+    }
+    for (targets, kind, unmatched) in [
+        (&old_targets, ChangeKind::SpriteRemoved, pairs.old_left()),
+        (&new_targets, ChangeKind::SpriteAdded, pairs.new_left()),
+    ] {
+        for i in unmatched {
+            changes.push(Change {
+                target: Some(targets[i].name.clone()),
+                kind: kind.clone(),
+            });
+        }
+    }
+
+    let mut pairs = Pairs::new(old.monitors.len(), new.monitors.len());
+    pairs.pair(|o, n| old.monitors[o].id == new.monitors[n].id);
+    for (o, n) in pairs.matched.clone() {
+        let (o, n) = (&old.monitors[o], &new.monitors[n]);
+        if (o.x, o.y) != (n.x, n.y) {
+            changes.push(Change {
+                target: n.sprite_name.clone(),
+                kind: ChangeKind::MonitorMoved {
+                    id: n.id.clone(),
+                    old: (o.x, o.y),
+                    new: (n.x, n.y),
+                },
+            });
+        }
+    }
+    for i in pairs.old_left() {
+        let monitor = &old.monitors[i];
+        changes.push(Change {
+            target: monitor.sprite_name.clone(),
+            kind: ChangeKind::MonitorRemoved {
+                id: monitor.id.clone(),
+            },
+        });
+    }
+    for i in pairs.new_left() {
+        let monitor = &new.monitors[i];
+        changes.push(Change {
+            target: monitor.sprite_name.clone(),
+            kind: ChangeKind::MonitorAdded {
+                id: monitor.id.clone(),
+            },
+        });
+    }
+
+    Diff { changes }
+}
+
+impl Diff {
+    /// Whether the projects are the same.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+fn diff_target(old: &Target, new: &Target) -> Vec<ChangeKind> {
+    let mut changes = vec![];
+
+    for kind in [DataKind::Variable, DataKind::List, DataKind::Broadcast] {
+        let (old, new) = (data(old, kind), data(new, kind));
+        let mut pairs = Pairs::new(old.len(), new.len());
+        pairs.pair(|o, n| old[o].0 == new[n].0);
+        // Ids that changed but not the name
+        pairs.pair(|o, n| old[o].1 == new[n].1);
+        for &(o, n) in &pairs.matched {
+            if old[o].1 != new[n].1 {
+                changes.push(ChangeKind::DataRenamed {
+                    kind,
+                    old: old[o].1.clone(),
+                    new: new[n].1.clone(),
+                });
+            }
+        }
+        for i in pairs.old_left() {
+            let name = old[i].1.clone();
+            changes.push(ChangeKind::DataRemoved { kind, name });
+        }
+        for i in pairs.new_left() {
+            let name = new[i].1.clone();
+            changes.push(ChangeKind::DataAdded { kind, name });
+        }
+    }
+
+    let (old_scripts, new_scripts) = (scripts(old), scripts(new));
+    let mut pairs = Pairs::new(old_scripts.len(), new_scripts.len());
+    pairs.pair(|o, n| old_scripts[o] == new_scripts[n]);
+    let unchanged = pairs.matched.len();
+    pairs.pair(|o, n| old_scripts[o].lines().next() == new_scripts[n].lines().next());
+    for &(o, n) in &pairs.matched[unchanged..] {
+        changes.push(ChangeKind::ScriptModified {
+            old: old_scripts[o].clone(),
+            new: new_scripts[n].clone(),
+        });
+    }
+    for i in pairs.old_left() {
+        let script = old_scripts[i].clone();
+        changes.push(ChangeKind::ScriptRemoved { script });
+    }
+    for i in pairs.new_left() {
+        let script = new_scripts[i].clone();
+        changes.push(ChangeKind::ScriptAdded { script });
+    }
+
+    for kind in [AssetKind::Costume, AssetKind::Sound] {
+        let (old, new) = (assets(old, kind), assets(new, kind));
+        let mut pairs = Pairs::new(old.len(), new.len());
+        pairs.pair(|o, n| old[o].0 == new[n].0);
+        let same_name = pairs.matched.len();
+        pairs.pair(|o, n| old[o].1 == new[n].1);
+        for (i, &(o, n)) in pairs.matched.iter().enumerate() {
+            let ((old_name, old_id), (new_name, new_id)) = (&old[o], &new[n]);
+            if i < same_name && old_id != new_id {
+                changes.push(ChangeKind::AssetChanged {
+                    kind,
+                    name: new_name.clone(),
+                    old: old_id.clone(),
+                    new: new_id.clone(),
+                });
+            } else if i >= same_name {
+                changes.push(ChangeKind::AssetRenamed {
+                    kind,
+                    asset_id: new_id.clone(),
+                    old: old_name.clone(),
+                    new: new_name.clone(),
+                });
+            }
+        }
+        for i in pairs.old_left() {
+            let (name, asset_id) = old[i].clone();
+            changes.push(ChangeKind::AssetRemoved {
+                kind,
+                name,
+                asset_id,
+            });
+        }
+        for i in pairs.new_left() {
+            let (name, asset_id) = new[i].clone();
+            changes.push(ChangeKind::AssetAdded {
+                kind,
+                name,
+                asset_id,
+            });
+        }
+    }
+
+    changes
+}
+
+/// Ids and names of the variables, lists or broadcasts of the target.
+pub(crate) fn data(target: &Target, kind: DataKind) -> Vec<(Uid, Name)> {
+    match kind {
+        DataKind::Variable => (target.variables.0.iter())
+            .map(|(id, v)| (id.clone(), v.name.clone()))
+            .collect(),
+        DataKind::List => (target.lists.0.iter())
+            .map(|(id, l)| (id.clone(), l.name.clone()))
+            .collect(),
+        DataKind::Broadcast => (target.broadcasts.0.iter())
+            .map(|(id, b)| (id.clone(), b.name.clone()))
+            .collect(),
+    }
+}
+
+/// Names and asset Ids of the costumes or sounds of the target.
+fn assets(target: &Target, kind: AssetKind) -> Vec<(Name, Uid)> {
+    let assets: Vec<_> = match kind {
+        AssetKind::Costume => target.costumes.iter().map(|c| &c.asset).collect(),
+        AssetKind::Sound => target.sounds.iter().map(|s| &s.asset).collect(),
+    };
+    assets
+        .into_iter()
+        .map(|a| (a.name.clone(), a.asset_id.clone()))
+        .collect()
+}
+
+/// Scripts of the target as scratchblocks, the error for those that can't be walked.
+fn scripts(target: &Target) -> Vec<String> {
+    render_scripts(target)
+        .into_iter()
+        .map(|script| script.unwrap_or_else(|e| format!("{e}\n")))
+        .collect()
+}
+
+/// Items of two lists paired with each other, each at most once.
+struct Pairs {
+    matched: Vec<(usize, usize)>,
+    old_used: Vec<bool>,
+    new_used: Vec<bool>,
+}
+
+impl Pairs {
+    fn new(old_len: usize, new_len: usize) -> Pairs {
+        Pairs {
+            matched: vec![],
+            old_used: vec![false; old_len],
+            new_used: vec![false; new_len],
+        }
+    }
+
+    /// Pair every item left in the old list with the first one left in the new list that is the same.
+    fn pair<F>(&mut self, mut same: F)
+    where
+        F: FnMut(usize, usize) -> bool,
+    {
+        for o in 0..self.old_used.len() {
+            if self.old_used[o] {
+                continue;
+            }
+            let found = (0..self.new_used.len()).find(|&n| !self.new_used[n] && same(o, n));
+            if let Some(n) = found {
+                self.old_used[o] = true;
+                self.new_used[n] = true;
+                self.matched.push((o, n));
+            }
+        }
+    }
+
+    /// Items of the old list left unpaired.
+    fn old_left(&self) -> Vec<usize> {
+        (0..self.old_used.len())
+            .filter(|&i| !self.old_used[i])
+            .collect()
+    }
+
+    /// Items of the new list left unpaired.
+    fn new_left(&self) -> Vec<usize> {
+        (0..self.new_used.len())
+            .filter(|&i| !self.new_used[i])
+            .collect()
+    }
+}
+
+/// Lines of both texts, marked `-` when only in the old one and `+` when only in the new one.
+fn line_diff<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // Longest common subsequence of the lines that follow
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for o in (0..old.len()).rev() {
+        for n in (0..new.len()).rev() {
+            lcs[o][n] = match old[o] == new[n] {
+                true => lcs[o + 1][n + 1] + 1,
+                false => lcs[o + 1][n].max(lcs[o][n + 1]),
+            };
+        }
+    }
+    let (mut o, mut n) = (0, 0);
+    let mut lines = vec![];
+    while o < old.len() || n < new.len() {
+        if o < old.len() && n < new.len() && old[o] == new[n] {
+            lines.push((' ', old[o]));
+            (o, n) = (o + 1, n + 1);
+        } else if o < old.len() && (n == new.len() || lcs[o + 1][n] >= lcs[o][n + 1]) {
+            lines.push(('-', old[o]));
+            o += 1;
+        } else {
+            lines.push(('+', new[n]));
+            n += 1;
+        }
+    }
+    lines
+}
+
+pub(crate) fn data_name(kind: DataKind) -> &'static str {
+    match kind {
+        DataKind::Variable => "variable",
+        DataKind::List => "list",
+        DataKind::Broadcast => "broadcast",
+    }
+}
+
+pub(crate) fn asset_name(kind: AssetKind) -> &'static str {
+    match kind {
+        AssetKind::Costume => "costume",
+        AssetKind::Sound => "sound",
+    }
+}
+
+// Display impl ================================================================
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Change {
+    /// One line, followed by the indented script for changes to scripts.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ChangeKind::*;
+
+        let target = self.target.as_deref().unwrap_or("Stage");
+        match &self.kind {
+            SpriteAdded => return write!(f, "+ sprite {target}"),
+            SpriteRemoved => return write!(f, "- sprite {target}"),
+            _ => write!(f, "{target}: ")?,
+        }
+        match &self.kind {
+            SpriteAdded | SpriteRemoved => unreachable!(),
+            DataAdded { kind, name } => write!(f, "+ {} {name}", data_name(*kind)),
+            DataRemoved { kind, name } => write!(f, "- {} {name}", data_name(*kind)),
+            DataRenamed { kind, old, new } => {
+                write!(f, "~ {} {old} renamed to {new}", data_name(*kind))
+            }
+            ScriptAdded { script } | ScriptRemoved { script } => {
+                let sign = if matches!(self.kind, ScriptAdded { .. }) {
+                    '+'
+                } else {
+                    '-'
+                };
+                write!(f, "{sign} script")?;
+                for line in script.lines() {
+                    write!(f, "\n    {line}")?;
+                }
+                Ok(())
+            }
+            ScriptModified { old, new } => {
+                write!(f, "~ script")?;
+                for (sign, line) in line_diff(old, new) {
+                    write!(f, "\n  {sign} {line}")?;
+                }
+                Ok(())
+            }
+            AssetAdded {
+                kind,
+                name,
+                asset_id,
+            } => write!(f, "+ {} {name} ({asset_id})", asset_name(*kind)),
+            AssetRemoved {
+                kind,
+                name,
+                asset_id,
+            } => write!(f, "- {} {name} ({asset_id})", asset_name(*kind)),
+            AssetRenamed { kind, old, new, .. } => {
+                write!(f, "~ {} {old} renamed to {new}", asset_name(*kind))
+            }
+            AssetChanged {
+                kind,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "~ {} {name} changed from {old} to {new}",
+                asset_name(*kind)
+            ),
+            MonitorAdded { id } => write!(f, "+ monitor {id}"),
+            MonitorRemoved { id } => write!(f, "- monitor {id}"),
+            MonitorMoved { id, old, new } => write!(
+                f,
+                "~ monitor {id} moved from ({}, {}) to ({}, {})",
+                old.0, old.1, new.0, new.1
+            ),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Module to deal with extensions
+//!
+//! Blocks of extensions have their opcode prefixed with the Id of the extension, such as `pen_clear`.
+//! Scratch only loads the extensions listed in [`Project::extensions`],
+//! so a project using pen blocks without `pen` in the list fails to load.
+//! [`Project::missing_extensions`] finds those and [`Project::add_missing_extensions`] adds them.
+
+use crate::block::Block;
+use crate::prelude::*;
+use crate::project::Project;
+use std::fmt;
+
+/// An extension a project can load.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Extension {
+    /// `pen`
+    Pen,
+    /// `music`
+    Music,
+    /// `videoSensing`
+    VideoSensing,
+    /// `text2speech`
+    TextToSpeech,
+    /// `translate`
+    Translate,
+    /// `makeymakey`
+    MakeyMakey,
+    /// `microbit`
+    Microbit,
+    /// `ev3`, LEGO MINDSTORMS EV3
+    Ev3,
+    /// `wedo2`, LEGO Education WeDo 2.0
+    Wedo2,
+    /// `boost`, LEGO BOOST
+    Boost,
+    /// `gdxfor`, Go Direct Force & Acceleration
+    Gdxfor,
+    /// Any other extension, such as the custom extensions of TurboWarp, by its Id.
+    Custom(String),
+}
+
+/// Opcode prefixes of the blocks that are always loaded. From scratch-vm `CORE_EXTENSIONS`.
+const CORE: [&str; 12] = [
+    "argument",
+    "colour",
+    "control",
+    "data",
+    "event",
+    "looks",
+    "math",
+    "motion",
+    "operator",
+    "procedures",
+    "sensing",
+    "sound",
+];
+
+impl Extension {
+    /// The Id of the extension, as it is in project.json.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Extension::Pen => "pen",
+            Extension::Music => "music",
+            Extension::VideoSensing => "videoSensing",
+            Extension::TextToSpeech => "text2speech",
+            Extension::Translate => "translate",
+            Extension::MakeyMakey => "makeymakey",
+            Extension::Microbit => "microbit",
+            Extension::Ev3 => "ev3",
+            Extension::Wedo2 => "wedo2",
+            Extension::Boost => "boost",
+            Extension::Gdxfor => "gdxfor",
+            Extension::Custom(id) => id,
+        }
+    }
+
+    /// The extension a block with the opcode belongs to.
+    /// [`None`] for blocks that are always loaded and for opcodes with no prefix, such as `note`.
+    pub fn from_opcode(opcode: &str) -> Option<Extension> {
+        let (prefix, _) = opcode.split_once('_')?;
+        if prefix.is_empty() || CORE.contains(&prefix) {
+            return None;
+        }
+        Some(Extension::from(prefix))
+    }
+}
+
+impl From<&str> for Extension {
+    fn from(id: &str) -> Self {
+        match id {
+            "pen" => Extension::Pen,
+            "music" => Extension::Music,
+            "videoSensing" => Extension::VideoSensing,
+            "text2speech" => Extension::TextToSpeech,
+            "translate" => Extension::Translate,
+            "makeymakey" => Extension::MakeyMakey,
+            "microbit" => Extension::Microbit,
+            "ev3" => Extension::Ev3,
+            "wedo2" => Extension::Wedo2,
+            "boost" => Extension::Boost,
+            "gdxfor" => Extension::Gdxfor,
+            id => Extension::Custom(id.to_owned()),
+        }
+    }
+}
+
+impl Project {
+    /// Extensions that blocks of any target use but the project does not list,
+    /// in the order they are first used.
+    pub fn missing_extensions(&self) -> Vec<Extension> {
+        let mut missing = vec![];
+        for target in &self.targets {
+            for block in target.target().blocks.0.values() {
+                let Block::Normal(block) = block else {
+                    continue;
+                };
+                let Some(extension) = Extension::from_opcode(&block.opcode) else {
+                    continue;
+                };
+                if !self.extensions.contains(&extension) && !missing.contains(&extension) {
+                    missing.push(extension);
+                }
+            }
+        }
+        missing
+    }
+
+    /// Add the extensions that blocks use to [`Project::extensions`], see [`Project::missing_extensions`].
+    pub fn add_missing_extensions(&mut self) {
+        let missing = self.missing_extensions();
+        self.extensions.extend(missing);
+    }
+}
+
+// Serde impl ==================================================================
+
+impl Serialize for Extension {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Extension {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let id = String::deserialize(deserializer)?;
+        Ok(Extension::from(id.as_str()))
+    }
+}
+
+// Display impl ================================================================
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,485 @@
+//! Module to read the size of costume images
+//!
+//! Scratch puts the rotation center of an uploaded costume in the middle of the image.
+//! [`Costume::center_from_bytes`] does the same from the `viewBox`, `width` and `height`
+//! of SVG files and the header of PNG, JPEG and GIF files,
+//! or in the middle of the visible pixels of a PNG with [`Centering::VisibleBounds`].
+
+use crate::asset::{Costume, DataFormat};
+use crate::prelude::*;
+use flate2::read::ZlibDecoder;
+use std::fmt;
+use std::io::Read;
+
+/// Size of an image.
+/// In user units for SVG, in pixels of the file for bitmaps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageSize {
+    /// The width
+    pub width: Float,
+
+    /// The height
+    pub height: Float,
+}
+
+/// Rectangle of pixels, `right` and `bottom` excluded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    /// The first column
+    pub left: u32,
+    /// The first row
+    pub top: u32,
+    /// The column after the last
+    pub right: u32,
+    /// The row after the last
+    pub bottom: u32,
+}
+
+/// Where [`Costume::center_from_bytes`] puts the rotation center.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Centering {
+    /// The middle of the image, like Scratch does.
+    #[default]
+    Image,
+
+    /// The middle of the pixels that are not fully transparent, for PNG files.
+    /// Other formats and PNG files with no visible pixel fall back to [`Centering::Image`].
+    VisibleBounds,
+}
+
+/// Error when reading the size of an image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// The file is not an SVG, PNG, JPEG or GIF file.
+    UnknownFormat,
+
+    /// The file ends in the middle of a header or of the image data.
+    Truncated,
+
+    /// The SVG has neither a `viewBox` nor a `width` and `height` in pixels.
+    NoSize,
+
+    /// The compressed data of the PNG file is invalid.
+    InvalidData,
+
+    /// The PNG file uses something that is not supported, such as interlacing.
+    Unsupported {
+        /// What is not supported
+        reason: &'static str,
+    },
+}
+
+impl ImageSize {
+    /// Read the size of an image of any costume format, detected from its magic bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ImageSize, ImageError> {
+        match DataFormat::detect(bytes) {
+            Some(DataFormat::Svg) => ImageSize::from_svg(bytes),
+            Some(DataFormat::Png) => ImageSize::from_png(bytes),
+            Some(DataFormat::Jpg) => ImageSize::from_jpeg(bytes),
+            Some(DataFormat::Gif) => ImageSize::from_gif(bytes),
+            _ => Err(ImageError::UnknownFormat),
+        }
+    }
+
+    /// Read the size of the `<svg>` tag.
+    /// The `viewBox` is what Scratch draws, `width` and `height` are used without it.
+    /// Lengths in units other than pixels are ignored.
+    pub fn from_svg(bytes: &[u8]) -> Result<ImageSize, ImageError> {
+        let text = String::from_utf8_lossy(bytes);
+        let tag = svg_tag(&text).ok_or(ImageError::UnknownFormat)?;
+        let attribute = |name: &str| svg_attribute(tag, name);
+
+        if let Some(view_box) = attribute("viewBox") {
+            let numbers: Vec<Float> = view_box
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|n| !n.is_empty())
+                .map_while(|n| n.parse().ok())
+                .collect();
+            if let [_, _, width, height] = numbers[..] {
+                if width > 0. && height > 0. {
+                    return Ok(ImageSize { width, height });
+                }
+            }
+        }
+        let length = |name| attribute(name).and_then(svg_length);
+        match (length("width"), length("height")) {
+            (Some(width), Some(height)) => Ok(ImageSize { width, height }),
+            _ => Err(ImageError::NoSize),
+        }
+    }
+
+    /// Read the size from the `IHDR` chunk of a PNG file.
+    pub fn from_png(bytes: &[u8]) -> Result<ImageSize, ImageError> {
+        if !bytes.starts_with(PNG_SIGNATURE) {
+            return Err(ImageError::UnknownFormat);
+        }
+        let header = png_header(bytes)?;
+        Ok(ImageSize {
+            width: header.width as Float,
+            height: header.height as Float,
+        })
+    }
+
+    /// Read the size from the start of frame segment of a JPEG file.
+    pub fn from_jpeg(bytes: &[u8]) -> Result<ImageSize, ImageError> {
+        if !bytes.starts_with(&[0xff, 0xd8]) {
+            return Err(ImageError::UnknownFormat);
+        }
+        let mut i = 2;
+        loop {
+            // Markers may be padded with any number of 0xff
+            while bytes.get(i) == Some(&0xff) && bytes.get(i + 1) == Some(&0xff) {
+                i += 1;
+            }
+            let (Some(0xff), Some(&marker)) = (bytes.get(i), bytes.get(i + 1)) else {
+                return Err(ImageError::Truncated);
+            };
+            i += 2;
+            // Markers without a segment
+            if matches!(marker, 0x01 | 0xd0..=0xd9) {
+                continue;
+            }
+            if i + 2 > bytes.len() {
+                return Err(ImageError::Truncated);
+            }
+            let len = u16_be(bytes, i) as usize;
+            // Start of frame markers, except DHT, JPG and DAC that share their range
+            if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                if i + 7 > bytes.len() {
+                    return Err(ImageError::Truncated);
+                }
+                return Ok(ImageSize {
+                    height: u16_be(bytes, i + 3) as Float,
+                    width: u16_be(bytes, i + 5) as Float,
+                });
+            }
+            i += len;
+        }
+    }
+
+    /// Read the size from the logical screen descriptor of a GIF file.
+    pub fn from_gif(bytes: &[u8]) -> Result<ImageSize, ImageError> {
+        if !bytes.starts_with(b"GIF") {
+            return Err(ImageError::UnknownFormat);
+        }
+        if bytes.len() < 10 {
+            return Err(ImageError::Truncated);
+        }
+        let u16_le = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        Ok(ImageSize {
+            width: u16_le(6) as Float,
+            height: u16_le(8) as Float,
+        })
+    }
+}
+
+/// The pixels of a PNG file that are not fully transparent.
+/// [`None`] if every pixel is.
+/// Interlaced files are not supported.
+pub fn png_visible_bounds(bytes: &[u8]) -> Result<Option<Bounds>, ImageError> {
+    if !bytes.starts_with(PNG_SIGNATURE) {
+        return Err(ImageError::UnknownFormat);
+    }
+    let header = png_header(bytes)?;
+    if header.interlaced {
+        return Err(ImageError::Unsupported {
+            reason: "interlaced PNG",
+        });
+    }
+
+    let mut transparency = &[][..];
+    let mut compressed = vec![];
+    let mut i = PNG_SIGNATURE.len();
+    while i + 8 <= bytes.len() {
+        let len = u32_be(bytes, i) as usize;
+        let data = bytes.get(i + 8..i + 8 + len).ok_or(ImageError::Truncated)?;
+        match &bytes[i + 4..i + 8] {
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        // Length, type, data and CRC
+        i += 12 + len;
+    }
+
+    let channels = match header.color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(ImageError::InvalidData),
+    };
+    let depth = header.bit_depth as usize;
+    if !matches!(depth, 1 | 2 | 4 | 8 | 16) {
+        return Err(ImageError::InvalidData);
+    }
+    let width = header.width as usize;
+    // Scanlines of the size in the header, each after its filter byte
+    let stride = (width.checked_mul(channels * depth)).map(|bits| bits.div_ceil(8));
+    let expected = stride.and_then(|stride| (stride + 1).checked_mul(header.height as usize));
+    let (Some(stride), Some(expected)) = (stride, expected) else {
+        return Err(ImageError::InvalidData);
+    };
+    // Filters work on whole pixels, or on bytes when pixels are smaller
+    let pixel_bytes = (channels * depth / 8).max(1);
+
+    // Never inflate more than the image needs, the data may be a decompression bomb
+    let mut data = vec![];
+    ZlibDecoder::new(&compressed[..])
+        .take(expected as u64)
+        .read_to_end(&mut data)
+        .map_err(|_| ImageError::InvalidData)?;
+    if data.len() < expected {
+        return Err(ImageError::Truncated);
+    }
+
+    let mut bounds: Option<Bounds> = None;
+    let mut previous = vec![0; stride];
+    let mut row = vec![0; stride];
+    for y in 0..header.height {
+        let start = y as usize * (stride + 1);
+        let filter = data[start];
+        row.copy_from_slice(&data[start + 1..start + 1 + stride]);
+        unfilter(filter, &mut row, &previous, pixel_bytes)?;
+
+        for x in 0..width {
+            let sample = |channel: usize| sample(&row, x * channels + channel, depth);
+            let visible = match header.color_type {
+                0 => transparency.len() < 2 || sample(0) != u16_be(transparency, 0),
+                2 => {
+                    transparency.len() < 6
+                        || (0..3).any(|c| sample(c) != u16_be(transparency, c * 2))
+                }
+                3 => transparency
+                    .get(sample(0) as usize)
+                    .is_none_or(|&alpha| alpha != 0),
+                _ => sample(channels - 1) != 0,
+            };
+            if !visible {
+                continue;
+            }
+            let x = x as u32;
+            let b = bounds.get_or_insert(Bounds {
+                left: x,
+                top: y,
+                right: x + 1,
+                bottom: y + 1,
+            });
+            b.left = b.left.min(x);
+            b.right = b.right.max(x + 1);
+            b.bottom = y + 1;
+        }
+        std::mem::swap(&mut previous, &mut row);
+    }
+    Ok(bounds)
+}
+
+impl Costume {
+    /// Set the rotation center and [`Costume::bitmap_resolution`] from the image file,
+    /// see [`ImageSize::from_bytes`] and [`Centering`].
+    ///
+    /// Like Scratch 3, bitmaps are double resolution, so the bitmap resolution is 2
+    /// and the rotation center is in pixels of the file. SVG files have a resolution of 1.
+    pub fn center_from_bytes(
+        &mut self,
+        bytes: &[u8],
+        centering: Centering,
+    ) -> Result<(), ImageError> {
+        let size = ImageSize::from_bytes(bytes)?;
+        let is_svg = DataFormat::detect(bytes) == Some(DataFormat::Svg);
+        let visible = match centering {
+            Centering::VisibleBounds if bytes.starts_with(PNG_SIGNATURE) => {
+                png_visible_bounds(bytes)?
+            }
+            _ => None,
+        };
+        let (x, y) = match visible {
+            Some(b) => (
+                (b.left + b.right) as Float / 2.,
+                (b.top + b.bottom) as Float / 2.,
+            ),
+            None => (size.width / 2., size.height / 2.),
+        };
+        self.rotation_center_x = number(x);
+        self.rotation_center_y = number(y);
+        self.bitmap_resolution = Some(if is_svg { 1 } else { 2 });
+        Ok(())
+    }
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// What the `IHDR` chunk of a PNG file says.
+struct PngHeader {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+/// The `IHDR` chunk comes first, right after the signature.
+fn png_header(bytes: &[u8]) -> Result<PngHeader, ImageError> {
+    if bytes.len() < 29 {
+        return Err(ImageError::Truncated);
+    }
+    if &bytes[12..16] != b"IHDR" {
+        return Err(ImageError::InvalidData);
+    }
+    let (width, height) = (u32_be(bytes, 16), u32_be(bytes, 20));
+    // Forbidden by the PNG specification
+    if width == 0 || height == 0 {
+        return Err(ImageError::InvalidData);
+    }
+    Ok(PngHeader {
+        width,
+        height,
+        bit_depth: bytes[24],
+        color_type: bytes[25],
+        interlaced: bytes[28] != 0,
+    })
+}
+
+/// Undo the filter of a PNG scanline, with the previous scanline already unfiltered.
+fn unfilter(
+    filter: u8,
+    row: &mut [u8],
+    previous: &[u8],
+    pixel_bytes: usize,
+) -> Result<(), ImageError> {
+    for i in 0..row.len() {
+        let left = if i >= pixel_bytes {
+            row[i - pixel_bytes]
+        } else {
+            0
+        };
+        let up = previous[i];
+        let up_left = if i >= pixel_bytes {
+            previous[i - pixel_bytes]
+        } else {
+            0
+        };
+        let predicted = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err(ImageError::InvalidData),
+        };
+        row[i] = row[i].wrapping_add(predicted);
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// The `index`th sample of an unfiltered scanline, of 1, 2, 4, 8 or 16 bits.
+fn sample(row: &[u8], index: usize, depth: usize) -> u16 {
+    match depth {
+        16 => u16_be(row, index * 2),
+        8 => row[index] as u16,
+        _ => {
+            let bit = index * depth;
+            let shift = 8 - depth - bit % 8;
+            ((row[bit / 8] >> shift) & ((1 << depth) - 1) as u8) as u16
+        }
+    }
+}
+
+/// The `<svg ...>` start tag, without the brackets. Comments before it are skipped.
+fn svg_tag(text: &str) -> Option<&str> {
+    let mut rest = text;
+    loop {
+        rest = &rest[rest.find('<')?..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = &comment[comment.find("-->")? + 3..];
+            continue;
+        }
+        rest = &rest[1..];
+        if let Some(tag) = rest.strip_prefix("svg") {
+            if tag.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+                return Some(&tag[..tag.find('>')?]);
+            }
+        }
+    }
+}
+
+/// The value of an attribute of a tag, quoted with `"` or `'`.
+fn svg_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    loop {
+        let at = rest.find(name)?;
+        let before = rest[..at].chars().next_back();
+        let after = rest[at + name.len()..].trim_start();
+        rest = &rest[at + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            continue;
+        }
+        let value = &value[1..];
+        return Some(&value[..value.find(quote)?]);
+    }
+}
+
+/// A length in pixels, with or without the `px` unit.
+fn svg_length(length: &str) -> Option<Float> {
+    let length = length.trim();
+    let number = length.strip_suffix("px").unwrap_or(length);
+    number.parse().ok().filter(|n: &Float| *n > 0.)
+}
+
+/// Whole numbers are written without a fraction, like Scratch does.
+fn number(n: Float) -> Number {
+    if n.fract() == 0. && n.abs() < Int::MAX as Float {
+        Number::Int(n as Int)
+    } else {
+        Number::Float(n)
+    }
+}
+
+fn u16_be(bytes: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_be(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+// Error impl ==================================================================
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::UnknownFormat => write!(f, "not an SVG, PNG, JPEG or GIF file"),
+            ImageError::Truncated => write!(f, "the file ends too early"),
+            ImageError::NoSize => write!(f, "the SVG has no viewBox, width or height"),
+            ImageError::InvalidData => write!(f, "invalid PNG data"),
+            ImageError::Unsupported { reason } => write!(f, "unsupported {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
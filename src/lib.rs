@@ -1,11 +1,26 @@
 //! Rust crate with Serde implementation for json part in .sb3 format. (the Scratch 3 project format)
 
+pub mod archive;
 pub mod asset;
+pub mod audio;
+pub mod builder;
+pub mod diff;
+pub mod extension;
+pub mod image;
+pub mod merge;
 pub mod monitor;
+pub mod opcode;
 pub mod project;
+pub mod rename;
+pub mod scratchblocks;
+pub mod script;
 pub mod string_hashmap;
 pub mod target;
+pub mod uid;
+pub mod unused;
+pub mod validate;
 pub mod value;
+pub mod vm;
 
 pub mod block;
 pub mod broadcast;
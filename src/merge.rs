@@ -0,0 +1,746 @@
+//! Module to merge projects
+//!
+//! [`merge`] is a three-way merge, like git does for text, but of what is in a project:
+//! two projects edited from the same base are merged target by target (by name),
+//! then variables, lists, broadcasts and comments by Id, costumes and sounds by name,
+//! whole scripts by the Id of their top block, and every other property by its key.
+//! What both sides changed differently is reported as a [`Conflict`] instead of being mixed up.
+//!
+//! Scripts of one side can still break what the other side changed, such as using a variable
+//! it removed, so what [`Project::validate`] finds in the merged project but on no side
+//! is reported as a conflict too.
+//!
+//! Values of variables, lists and monitors change whenever the project runs,
+//! so they never conflict: ours is kept when both sides changed them.
+//! Same for where scripts are in the code area and which costume a target is wearing.
+
+use crate::archive::AssetKind;
+use crate::block::{Block, DataKind};
+use crate::diff::{asset_name, data_name};
+use crate::monitor::Monitor;
+use crate::prelude::*;
+use crate::project::Project;
+use crate::scratchblocks::render_script;
+use crate::script::scripts;
+use crate::target::{SpriteOrStage, Target};
+use crate::validate::Diagnostic;
+use indexmap::IndexMap;
+use serde_json::Map;
+use std::fmt;
+
+/// Everything [`merge`] could not merge.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Conflicts {
+    /// Conflicts in the order of the targets, monitors and project properties come last
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Something both sides changed differently.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Conflict {
+    /// Name of the target the conflict is in.
+    /// [`None`] for properties of the project and monitors of the stage.
+    pub target: Option<Name>,
+
+    /// See [`Item`]
+    pub item: Item,
+
+    /// See [`ConflictKind`]
+    pub kind: ConflictKind,
+}
+
+/// What is in conflict.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "item", rename_all = "snake_case")]
+pub enum Item {
+    /// The sprite as a whole, such as when one side removed it.
+    Target,
+
+    /// A property of the target or the project.
+    Property {
+        /// Its key in project.json
+        key: String,
+    },
+
+    /// A variable, list or broadcast.
+    Data {
+        /// Variable, list or broadcast
+        kind: DataKind,
+        /// Its name
+        name: Name,
+    },
+
+    /// A comment.
+    Comment {
+        /// Id of the comment
+        id: Uid,
+    },
+
+    /// A costume or sound.
+    Asset {
+        /// Costume or sound
+        kind: AssetKind,
+        /// Its name
+        name: Name,
+    },
+
+    /// A script.
+    Script {
+        /// Id of the top block
+        id: Uid,
+        /// First line of the script as scratchblocks
+        hat: String,
+    },
+
+    /// A monitor.
+    Monitor {
+        /// Id of the monitor
+        id: Uid,
+    },
+
+    /// A problem [`Project::validate`] finds in the merged project.
+    Diagnostic {
+        /// JSON pointer to the offending value in the merged project.json
+        path: String,
+        /// What is wrong
+        message: String,
+    },
+}
+
+/// How both sides changed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// Changed on both sides differently.
+    BothChanged,
+
+    /// Changed on one side and removed on the other.
+    ChangedAndRemoved,
+
+    /// Not in the base but added on both sides differently.
+    BothAdded,
+
+    /// Fine on every side but not once both sides are merged.
+    Merged,
+}
+
+/// Merge what ours and theirs changed from base.
+///
+/// Targets, variables, lists, broadcasts, comments, costumes and sounds are kept in the order of ours,
+/// followed by what only theirs added.
+/// Sprites are given layer orders from 1 in the order of their layer order.
+pub fn merge(base: &Project, ours: &Project, theirs: &Project) -> Result<Project, Conflicts> {
+    let mut merger = Merger { conflicts: vec![] };
+
+    let targets = |project: &Project| -> Vec<(Option<Name>, SpriteOrStage)> {
+        (project.targets.iter())
+            .map(|t| {
+                let key = (!t.is_stage()).then(|| t.target().name.clone());
+                (key, t.clone())
+            })
+            .collect()
+    };
+    let mut targets: Vec<SpriteOrStage> = merge_keyed(
+        &targets(base),
+        &targets(ours),
+        &targets(theirs),
+        |name, b, o, t| match (b, o, t) {
+            (Some(b), Some(o), Some(t)) => Some(merger.target(b, o, t)),
+            _ => {
+                merger.conflict(name.clone(), Item::Target, ConflictKind::of(b, o, t));
+                o.cloned()
+            }
+        },
+    )
+    .into_iter()
+    .map(|(_, t)| t)
+    .collect();
+    let mut sprites: Vec<&mut Target> = (targets.iter_mut())
+        .filter(|t| !t.is_stage())
+        .map(|t| t.target_mut())
+        .collect();
+    sprites.sort_by_key(|t| t.layer_order);
+    for (i, sprite) in sprites.into_iter().enumerate() {
+        sprite.layer_order = i as Int + 1;
+    }
+
+    let monitors = |project: &Project| -> Vec<(Uid, Monitor)> {
+        (project.monitors.iter())
+            .map(|m| (m.id.clone(), m.clone()))
+            .collect()
+    };
+    let monitors = merge_keyed(
+        &monitors(base),
+        &monitors(ours),
+        &monitors(theirs),
+        |id, b, o, t| {
+            if let (Some(b), Some(o), Some(t)) = (b, o, t) {
+                let [b, o, t] = [b, o, t].map(|m| match serde_json::to_value(m) {
+                    Ok(Json::Object(object)) => object,
+                    _ => unreachable!("monitors serialize to objects"),
+                });
+                let (merged, keys) = merge_object(&b, &o, &t);
+                if keys.iter().all(|key| key == "value") {
+                    if let Ok(merged) = serde_json::from_value(Json::Object(merged)) {
+                        return Some(merged);
+                    }
+                }
+            }
+            let monitor = o.or(t).or(b).expect("the monitor is on some side");
+            merger.conflicts.push(Conflict {
+                target: monitor.sprite_name.clone(),
+                item: Item::Monitor { id: id.clone() },
+                kind: ConflictKind::of(b, o, t),
+            });
+            o.cloned()
+        },
+    )
+    .into_iter()
+    .map(|(_, m)| m)
+    .collect();
+
+    let extensions = |project: &Project| -> Vec<_> {
+        project.extensions.iter().map(|e| (e.clone(), ())).collect()
+    };
+    let extensions = merge_keyed(
+        &extensions(base),
+        &extensions(ours),
+        &extensions(theirs),
+        |_, _, o, _| o.copied(),
+    )
+    .into_iter()
+    .map(|(e, ())| e)
+    .collect();
+
+    let extra =
+        |project: &Project| -> Map<String, Json> { project.extra.0.clone().into_iter().collect() };
+    let (extra, keys) = merge_object(&extra(base), &extra(ours), &extra(theirs));
+    for key in keys {
+        merger.conflict(None, Item::Property { key }, ConflictKind::BothChanged);
+    }
+
+    let merged = Project {
+        meta: pick(&base.meta, &ours.meta, &theirs.meta)
+            .unwrap_or(&ours.meta)
+            .clone(),
+        extensions,
+        monitors,
+        targets,
+        extra: extra.into_iter().collect(),
+    };
+    merger.diagnostics(&merged, [base, ours, theirs]);
+
+    if !merger.conflicts.is_empty() {
+        return Err(Conflicts {
+            conflicts: merger.conflicts,
+        });
+    }
+    Ok(merged)
+}
+
+/// Keys of the targets merged apart from the other properties.
+const COLLECTIONS: [&str; 8] = [
+    "variables",
+    "lists",
+    "broadcasts",
+    "blocks",
+    "comments",
+    "costumes",
+    "sounds",
+    "currentCostume",
+];
+
+struct Merger {
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger {
+    fn conflict(&mut self, target: Option<Name>, item: Item, kind: ConflictKind) {
+        self.conflicts.push(Conflict { target, item, kind });
+    }
+
+    /// Merge a target that is on every side.
+    fn target(
+        &mut self,
+        base: &SpriteOrStage,
+        ours: &SpriteOrStage,
+        theirs: &SpriteOrStage,
+    ) -> SpriteOrStage {
+        let name = Some(ours.target().name.clone());
+        let [b, o, t] = [base, ours, theirs].map(|t| t.target());
+
+        let [base_json, ours_json, theirs_json] = [base, ours, theirs].map(|t| {
+            let Ok(Json::Object(mut object)) = serde_json::to_value(t) else {
+                unreachable!("targets serialize to objects")
+            };
+            object.retain(|key, _| !COLLECTIONS.contains(&key.as_str()));
+            object
+        });
+        let (properties, keys) = merge_object(&base_json, &ours_json, &theirs_json);
+        // Layer orders are given again after the merge
+        for key in keys.into_iter().filter(|key| key != "layerOrder") {
+            let kind = ConflictKind::BothChanged;
+            self.conflict(name.clone(), Item::Property { key }, kind);
+        }
+        let mut merged = serde_json::to_value(ours).expect("targets serialize");
+        if let Json::Object(object) = &mut merged {
+            object.retain(|key, _| COLLECTIONS.contains(&key.as_str()));
+            object.extend(properties);
+        }
+        let mut merged: SpriteOrStage = match serde_json::from_value(merged) {
+            Ok(merged) => merged,
+            Err(_) => {
+                self.conflict(name, Item::Target, ConflictKind::BothChanged);
+                return ours.clone();
+            }
+        };
+        let target = merged.target_mut();
+
+        target.variables = self.data(
+            &name,
+            DataKind::Variable,
+            [&b.variables, &o.variables, &t.variables],
+            |v| &v.name,
+            |v| &mut v.value,
+        );
+        target.lists = self.data(
+            &name,
+            DataKind::List,
+            [&b.lists, &o.lists, &t.lists],
+            |l| &l.name,
+            |l| &mut l.values,
+        );
+        target.broadcasts = self.map(
+            &name,
+            [&b.broadcasts, &o.broadcasts, &t.broadcasts],
+            |_, b| Item::Data {
+                kind: DataKind::Broadcast,
+                name: b.name.clone(),
+            },
+        );
+        target.blocks = self.blocks(&name, [&b.blocks, &o.blocks, &t.blocks]);
+        target.comments = self.map(&name, [&b.comments, &o.comments, &t.comments], |id, _| {
+            Item::Comment { id: id.clone() }
+        });
+
+        let costumes = |t: &Target| -> Vec<_> {
+            (t.costumes.iter())
+                .map(|c| (c.asset.name.clone(), c.clone()))
+                .collect()
+        };
+        target.costumes = merge_keyed(&costumes(b), &costumes(o), &costumes(t), |n, b, o, t| {
+            let item = Item::Asset {
+                kind: AssetKind::Costume,
+                name: n.clone(),
+            };
+            self.conflict(name.clone(), item, ConflictKind::of(b, o, t));
+            o.cloned()
+        })
+        .into_iter()
+        .map(|(_, c)| c)
+        .collect();
+        let sounds = |t: &Target| -> Vec<_> {
+            (t.sounds.iter())
+                .map(|s| (s.asset.name.clone(), s.clone()))
+                .collect()
+        };
+        target.sounds = merge_keyed(&sounds(b), &sounds(o), &sounds(t), |n, b, o, t| {
+            let item = Item::Asset {
+                kind: AssetKind::Sound,
+                name: n.clone(),
+            };
+            self.conflict(name.clone(), item, ConflictKind::of(b, o, t));
+            o.cloned()
+        })
+        .into_iter()
+        .map(|(_, s)| s)
+        .collect();
+
+        // The costume worn is followed by name, as costumes may have moved
+        let wearing = |t: &Target| {
+            let current = usize::try_from(t.current_costume).ok()?;
+            Some(t.costumes.get(current)?.asset.name.clone())
+        };
+        let [b, o, t] = [b, o, t].map(wearing);
+        let wearing = pick(&b, &o, &t).unwrap_or(&o);
+        target.current_costume = (target.costumes.iter())
+            .position(|c| Some(&c.asset.name) == wearing.as_ref())
+            .unwrap_or(0) as Int;
+
+        merged
+    }
+
+    /// Merge a map of items that are changed as a whole.
+    fn map<V>(
+        &mut self,
+        target: &Option<Name>,
+        [base, ours, theirs]: [&StringHashMap<V>; 3],
+        item: impl Fn(&Uid, &V) -> Item,
+    ) -> StringHashMap<V>
+    where
+        V: Clone + PartialEq,
+    {
+        let entries = |map: &StringHashMap<V>| -> Vec<(Uid, V)> {
+            map.0
+                .iter()
+                .map(|(id, v)| (id.clone(), v.clone()))
+                .collect()
+        };
+        merge_keyed(
+            &entries(base),
+            &entries(ours),
+            &entries(theirs),
+            |id, b, o, t| {
+                let item = item(id, o.or(t).or(b).expect("the item is on some side"));
+                self.conflict(target.clone(), item, ConflictKind::of(b, o, t));
+                o.cloned()
+            },
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// Merge variables or lists, their values apart from the rest.
+    fn data<V, W>(
+        &mut self,
+        target: &Option<Name>,
+        kind: DataKind,
+        [base, ours, theirs]: [&StringHashMap<V>; 3],
+        name: fn(&V) -> &Name,
+        value: fn(&mut V) -> &mut W,
+    ) -> StringHashMap<V>
+    where
+        V: Clone + PartialEq,
+        W: Clone + PartialEq + Default,
+    {
+        let declarations = |map: &StringHashMap<V>| -> StringHashMap<V> {
+            (map.0.iter())
+                .map(|(id, v)| {
+                    let mut v = v.clone();
+                    std::mem::take(value(&mut v));
+                    (id.clone(), v)
+                })
+                .collect()
+        };
+        let values = |map: &StringHashMap<V>, id: &Uid| {
+            let mut v = map.0.get(id)?.clone();
+            Some(std::mem::take(value(&mut v)))
+        };
+        let merged = self.map(
+            target,
+            [base, ours, theirs].map(declarations).each_ref(),
+            |_, v| Item::Data {
+                kind,
+                name: name(v).clone(),
+            },
+        );
+        (merged.0.into_iter())
+            .map(|(id, mut v)| {
+                let [b, o, t] = [base, ours, theirs].map(|map| values(map, &id));
+                let merged = pick(&b, &o, &t).cloned().flatten();
+                *value(&mut v) = merged.or(o).or(t).unwrap_or_default();
+                (id, v)
+            })
+            .collect()
+    }
+
+    /// Merge whole scripts by the Id of their top block.
+    fn blocks(
+        &mut self,
+        target: &Option<Name>,
+        sides: [&StringHashMap<Block>; 3],
+    ) -> StringHashMap<Block> {
+        let [base, ours, theirs] = sides.map(script_blocks);
+        let merged = merge_keyed(&base, &ours, &theirs, |id, b, o, t| {
+            if let (Some(b), Some(o), Some(t)) = (b, o, t) {
+                if let Some(blocks) = pick(&b.blocks, &o.blocks, &t.blocks) {
+                    // Moved on both sides
+                    return Some(ScriptBlocks {
+                        blocks: blocks.clone(),
+                        at: o.at,
+                    });
+                }
+            }
+            // Only moved on the side that did not remove it
+            if let (Some(b), None, Some(other)) | (Some(b), Some(other), None) = (b, o, t) {
+                if b.blocks == other.blocks {
+                    return None;
+                }
+            }
+            let script = o.or(t).or(b).expect("the script is on some side");
+            let item = Item::Script {
+                id: id.clone(),
+                hat: script.hat(id),
+            };
+            self.conflict(target.clone(), item, ConflictKind::of(b, o, t));
+            o.cloned()
+        });
+        let mut blocks = StringHashMap::default();
+        for (id, script) in merged {
+            let hat = script.hat(&id);
+            let mut shared = false;
+            for (block_id, block) in script.into_blocks(&id) {
+                // Each side moved the block to a different script
+                if blocks.0.contains_key(&block_id) {
+                    shared = true;
+                    continue;
+                }
+                blocks.0.insert(block_id, block);
+            }
+            if shared {
+                let item = Item::Script { id, hat };
+                self.conflict(target.clone(), item, ConflictKind::BothChanged);
+            }
+        }
+        blocks
+    }
+
+    /// Report what [`Project::validate`] finds in the merged project but on no side.
+    fn diagnostics(&mut self, merged: &Project, sides: [&Project; 3]) {
+        let known: Vec<_> = (sides.into_iter())
+            .flat_map(|project| {
+                (project.validate().into_iter())
+                    .map(|d| {
+                        let (target, path) = locate(project, &d);
+                        (target, path.to_owned(), d.kind)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for diagnostic in merged.validate() {
+            let (target, path) = locate(merged, &diagnostic);
+            let is_known = (known.iter())
+                .any(|(t, p, kind)| *t == target && p == path && *kind == diagnostic.kind);
+            if !is_known {
+                let item = Item::Diagnostic {
+                    message: diagnostic.kind.to_string(),
+                    path: diagnostic.path,
+                };
+                self.conflict(target, item, ConflictKind::Merged);
+            }
+        }
+    }
+}
+
+/// Name of the target a diagnostic is in and its path within the target,
+/// as indices of targets differ between sides.
+/// Other diagnostics are told apart by their [`DiagnosticKind`](crate::validate::DiagnosticKind) alone.
+fn locate<'a>(project: &Project, diagnostic: &'a Diagnostic) -> (Option<Name>, &'a str) {
+    let Some(path) = diagnostic.path.strip_prefix("/targets/") else {
+        return (None, "");
+    };
+    let (index, path) = path.split_once('/').unwrap_or((path, ""));
+    let target = (index.parse::<usize>().ok())
+        .and_then(|i| project.targets.get(i))
+        .map(|t| t.target().name.clone());
+    (target, path)
+}
+
+/// The blocks of a script, without where it is in the code area.
+#[derive(Debug, Clone, PartialEq)]
+struct ScriptBlocks {
+    blocks: Blocks,
+    at: (Option<Number>, Option<Number>),
+}
+
+/// Blocks by Id, the same whatever their order.
+#[derive(Debug, Clone)]
+struct Blocks(Vec<(Uid, Block)>);
+
+impl PartialEq for Blocks {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().all(|block| other.0.contains(block))
+    }
+}
+
+impl ScriptBlocks {
+    /// First line of the script as scratchblocks, or the opcode of the top block.
+    fn hat(&self, id: &Uid) -> String {
+        let blocks = self.clone().into_blocks(id).into_iter().collect();
+        let script = scripts(&blocks).into_iter().find(|s| s.id() == id);
+        match script.map(render_script) {
+            Some(Ok(text)) => text.lines().next().unwrap_or_default().to_owned(),
+            _ => match blocks.0.get(id) {
+                Some(Block::Normal(b)) => b.opcode.clone(),
+                _ => id.clone(),
+            },
+        }
+    }
+
+    fn into_blocks(self, id: &Uid) -> Vec<(Uid, Block)> {
+        let mut blocks = self.blocks.0;
+        if let Some((_, block)) = blocks.iter_mut().find(|(i, _)| i == id) {
+            match block {
+                Block::Normal(b) => (b.x, b.y) = self.at,
+                Block::VarList(b) => {
+                    let (x, y) = self.at;
+                    (b.x, b.y) = (x.unwrap_or_default(), y.unwrap_or_default());
+                }
+            }
+        }
+        blocks
+    }
+}
+
+/// Blocks grouped by the Id of the top block of their script.
+fn script_blocks(blocks: &StringHashMap<Block>) -> Vec<(Uid, ScriptBlocks)> {
+    let mut scripts: IndexMap<Uid, ScriptBlocks> = IndexMap::new();
+    for (id, block) in &blocks.0 {
+        let top = top(blocks, id);
+        let script = scripts.entry(top.clone()).or_insert(ScriptBlocks {
+            blocks: Blocks(vec![]),
+            at: (None, None),
+        });
+        let mut block = block.clone();
+        if top == id {
+            script.at = match &mut block {
+                Block::Normal(b) => (b.x.take(), b.y.take()),
+                Block::VarList(b) => (
+                    Some(std::mem::take(&mut b.x)),
+                    Some(std::mem::take(&mut b.y)),
+                ),
+            };
+        }
+        script.blocks.0.push((id.clone(), block));
+    }
+    scripts.into_iter().collect()
+}
+
+/// Id of the top block of the script the block is in, following parents that are in the map.
+fn top<'a>(blocks: &'a StringHashMap<Block>, mut id: &'a Uid) -> &'a Uid {
+    // Bounded in case parents loop
+    for _ in 0..blocks.0.len() {
+        match blocks.0.get(id) {
+            Some(Block::Normal(b)) => match &b.parent {
+                Some(parent) if blocks.0.contains_key(parent) => id = parent,
+                _ => break,
+            },
+            _ => break,
+        }
+    }
+    id
+}
+
+/// Merge items by key, in the order of ours then what only theirs added.
+/// `conflict` is given the key and each side of items both sides changed differently,
+/// and returns what to keep.
+fn merge_keyed<K, V, F>(
+    base: &[(K, V)],
+    ours: &[(K, V)],
+    theirs: &[(K, V)],
+    mut conflict: F,
+) -> Vec<(K, V)>
+where
+    K: Clone + PartialEq,
+    V: Clone + PartialEq,
+    F: FnMut(&K, Option<&V>, Option<&V>, Option<&V>) -> Option<V>,
+{
+    fn get<'a, K: PartialEq, V>(items: &'a [(K, V)], key: &K) -> Option<&'a V> {
+        items.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    let added = (theirs.iter()).filter(|(key, _)| get(ours, key).is_none());
+    let mut merged = vec![];
+    for (key, _) in ours.iter().chain(added) {
+        let (b, o, t) = (get(base, key), get(ours, key), get(theirs, key));
+        let item = match pick(b, o, t) {
+            Some(item) => item.cloned(),
+            None => conflict(key, b, o, t),
+        };
+        if let Some(item) = item {
+            merged.push((key.clone(), item));
+        }
+    }
+    merged
+}
+
+/// Merge JSON objects by key, with the keys both sides changed differently, which are kept as ours.
+fn merge_object(
+    base: &Map<String, Json>,
+    ours: &Map<String, Json>,
+    theirs: &Map<String, Json>,
+) -> (Map<String, Json>, Vec<String>) {
+    let entries = |object: &Map<String, Json>| -> Vec<(String, Json)> {
+        object.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    };
+    let mut keys = vec![];
+    let merged = merge_keyed(
+        &entries(base),
+        &entries(ours),
+        &entries(theirs),
+        |key, _, o, _| {
+            keys.push(key.clone());
+            o.cloned()
+        },
+    );
+    (merged.into_iter().collect(), keys)
+}
+
+/// The side that changed, [`None`] if both changed differently.
+fn pick<T: PartialEq>(base: T, ours: T, theirs: T) -> Option<T> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+impl ConflictKind {
+    fn of<V>(base: Option<V>, ours: Option<V>, theirs: Option<V>) -> ConflictKind {
+        match (base, ours, theirs) {
+            (None, _, _) => ConflictKind::BothAdded,
+            (Some(_), Some(_), Some(_)) => ConflictKind::BothChanged,
+            _ => ConflictKind::ChangedAndRemoved,
+        }
+    }
+}
+
+// Display impl ================================================================
+
+impl fmt::Display for Conflicts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for conflict in &self.conflicts {
+            writeln!(f, "{conflict}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(target) = &self.target {
+            write!(f, "{target}: ")?;
+        }
+        write!(f, "{} {}", self.item, self.kind)
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Target => write!(f, "sprite"),
+            Item::Property { key } => write!(f, "property {key}"),
+            Item::Data { kind, name } => write!(f, "{} {name}", data_name(*kind)),
+            Item::Comment { id } => write!(f, "comment {id}"),
+            Item::Asset { kind, name } => write!(f, "{} {name}", asset_name(*kind)),
+            Item::Script { hat, .. } => write!(f, "script `{hat}`"),
+            Item::Monitor { id } => write!(f, "monitor {id}"),
+            Item::Diagnostic { path, message } => write!(f, "{path}: {message}"),
+        }
+    }
+}
+
+impl fmt::Display for ConflictKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConflictKind::BothChanged => "changed on both sides",
+            ConflictKind::ChangedAndRemoved => "changed on one side and removed on the other",
+            ConflictKind::BothAdded => "added on both sides",
+            ConflictKind::Merged => "once both sides are merged",
+        })
+    }
+}
+
+impl std::error::Error for Conflicts {}
@@ -46,6 +46,10 @@ pub struct Monitor {
     /// [`None`] if [`Mode`] is [`Mode::List`]
     #[serde(flatten)]
     pub slider: Option<Slider>,
+
+    /// Unknown keys, see [`StringHashMap`]
+    #[serde(flatten)]
+    pub extra: StringHashMap<Json>,
 }
 
 /// Monitor's Mode
@@ -133,10 +137,11 @@ impl<'de> Visitor<'de> for ParameterVisitor {
         A: serde::de::MapAccess<'de>,
     {
         use serde::de::Error;
-        if let Some((k, v)) = map.next_entry::<&str, &str>()? {
-            Ok(match (k, v) {
-                ("VARIABLE", v) => Parameter::Variable(v.to_owned()),
-                ("LIST", v) => Parameter::List(v.into()),
+        // Owned strings so this also works with deserializers that cannot borrow, like `serde_json::Value`
+        if let Some((k, v)) = map.next_entry::<String, String>()? {
+            Ok(match (k.as_str(), v.as_str()) {
+                ("VARIABLE", _) => Parameter::Variable(v),
+                ("LIST", _) => Parameter::List(v),
                 ("NUMBER_NAME", "name") => Parameter::NumberName(NumberName::Name),
                 ("NUMBER_NAME", "number") => Parameter::NumberName(NumberName::Number),
                 (k, _) => {
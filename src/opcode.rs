@@ -0,0 +1,498 @@
+//! Module to deal with Scratch opcode
+//!
+//! [`KnownOpcode`] is a catalog of the opcodes of vanilla Scratch 3 and its built-in extensions.
+//! Every opcode knows its [`Category`], its [`Shape`] and the names of the inputs and fields it expects.
+
+use crate::prelude::*;
+use std::fmt;
+
+/// Palette category of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Motion, blocks starting with `motion_`.
+    Motion,
+
+    /// Looks, blocks starting with `looks_`.
+    Looks,
+
+    /// Sound, blocks starting with `sound_`.
+    Sound,
+
+    /// Events, blocks starting with `event_`.
+    Events,
+
+    /// Control, blocks starting with `control_`.
+    Control,
+
+    /// Sensing, blocks starting with `sensing_`.
+    Sensing,
+
+    /// Operators, blocks starting with `operator_`.
+    Operators,
+
+    /// Variables and lists, blocks starting with `data_`.
+    Data,
+
+    /// My Blocks, blocks starting with `procedures_`.
+    Procedures,
+
+    /// Parameters of custom blocks, blocks starting with `argument_`.
+    Argument,
+
+    /// The Pen extension.
+    Pen,
+
+    /// The Music extension.
+    Music,
+
+    /// The Video Sensing extension.
+    VideoSensing,
+
+    /// The Text to Speech extension.
+    TextToSpeech,
+
+    /// The Translate extension.
+    Translate,
+}
+
+/// Shape of a block, which also tells how it connects to other blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shape {
+    /// Starts a script, nothing can go above it.
+    Hat,
+
+    /// Goes in a stack of blocks.
+    Stack,
+
+    /// Has one or more mouths that hold a stack of blocks.
+    C,
+
+    /// Ends a script, nothing can go below it.
+    Cap,
+
+    /// Round block that reports a value. Menus are reporters too.
+    Reporter,
+
+    /// Hexagonal block that reports true or false.
+    Boolean,
+}
+
+macro_rules! opcodes {
+    ($(
+        $(#[$attr:meta])*
+        $variant:ident = $name:literal, $category:ident, $shape:ident,
+            [$($input:literal),*], [$($field:literal),*];
+    )*) => {
+        /// Opcode of vanilla Scratch 3 and its built-in extensions.
+        /// [`KnownOpcode::Unknown`] for anything else.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum KnownOpcode {
+            $(
+                $(#[$attr])*
+                #[doc = concat!("`", $name, "`")]
+                $variant,
+            )*
+            /// Opcode that is not in the catalog, such as from a custom extension.
+            Unknown(OpCode),
+        }
+
+        impl KnownOpcode {
+            /// Every known opcode.
+            pub const ALL: &'static [KnownOpcode] = &[$(KnownOpcode::$variant),*];
+
+            /// The opcode as it appears in project.json.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(KnownOpcode::$variant => $name,)*
+                    KnownOpcode::Unknown(s) => s,
+                }
+            }
+
+            /// See [`Category`].
+            /// Unknown opcodes are categorized by their prefix, if it is a known one.
+            pub fn category(&self) -> Option<Category> {
+                match self {
+                    $(KnownOpcode::$variant => Some(Category::$category),)*
+                    KnownOpcode::Unknown(s) => Category::from_opcode(s),
+                }
+            }
+
+            /// See [`Shape`]. [`None`] for unknown opcodes.
+            pub fn shape(&self) -> Option<Shape> {
+                match self {
+                    $(KnownOpcode::$variant => Some(Shape::$shape),)*
+                    KnownOpcode::Unknown(_) => None,
+                }
+            }
+
+            /// Names of the inputs the block expects.
+            /// Custom blocks name their inputs by argument Id so they have none here.
+            pub fn inputs(&self) -> &'static [&'static str] {
+                match self {
+                    $(KnownOpcode::$variant => &[$($input),*],)*
+                    KnownOpcode::Unknown(_) => &[],
+                }
+            }
+
+            /// Names of the fields the block expects.
+            pub fn fields(&self) -> &'static [&'static str] {
+                match self {
+                    $(KnownOpcode::$variant => &[$($field),*],)*
+                    KnownOpcode::Unknown(_) => &[],
+                }
+            }
+        }
+
+        impl From<&str> for KnownOpcode {
+            fn from(s: &str) -> Self {
+                match s {
+                    $($name => KnownOpcode::$variant,)*
+                    s => KnownOpcode::Unknown(s.to_owned()),
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    // Motion ==================================================================
+    MotionMovesteps = "motion_movesteps", Motion, Stack, ["STEPS"], [];
+    MotionTurnright = "motion_turnright", Motion, Stack, ["DEGREES"], [];
+    MotionTurnleft = "motion_turnleft", Motion, Stack, ["DEGREES"], [];
+    MotionGoto = "motion_goto", Motion, Stack, ["TO"], [];
+    MotionGotoMenu = "motion_goto_menu", Motion, Reporter, [], ["TO"];
+    MotionGotoxy = "motion_gotoxy", Motion, Stack, ["X", "Y"], [];
+    MotionGlideto = "motion_glideto", Motion, Stack, ["SECS", "TO"], [];
+    MotionGlidetoMenu = "motion_glideto_menu", Motion, Reporter, [], ["TO"];
+    MotionGlidesecstoxy = "motion_glidesecstoxy", Motion, Stack, ["SECS", "X", "Y"], [];
+    MotionPointindirection = "motion_pointindirection", Motion, Stack, ["DIRECTION"], [];
+    MotionPointtowards = "motion_pointtowards", Motion, Stack, ["TOWARDS"], [];
+    MotionPointtowardsMenu = "motion_pointtowards_menu", Motion, Reporter, [], ["TOWARDS"];
+    MotionChangexby = "motion_changexby", Motion, Stack, ["DX"], [];
+    MotionSetx = "motion_setx", Motion, Stack, ["X"], [];
+    MotionChangeyby = "motion_changeyby", Motion, Stack, ["DY"], [];
+    MotionSety = "motion_sety", Motion, Stack, ["Y"], [];
+    MotionIfonedgebounce = "motion_ifonedgebounce", Motion, Stack, [], [];
+    MotionSetrotationstyle = "motion_setrotationstyle", Motion, Stack, [], ["STYLE"];
+    MotionXposition = "motion_xposition", Motion, Reporter, [], [];
+    MotionYposition = "motion_yposition", Motion, Reporter, [], [];
+    MotionDirection = "motion_direction", Motion, Reporter, [], [];
+    /// Only found in projects converted from Scratch 2.0
+    MotionScrollRight = "motion_scroll_right", Motion, Stack, ["DISTANCE"], [];
+    /// Only found in projects converted from Scratch 2.0
+    MotionScrollUp = "motion_scroll_up", Motion, Stack, ["DISTANCE"], [];
+    /// Only found in projects converted from Scratch 2.0
+    MotionAlignScene = "motion_align_scene", Motion, Stack, [], ["ALIGNMENT"];
+    /// Only found in projects converted from Scratch 2.0
+    MotionXscroll = "motion_xscroll", Motion, Reporter, [], [];
+    /// Only found in projects converted from Scratch 2.0
+    MotionYscroll = "motion_yscroll", Motion, Reporter, [], [];
+
+    // Looks ===================================================================
+    LooksSayforsecs = "looks_sayforsecs", Looks, Stack, ["MESSAGE", "SECS"], [];
+    LooksSay = "looks_say", Looks, Stack, ["MESSAGE"], [];
+    LooksThinkforsecs = "looks_thinkforsecs", Looks, Stack, ["MESSAGE", "SECS"], [];
+    LooksThink = "looks_think", Looks, Stack, ["MESSAGE"], [];
+    LooksSwitchcostumeto = "looks_switchcostumeto", Looks, Stack, ["COSTUME"], [];
+    LooksCostume = "looks_costume", Looks, Reporter, [], ["COSTUME"];
+    LooksNextcostume = "looks_nextcostume", Looks, Stack, [], [];
+    LooksSwitchbackdropto = "looks_switchbackdropto", Looks, Stack, ["BACKDROP"], [];
+    LooksBackdrops = "looks_backdrops", Looks, Reporter, [], ["BACKDROP"];
+    LooksSwitchbackdroptoandwait = "looks_switchbackdroptoandwait", Looks, Stack, ["BACKDROP"], [];
+    LooksNextbackdrop = "looks_nextbackdrop", Looks, Stack, [], [];
+    LooksChangesizeby = "looks_changesizeby", Looks, Stack, ["CHANGE"], [];
+    LooksSetsizeto = "looks_setsizeto", Looks, Stack, ["SIZE"], [];
+    LooksChangeeffectby = "looks_changeeffectby", Looks, Stack, ["CHANGE"], ["EFFECT"];
+    LooksSeteffectto = "looks_seteffectto", Looks, Stack, ["VALUE"], ["EFFECT"];
+    LooksCleargraphiceffects = "looks_cleargraphiceffects", Looks, Stack, [], [];
+    LooksShow = "looks_show", Looks, Stack, [], [];
+    LooksHide = "looks_hide", Looks, Stack, [], [];
+    LooksGotofrontback = "looks_gotofrontback", Looks, Stack, [], ["FRONT_BACK"];
+    LooksGoforwardbackwardlayers = "looks_goforwardbackwardlayers", Looks, Stack, ["NUM"], ["FORWARD_BACKWARD"];
+    LooksCostumenumbername = "looks_costumenumbername", Looks, Reporter, [], ["NUMBER_NAME"];
+    LooksBackdropnumbername = "looks_backdropnumbername", Looks, Reporter, [], ["NUMBER_NAME"];
+    LooksSize = "looks_size", Looks, Reporter, [], [];
+    /// Only found in projects converted from Scratch 2.0
+    LooksHideallsprites = "looks_hideallsprites", Looks, Stack, [], [];
+    /// Only found in projects converted from Scratch 2.0
+    LooksSetstretchto = "looks_setstretchto", Looks, Stack, ["STRETCH"], [];
+    /// Only found in projects converted from Scratch 2.0
+    LooksChangestretchby = "looks_changestretchby", Looks, Stack, ["CHANGE"], [];
+
+    // Sound ===================================================================
+    SoundPlayuntildone = "sound_playuntildone", Sound, Stack, ["SOUND_MENU"], [];
+    SoundPlay = "sound_play", Sound, Stack, ["SOUND_MENU"], [];
+    SoundSoundsMenu = "sound_sounds_menu", Sound, Reporter, [], ["SOUND_MENU"];
+    SoundStopallsounds = "sound_stopallsounds", Sound, Stack, [], [];
+    SoundChangeeffectby = "sound_changeeffectby", Sound, Stack, ["VALUE"], ["EFFECT"];
+    SoundSeteffectto = "sound_seteffectto", Sound, Stack, ["VALUE"], ["EFFECT"];
+    SoundCleareffects = "sound_cleareffects", Sound, Stack, [], [];
+    SoundChangevolumeby = "sound_changevolumeby", Sound, Stack, ["VOLUME"], [];
+    SoundSetvolumeto = "sound_setvolumeto", Sound, Stack, ["VOLUME"], [];
+    SoundVolume = "sound_volume", Sound, Reporter, [], [];
+
+    // Events ==================================================================
+    EventWhenflagclicked = "event_whenflagclicked", Events, Hat, [], [];
+    EventWhenkeypressed = "event_whenkeypressed", Events, Hat, [], ["KEY_OPTION"];
+    EventWhenthisspriteclicked = "event_whenthisspriteclicked", Events, Hat, [], [];
+    EventWhenstageclicked = "event_whenstageclicked", Events, Hat, [], [];
+    EventWhenbackdropswitchesto = "event_whenbackdropswitchesto", Events, Hat, [], ["BACKDROP"];
+    EventWhengreaterthan = "event_whengreaterthan", Events, Hat, ["VALUE"], ["WHENGREATERTHANMENU"];
+    EventWhenbroadcastreceived = "event_whenbroadcastreceived", Events, Hat, [], ["BROADCAST_OPTION"];
+    EventBroadcast = "event_broadcast", Events, Stack, ["BROADCAST_INPUT"], [];
+    EventBroadcastandwait = "event_broadcastandwait", Events, Stack, ["BROADCAST_INPUT"], [];
+    EventBroadcastMenu = "event_broadcast_menu", Events, Reporter, [], ["BROADCAST_OPTION"];
+    EventWhentouchingobject = "event_whentouchingobject", Events, Hat, ["TOUCHINGOBJECTMENU"], [];
+    EventTouchingobjectmenu = "event_touchingobjectmenu", Events, Reporter, [], ["TOUCHINGOBJECTMENU"];
+
+    // Control =================================================================
+    ControlWait = "control_wait", Control, Stack, ["DURATION"], [];
+    ControlRepeat = "control_repeat", Control, C, ["TIMES", "SUBSTACK"], [];
+    ControlForever = "control_forever", Control, C, ["SUBSTACK"], [];
+    ControlIf = "control_if", Control, C, ["CONDITION", "SUBSTACK"], [];
+    ControlIfElse = "control_if_else", Control, C, ["CONDITION", "SUBSTACK", "SUBSTACK2"], [];
+    ControlWaitUntil = "control_wait_until", Control, Stack, ["CONDITION"], [];
+    ControlRepeatUntil = "control_repeat_until", Control, C, ["CONDITION", "SUBSTACK"], [];
+    ControlWhile = "control_while", Control, C, ["CONDITION", "SUBSTACK"], [];
+    ControlForEach = "control_for_each", Control, C, ["VALUE", "SUBSTACK"], ["VARIABLE"];
+    /// A stack block when the mutation says it has next, see [`crate::block::BlockMutationEnum::ControlStop`]
+    ControlStop = "control_stop", Control, Cap, [], ["STOP_OPTION"];
+    ControlStartAsClone = "control_start_as_clone", Control, Hat, [], [];
+    ControlCreateCloneOf = "control_create_clone_of", Control, Stack, ["CLONE_OPTION"], [];
+    ControlCreateCloneOfMenu = "control_create_clone_of_menu", Control, Reporter, [], ["CLONE_OPTION"];
+    ControlDeleteThisClone = "control_delete_this_clone", Control, Cap, [], [];
+    ControlGetCounter = "control_get_counter", Control, Reporter, [], [];
+    ControlIncrCounter = "control_incr_counter", Control, Stack, [], [];
+    ControlClearCounter = "control_clear_counter", Control, Stack, [], [];
+    ControlAllAtOnce = "control_all_at_once", Control, C, ["SUBSTACK"], [];
+
+    // Sensing =================================================================
+    SensingTouchingobject = "sensing_touchingobject", Sensing, Boolean, ["TOUCHINGOBJECTMENU"], [];
+    SensingTouchingobjectmenu = "sensing_touchingobjectmenu", Sensing, Reporter, [], ["TOUCHINGOBJECTMENU"];
+    SensingTouchingcolor = "sensing_touchingcolor", Sensing, Boolean, ["COLOR"], [];
+    SensingColoristouchingcolor = "sensing_coloristouchingcolor", Sensing, Boolean, ["COLOR", "COLOR2"], [];
+    SensingDistanceto = "sensing_distanceto", Sensing, Reporter, ["DISTANCETOMENU"], [];
+    SensingDistancetomenu = "sensing_distancetomenu", Sensing, Reporter, [], ["DISTANCETOMENU"];
+    SensingAskandwait = "sensing_askandwait", Sensing, Stack, ["QUESTION"], [];
+    SensingAnswer = "sensing_answer", Sensing, Reporter, [], [];
+    SensingKeypressed = "sensing_keypressed", Sensing, Boolean, ["KEY_OPTION"], [];
+    SensingKeyoptions = "sensing_keyoptions", Sensing, Reporter, [], ["KEY_OPTION"];
+    SensingMousedown = "sensing_mousedown", Sensing, Boolean, [], [];
+    SensingMousex = "sensing_mousex", Sensing, Reporter, [], [];
+    SensingMousey = "sensing_mousey", Sensing, Reporter, [], [];
+    SensingSetdragmode = "sensing_setdragmode", Sensing, Stack, [], ["DRAG_MODE"];
+    SensingLoudness = "sensing_loudness", Sensing, Reporter, [], [];
+    /// Only found in projects converted from Scratch 2.0
+    SensingLoud = "sensing_loud", Sensing, Boolean, [], [];
+    SensingTimer = "sensing_timer", Sensing, Reporter, [], [];
+    SensingResettimer = "sensing_resettimer", Sensing, Stack, [], [];
+    SensingOf = "sensing_of", Sensing, Reporter, ["OBJECT"], ["PROPERTY"];
+    SensingOfObjectMenu = "sensing_of_object_menu", Sensing, Reporter, [], ["OBJECT"];
+    SensingCurrent = "sensing_current", Sensing, Reporter, [], ["CURRENTMENU"];
+    SensingDayssince2000 = "sensing_dayssince2000", Sensing, Reporter, [], [];
+    SensingUsername = "sensing_username", Sensing, Reporter, [], [];
+    /// Only found in projects converted from Scratch 2.0
+    SensingUserid = "sensing_userid", Sensing, Reporter, [], [];
+
+    // Operators ===============================================================
+    OperatorAdd = "operator_add", Operators, Reporter, ["NUM1", "NUM2"], [];
+    OperatorSubtract = "operator_subtract", Operators, Reporter, ["NUM1", "NUM2"], [];
+    OperatorMultiply = "operator_multiply", Operators, Reporter, ["NUM1", "NUM2"], [];
+    OperatorDivide = "operator_divide", Operators, Reporter, ["NUM1", "NUM2"], [];
+    OperatorRandom = "operator_random", Operators, Reporter, ["FROM", "TO"], [];
+    OperatorGt = "operator_gt", Operators, Boolean, ["OPERAND1", "OPERAND2"], [];
+    OperatorLt = "operator_lt", Operators, Boolean, ["OPERAND1", "OPERAND2"], [];
+    OperatorEquals = "operator_equals", Operators, Boolean, ["OPERAND1", "OPERAND2"], [];
+    OperatorAnd = "operator_and", Operators, Boolean, ["OPERAND1", "OPERAND2"], [];
+    OperatorOr = "operator_or", Operators, Boolean, ["OPERAND1", "OPERAND2"], [];
+    OperatorNot = "operator_not", Operators, Boolean, ["OPERAND"], [];
+    OperatorJoin = "operator_join", Operators, Reporter, ["STRING1", "STRING2"], [];
+    OperatorLetterOf = "operator_letter_of", Operators, Reporter, ["LETTER", "STRING"], [];
+    OperatorLength = "operator_length", Operators, Reporter, ["STRING"], [];
+    OperatorContains = "operator_contains", Operators, Boolean, ["STRING1", "STRING2"], [];
+    OperatorMod = "operator_mod", Operators, Reporter, ["NUM1", "NUM2"], [];
+    OperatorRound = "operator_round", Operators, Reporter, ["NUM"], [];
+    OperatorMathop = "operator_mathop", Operators, Reporter, ["NUM"], ["OPERATOR"];
+
+    // Data ====================================================================
+    DataVariable = "data_variable", Data, Reporter, [], ["VARIABLE"];
+    DataSetvariableto = "data_setvariableto", Data, Stack, ["VALUE"], ["VARIABLE"];
+    DataChangevariableby = "data_changevariableby", Data, Stack, ["VALUE"], ["VARIABLE"];
+    DataShowvariable = "data_showvariable", Data, Stack, [], ["VARIABLE"];
+    DataHidevariable = "data_hidevariable", Data, Stack, [], ["VARIABLE"];
+    DataListcontents = "data_listcontents", Data, Reporter, [], ["LIST"];
+    DataAddtolist = "data_addtolist", Data, Stack, ["ITEM"], ["LIST"];
+    DataDeleteoflist = "data_deleteoflist", Data, Stack, ["INDEX"], ["LIST"];
+    DataDeletealloflist = "data_deletealloflist", Data, Stack, [], ["LIST"];
+    DataInsertatlist = "data_insertatlist", Data, Stack, ["ITEM", "INDEX"], ["LIST"];
+    DataReplaceitemoflist = "data_replaceitemoflist", Data, Stack, ["INDEX", "ITEM"], ["LIST"];
+    DataItemoflist = "data_itemoflist", Data, Reporter, ["INDEX"], ["LIST"];
+    DataItemnumoflist = "data_itemnumoflist", Data, Reporter, ["ITEM"], ["LIST"];
+    DataLengthoflist = "data_lengthoflist", Data, Reporter, [], ["LIST"];
+    DataListcontainsitem = "data_listcontainsitem", Data, Boolean, ["ITEM"], ["LIST"];
+    DataShowlist = "data_showlist", Data, Stack, [], ["LIST"];
+    DataHidelist = "data_hidelist", Data, Stack, [], ["LIST"];
+
+    // Procedures ==============================================================
+    ProceduresDefinition = "procedures_definition", Procedures, Hat, ["custom_block"], [];
+    ProceduresPrototype = "procedures_prototype", Procedures, Stack, [], [];
+    ProceduresCall = "procedures_call", Procedures, Stack, [], [];
+
+    // Argument ================================================================
+    ArgumentReporterStringNumber = "argument_reporter_string_number", Argument, Reporter, [], ["VALUE"];
+    ArgumentReporterBoolean = "argument_reporter_boolean", Argument, Boolean, [], ["VALUE"];
+    ArgumentEditorStringNumber = "argument_editor_string_number", Argument, Reporter, [], ["TEXT"];
+    ArgumentEditorBoolean = "argument_editor_boolean", Argument, Boolean, [], ["TEXT"];
+
+    // Pen =====================================================================
+    PenClear = "pen_clear", Pen, Stack, [], [];
+    PenStamp = "pen_stamp", Pen, Stack, [], [];
+    PenPenDown = "pen_penDown", Pen, Stack, [], [];
+    PenPenUp = "pen_penUp", Pen, Stack, [], [];
+    PenSetPenColorToColor = "pen_setPenColorToColor", Pen, Stack, ["COLOR"], [];
+    PenChangePenColorParamBy = "pen_changePenColorParamBy", Pen, Stack, ["COLOR_PARAM", "VALUE"], [];
+    PenSetPenColorParamTo = "pen_setPenColorParamTo", Pen, Stack, ["COLOR_PARAM", "VALUE"], [];
+    PenMenuColorParam = "pen_menu_colorParam", Pen, Reporter, [], ["colorParam"];
+    PenChangePenSizeBy = "pen_changePenSizeBy", Pen, Stack, ["SIZE"], [];
+    PenSetPenSizeTo = "pen_setPenSizeTo", Pen, Stack, ["SIZE"], [];
+    /// Only found in projects converted from Scratch 2.0
+    PenSetPenShadeToNumber = "pen_setPenShadeToNumber", Pen, Stack, ["SHADE"], [];
+    /// Only found in projects converted from Scratch 2.0
+    PenChangePenShadeBy = "pen_changePenShadeBy", Pen, Stack, ["SHADE"], [];
+    /// Only found in projects converted from Scratch 2.0
+    PenSetPenHueToNumber = "pen_setPenHueToNumber", Pen, Stack, ["HUE"], [];
+    /// Only found in projects converted from Scratch 2.0
+    PenChangePenHueBy = "pen_changePenHueBy", Pen, Stack, ["HUE"], [];
+
+    // Music ===================================================================
+    MusicPlayDrumForBeats = "music_playDrumForBeats", Music, Stack, ["DRUM", "BEATS"], [];
+    MusicMenuDrum = "music_menu_DRUM", Music, Reporter, [], ["DRUM"];
+    MusicRestForBeats = "music_restForBeats", Music, Stack, ["BEATS"], [];
+    MusicPlayNoteForBeats = "music_playNoteForBeats", Music, Stack, ["NOTE", "BEATS"], [];
+    /// The piano shadow of [`KnownOpcode::MusicPlayNoteForBeats`], it has no prefix.
+    Note = "note", Music, Reporter, [], ["NOTE"];
+    MusicSetInstrument = "music_setInstrument", Music, Stack, ["INSTRUMENT"], [];
+    MusicMenuInstrument = "music_menu_INSTRUMENT", Music, Reporter, [], ["INSTRUMENT"];
+    MusicSetTempo = "music_setTempo", Music, Stack, ["TEMPO"], [];
+    MusicChangeTempo = "music_changeTempo", Music, Stack, ["TEMPO"], [];
+    MusicGetTempo = "music_getTempo", Music, Reporter, [], [];
+    /// Only found in projects converted from Scratch 2.0
+    MusicMidiPlayDrumForBeats = "music_midiPlayDrumForBeats", Music, Stack, ["DRUM", "BEATS"], [];
+    /// Only found in projects converted from Scratch 2.0
+    MusicMidiSetInstrument = "music_midiSetInstrument", Music, Stack, ["INSTRUMENT"], [];
+
+    // Video Sensing ===========================================================
+    VideoSensingWhenMotionGreaterThan = "videoSensing_whenMotionGreaterThan", VideoSensing, Hat, ["REFERENCE"], [];
+    VideoSensingVideoOn = "videoSensing_videoOn", VideoSensing, Reporter, ["ATTRIBUTE", "SUBJECT"], [];
+    VideoSensingMenuAttribute = "videoSensing_menu_ATTRIBUTE", VideoSensing, Reporter, [], ["ATTRIBUTE"];
+    VideoSensingMenuSubject = "videoSensing_menu_SUBJECT", VideoSensing, Reporter, [], ["SUBJECT"];
+    VideoSensingVideoToggle = "videoSensing_videoToggle", VideoSensing, Stack, ["VIDEO_STATE"], [];
+    VideoSensingMenuVideoState = "videoSensing_menu_VIDEO_STATE", VideoSensing, Reporter, [], ["VIDEO_STATE"];
+    VideoSensingSetVideoTransparency = "videoSensing_setVideoTransparency", VideoSensing, Stack, ["TRANSPARENCY"], [];
+
+    // Text to Speech ==========================================================
+    TextToSpeechSpeakAndWait = "text2speech_speakAndWait", TextToSpeech, Stack, ["WORDS"], [];
+    TextToSpeechSetVoice = "text2speech_setVoice", TextToSpeech, Stack, ["VOICE"], [];
+    TextToSpeechMenuVoices = "text2speech_menu_voices", TextToSpeech, Reporter, [], ["voices"];
+    TextToSpeechSetLanguage = "text2speech_setLanguage", TextToSpeech, Stack, ["LANGUAGE"], [];
+    TextToSpeechMenuLanguages = "text2speech_menu_languages", TextToSpeech, Reporter, [], ["languages"];
+
+    // Translate ===============================================================
+    TranslateGetTranslate = "translate_getTranslate", Translate, Reporter, ["WORDS", "LANGUAGE"], [];
+    TranslateMenuLanguages = "translate_menu_languages", Translate, Reporter, [], ["languages"];
+    TranslateGetViewerLanguage = "translate_getViewerLanguage", Translate, Reporter, [], [];
+}
+
+impl Category {
+    /// Category of an opcode by its prefix, such as `motion` in `motion_movesteps`.
+    pub fn from_opcode(opcode: &str) -> Option<Category> {
+        let (prefix, _) = opcode.split_once('_')?;
+        Some(match prefix {
+            "motion" => Category::Motion,
+            "looks" => Category::Looks,
+            "sound" => Category::Sound,
+            "event" => Category::Events,
+            "control" => Category::Control,
+            "sensing" => Category::Sensing,
+            "operator" => Category::Operators,
+            "data" => Category::Data,
+            "procedures" => Category::Procedures,
+            "argument" => Category::Argument,
+            "pen" => Category::Pen,
+            "music" => Category::Music,
+            "videoSensing" => Category::VideoSensing,
+            "text2speech" => Category::TextToSpeech,
+            "translate" => Category::Translate,
+            _ => return None,
+        })
+    }
+}
+
+impl KnownOpcode {
+    /// True if this is not [`KnownOpcode::Unknown`]
+    pub fn is_known(&self) -> bool {
+        !matches!(self, KnownOpcode::Unknown(_))
+    }
+}
+
+impl From<OpCode> for KnownOpcode {
+    fn from(s: OpCode) -> Self {
+        match KnownOpcode::from(s.as_str()) {
+            KnownOpcode::Unknown(_) => KnownOpcode::Unknown(s),
+            known => known,
+        }
+    }
+}
+
+impl From<&OpCode> for KnownOpcode {
+    fn from(s: &OpCode) -> Self {
+        KnownOpcode::from(s.as_str())
+    }
+}
+
+impl From<KnownOpcode> for OpCode {
+    fn from(op: KnownOpcode) -> Self {
+        match op {
+            KnownOpcode::Unknown(s) => s,
+            known => known.as_str().to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for KnownOpcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq<str> for KnownOpcode {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for KnownOpcode {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+// Serde impl ==================================================================
+
+impl<'de> Deserialize<'de> for KnownOpcode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(OpCode::deserialize(deserializer)?.into())
+    }
+}
+
+impl Serialize for KnownOpcode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
@@ -1,10 +1,6 @@
 //! For to use within the crate
 
-pub use serde::{
-    de::Unexpected,
-    de::{DeserializeOwned, Visitor},
-    Deserialize, Deserializer, Serialize, Serializer,
-};
+pub use serde::{de::Unexpected, de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
 pub use serde_json::Value as Json;
 pub use serde_repr::{Deserialize_repr, Serialize_repr};
 
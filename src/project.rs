@@ -1,16 +1,22 @@
 //! Module to deal with Scratch project
 
+use crate::block::Block;
+use crate::extension::Extension;
 use crate::monitor::Monitor;
 use crate::prelude::*;
 use crate::target::SpriteOrStage;
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Project {
     pub meta: Meta,
-    pub extensions: Json,
+    /// Extensions the project loads, see [`Extension`]
+    pub extensions: Vec<Extension>,
     pub monitors: Vec<Monitor>,
     pub targets: Vec<SpriteOrStage>,
+    /// Unknown keys, see [`StringHashMap`]
+    #[serde(flatten)]
+    pub extra: StringHashMap<Json>,
 }
 
 /// About the project's author and the Scratch version used.
@@ -25,4 +31,29 @@ pub struct Meta {
 
     /// The user agent of the last person to edit the project from the editor.
     pub agent: String,
+
+    /// Unknown keys, see [`StringHashMap`]
+    #[serde(flatten)]
+    pub extra: StringHashMap<Json>,
+}
+
+impl Project {
+    /// Sort the keys of every map in the project, for canonical output that does not depend on insertion order.
+    /// See [`StringHashMap::sort_keys`]
+    pub fn sort_keys(&mut self) {
+        for target in &mut self.targets {
+            let target = target.target_mut();
+            target.variables.sort_keys();
+            target.lists.sort_keys();
+            target.broadcasts.sort_keys();
+            target.comments.sort_keys();
+            target.blocks.sort_keys();
+            for block in target.blocks.values_mut() {
+                if let Block::Normal(block) = block {
+                    block.inputs.sort_keys();
+                    block.fields.sort_keys();
+                }
+            }
+        }
+    }
 }
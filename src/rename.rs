@@ -0,0 +1,162 @@
+//! Module to rename variables, lists and broadcasts
+//!
+//! Blocks and monitors refer to them by name as well as by Id,
+//! so renaming only [`Variable::name`](crate::variable::Variable::name) leaves the project
+//! showing the old name everywhere else. [`Project::rename_variable`] and its siblings
+//! rename every occurrence at once.
+
+use crate::block::DataKind;
+use crate::diff::{data, data_name};
+use crate::monitor::Parameter;
+use crate::prelude::*;
+use crate::project::Project;
+use crate::target::Target;
+use std::fmt;
+
+/// Why a variable, list or broadcast could not be renamed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameError {
+    /// No target declares a variable, list or broadcast with the Id.
+    NotFound {
+        /// Variable, list or broadcast
+        kind: DataKind,
+        /// The Id
+        id: Uid,
+    },
+
+    /// The new name is empty.
+    EmptyName,
+
+    /// Another variable, list or broadcast in scope already has the name.
+    Taken {
+        /// Variable, list or broadcast
+        kind: DataKind,
+        /// The name
+        name: Name,
+        /// Name of the target that declares the other one
+        target: Name,
+    },
+}
+
+impl Project {
+    /// Rename the variable with the Id, along with every block and monitor that shows its name.
+    ///
+    /// A global variable can't take the name of any other variable of the project,
+    /// a local variable can't take the name of the other variables of its sprite or of a global one.
+    pub fn rename_variable(&mut self, id: &str, name: &str) -> Result<(), RenameError> {
+        self.rename_data(DataKind::Variable, id, name)
+    }
+
+    /// Rename the list with the Id, same as [`Project::rename_variable`].
+    pub fn rename_list(&mut self, id: &str, name: &str) -> Result<(), RenameError> {
+        self.rename_data(DataKind::List, id, name)
+    }
+
+    /// Rename the broadcast with the Id, along with every block that shows its name.
+    /// Broadcasts are all global, and their names are compared ignoring case like Scratch does.
+    pub fn rename_broadcast(&mut self, id: &str, name: &str) -> Result<(), RenameError> {
+        self.rename_data(DataKind::Broadcast, id, name)
+    }
+
+    /// Rename the variable, list or broadcast with the Id.
+    pub fn rename_data(&mut self, kind: DataKind, id: &str, name: &str) -> Result<(), RenameError> {
+        let declares = |t: &Target| data(t, kind).iter().any(|(i, _)| i == id);
+        let Some(owner) = self.targets.iter().position(|t| declares(t.target())) else {
+            return Err(RenameError::NotFound {
+                kind,
+                id: id.to_owned(),
+            });
+        };
+        if name.is_empty() {
+            return Err(RenameError::EmptyName);
+        }
+        let global = self.targets[owner].is_stage() || kind == DataKind::Broadcast;
+
+        let same = |a: &str, b: &str| match kind {
+            DataKind::Broadcast => a.to_lowercase() == b.to_lowercase(),
+            _ => a == b,
+        };
+        for (i, target) in self.targets.iter().enumerate() {
+            let in_scope = global || i == owner || target.is_stage();
+            let taken = data(target.target(), kind)
+                .into_iter()
+                .any(|(other, other_name)| other != id && same(&other_name, name));
+            if in_scope && taken {
+                return Err(RenameError::Taken {
+                    kind,
+                    name: name.to_owned(),
+                    target: target.target().name.clone(),
+                });
+            }
+        }
+
+        let owner_target = &self.targets[owner];
+        let sprite_name = (!owner_target.is_stage()).then(|| owner_target.target().name.clone());
+        let mut old = None;
+        for (i, target) in self.targets.iter_mut().enumerate() {
+            // Sprites that declare the Id too refer to their own
+            let refers = match global {
+                true => i == owner || !declares(target.target()),
+                false => i == owner,
+            };
+            if !refers {
+                continue;
+            }
+            let target = target.target_mut();
+            if i == owner {
+                let declared = match kind {
+                    DataKind::Variable => &mut target.variables.0[id].name,
+                    DataKind::List => &mut target.lists.0[id].name,
+                    DataKind::Broadcast => &mut target.broadcasts.0[id].name,
+                };
+                old = Some(std::mem::replace(declared, name.to_owned()));
+            }
+            for block in target.blocks.0.values_mut() {
+                block.for_each_data_reference_mut(|k, n, i| {
+                    if k == kind && i == id {
+                        *n = name.to_owned();
+                    }
+                });
+            }
+        }
+
+        let old = old.expect("the owner declares the Id");
+        for monitor in &mut self.monitors {
+            if monitor.sprite_name != sprite_name {
+                continue;
+            }
+            match (&mut monitor.params, kind) {
+                (Parameter::Variable(n), DataKind::Variable)
+                | (Parameter::List(n), DataKind::List)
+                    if *n == old =>
+                {
+                    *n = name.to_owned()
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// Error impl ==================================================================
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::NotFound { kind, id } => {
+                write!(f, "no {} has Id `{id}`", data_name(*kind))
+            }
+            RenameError::EmptyName => write!(f, "name is empty"),
+            RenameError::Taken { kind, name, target } => {
+                write!(
+                    f,
+                    "{target} already has a {} named `{name}`",
+                    data_name(*kind)
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenameError {}
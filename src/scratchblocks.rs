@@ -0,0 +1,1526 @@
+//! Module to write and read scripts as scratchblocks text
+//!
+//! [scratchblocks](https://scratchblocks.github.io) is the text syntax the Scratch Wiki and forums use for scripts:
+//!
+//! ```text
+//! when green flag clicked
+//! go to x: (0) y: (0)
+//! forever
+//!     if <touching (edge v)?> then
+//!         turn right (180) degrees
+//!     end
+//!     move (10) steps
+//! end
+//! ```
+//!
+//! Numbers are in round brackets and text in square brackets, menus end with ` v`.
+//! Custom blocks that run without screen refresh get a `// run without screen refresh` comment on their `define`.
+//! Blocks that have no scratchblocks text are written as their opcode followed by ` :: grey`.
+//!
+//! [`Project::add_scratchblocks`] reads such text back into blocks, [`ParseError`] tells where it went wrong.
+
+use crate::block::{
+    Block, BlockField, BlockInputValue, BlockMutation, BlockMutationEnum, DataKind, ListOrVariable,
+};
+use crate::builder::{self, control, Arg, BlockBuilder, LiteralKind, StackBuilder};
+use crate::opcode::{KnownOpcode, Shape};
+use crate::prelude::*;
+use crate::project::Project;
+use crate::script::{scripts, BlockRef, Error, InputRef, Script};
+use crate::target::Target;
+use crate::uid;
+use crate::value::js_number;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Text of every block that has one.
+///
+/// `{NAME}` is the input with the name, `<NAME>` a boolean input and `[NAME]` a field.
+/// C mouths are not in the text, see [`render_script`].
+pub const TEMPLATES: &[(&str, &str)] = &[
+    // Motion ==================================================================
+    ("motion_movesteps", "move {STEPS} steps"),
+    ("motion_turnright", "turn right {DEGREES} degrees"),
+    ("motion_turnleft", "turn left {DEGREES} degrees"),
+    ("motion_goto", "go to {TO}"),
+    ("motion_gotoxy", "go to x: {X} y: {Y}"),
+    ("motion_glideto", "glide {SECS} secs to {TO}"),
+    ("motion_glidesecstoxy", "glide {SECS} secs to x: {X} y: {Y}"),
+    ("motion_pointindirection", "point in direction {DIRECTION}"),
+    ("motion_pointtowards", "point towards {TOWARDS}"),
+    ("motion_changexby", "change x by {DX}"),
+    ("motion_setx", "set x to {X}"),
+    ("motion_changeyby", "change y by {DY}"),
+    ("motion_sety", "set y to {Y}"),
+    ("motion_ifonedgebounce", "if on edge, bounce"),
+    ("motion_setrotationstyle", "set rotation style [STYLE]"),
+    ("motion_xposition", "x position"),
+    ("motion_yposition", "y position"),
+    ("motion_direction", "direction"),
+    // Looks ===================================================================
+    ("looks_sayforsecs", "say {MESSAGE} for {SECS} seconds"),
+    ("looks_say", "say {MESSAGE}"),
+    ("looks_thinkforsecs", "think {MESSAGE} for {SECS} seconds"),
+    ("looks_think", "think {MESSAGE}"),
+    ("looks_switchcostumeto", "switch costume to {COSTUME}"),
+    ("looks_nextcostume", "next costume"),
+    (
+        "looks_switchbackdroptoandwait",
+        "switch backdrop to {BACKDROP} and wait",
+    ),
+    ("looks_switchbackdropto", "switch backdrop to {BACKDROP}"),
+    ("looks_nextbackdrop", "next backdrop"),
+    ("looks_changesizeby", "change size by {CHANGE}"),
+    ("looks_setsizeto", "set size to {SIZE} %"),
+    ("looks_changeeffectby", "change [EFFECT] effect by {CHANGE}"),
+    ("looks_seteffectto", "set [EFFECT] effect to {VALUE}"),
+    ("looks_cleargraphiceffects", "clear graphic effects"),
+    ("looks_show", "show"),
+    ("looks_hide", "hide"),
+    ("looks_gotofrontback", "go to [FRONT_BACK] layer"),
+    (
+        "looks_goforwardbackwardlayers",
+        "go [FORWARD_BACKWARD] {NUM} layers",
+    ),
+    ("looks_costumenumbername", "costume [NUMBER_NAME]"),
+    ("looks_backdropnumbername", "backdrop [NUMBER_NAME]"),
+    ("looks_size", "size"),
+    // Sound ===================================================================
+    ("sound_playuntildone", "play sound {SOUND_MENU} until done"),
+    ("sound_play", "start sound {SOUND_MENU}"),
+    ("sound_stopallsounds", "stop all sounds"),
+    ("sound_changeeffectby", "change [EFFECT] effect by {VALUE}"),
+    ("sound_seteffectto", "set [EFFECT] effect to {VALUE}"),
+    ("sound_cleareffects", "clear sound effects"),
+    ("sound_changevolumeby", "change volume by {VOLUME}"),
+    ("sound_setvolumeto", "set volume to {VOLUME} %"),
+    ("sound_volume", "volume"),
+    // Events ==================================================================
+    ("event_whenflagclicked", "when green flag clicked"),
+    ("event_whenkeypressed", "when [KEY_OPTION] key pressed"),
+    ("event_whenthisspriteclicked", "when this sprite clicked"),
+    ("event_whenstageclicked", "when stage clicked"),
+    (
+        "event_whenbackdropswitchesto",
+        "when backdrop switches to [BACKDROP]",
+    ),
+    (
+        "event_whengreaterthan",
+        "when [WHENGREATERTHANMENU] > {VALUE}",
+    ),
+    (
+        "event_whenbroadcastreceived",
+        "when I receive [BROADCAST_OPTION]",
+    ),
+    (
+        "event_broadcastandwait",
+        "broadcast {BROADCAST_INPUT} and wait",
+    ),
+    ("event_broadcast", "broadcast {BROADCAST_INPUT}"),
+    // Control =================================================================
+    ("control_wait", "wait {DURATION} seconds"),
+    ("control_repeat", "repeat {TIMES}"),
+    ("control_forever", "forever"),
+    ("control_if_else", "if <CONDITION> then"),
+    ("control_if", "if <CONDITION> then"),
+    ("control_wait_until", "wait until <CONDITION>"),
+    ("control_repeat_until", "repeat until <CONDITION>"),
+    ("control_while", "while <CONDITION>"),
+    ("control_for_each", "for each [VARIABLE] in {VALUE}"),
+    ("control_stop", "stop [STOP_OPTION]"),
+    ("control_start_as_clone", "when I start as a clone"),
+    ("control_create_clone_of", "create clone of {CLONE_OPTION}"),
+    ("control_delete_this_clone", "delete this clone"),
+    ("control_get_counter", "counter"),
+    ("control_incr_counter", "increment counter"),
+    ("control_clear_counter", "clear counter"),
+    ("control_all_at_once", "all at once"),
+    // Sensing =================================================================
+    ("sensing_touchingobject", "touching {TOUCHINGOBJECTMENU}?"),
+    ("sensing_touchingcolor", "touching color {COLOR}?"),
+    (
+        "sensing_coloristouchingcolor",
+        "color {COLOR} is touching {COLOR2}?",
+    ),
+    ("sensing_distanceto", "distance to {DISTANCETOMENU}"),
+    ("sensing_askandwait", "ask {QUESTION} and wait"),
+    ("sensing_answer", "answer"),
+    ("sensing_keypressed", "key {KEY_OPTION} pressed?"),
+    ("sensing_mousedown", "mouse down?"),
+    ("sensing_mousex", "mouse x"),
+    ("sensing_mousey", "mouse y"),
+    ("sensing_setdragmode", "set drag mode [DRAG_MODE]"),
+    ("sensing_loudness", "loudness"),
+    ("sensing_timer", "timer"),
+    ("sensing_resettimer", "reset timer"),
+    ("sensing_of", "[PROPERTY] of {OBJECT}"),
+    ("sensing_current", "current [CURRENTMENU]"),
+    ("sensing_dayssince2000", "days since 2000"),
+    ("sensing_username", "username"),
+    // Operators ===============================================================
+    ("operator_add", "{NUM1} + {NUM2}"),
+    ("operator_subtract", "{NUM1} - {NUM2}"),
+    ("operator_multiply", "{NUM1} * {NUM2}"),
+    ("operator_divide", "{NUM1} / {NUM2}"),
+    ("operator_random", "pick random {FROM} to {TO}"),
+    ("operator_gt", "{OPERAND1} > {OPERAND2}"),
+    ("operator_lt", "{OPERAND1} < {OPERAND2}"),
+    ("operator_equals", "{OPERAND1} = {OPERAND2}"),
+    ("operator_and", "<OPERAND1> and <OPERAND2>"),
+    ("operator_or", "<OPERAND1> or <OPERAND2>"),
+    ("operator_not", "not <OPERAND>"),
+    ("operator_join", "join {STRING1} {STRING2}"),
+    ("operator_letter_of", "letter {LETTER} of {STRING}"),
+    ("operator_length", "length of {STRING}"),
+    ("operator_contains", "{STRING1} contains {STRING2}?"),
+    ("operator_mod", "{NUM1} mod {NUM2}"),
+    ("operator_round", "round {NUM}"),
+    ("operator_mathop", "[OPERATOR] of {NUM}"),
+    // Data ====================================================================
+    ("data_setvariableto", "set [VARIABLE] to {VALUE}"),
+    ("data_changevariableby", "change [VARIABLE] by {VALUE}"),
+    ("data_showvariable", "show variable [VARIABLE]"),
+    ("data_hidevariable", "hide variable [VARIABLE]"),
+    ("data_addtolist", "add {ITEM} to [LIST]"),
+    ("data_deleteoflist", "delete {INDEX} of [LIST]"),
+    ("data_deletealloflist", "delete all of [LIST]"),
+    ("data_insertatlist", "insert {ITEM} at {INDEX} of [LIST]"),
+    (
+        "data_replaceitemoflist",
+        "replace item {INDEX} of [LIST] with {ITEM}",
+    ),
+    ("data_itemoflist", "item {INDEX} of [LIST]"),
+    ("data_itemnumoflist", "item # of {ITEM} in [LIST]"),
+    ("data_lengthoflist", "length of [LIST]"),
+    ("data_listcontainsitem", "[LIST] contains {ITEM}?"),
+    ("data_showlist", "show list [LIST]"),
+    ("data_hidelist", "hide list [LIST]"),
+    // Pen =====================================================================
+    ("pen_clear", "erase all"),
+    ("pen_stamp", "stamp"),
+    ("pen_penDown", "pen down"),
+    ("pen_penUp", "pen up"),
+    ("pen_setPenColorToColor", "set pen color to {COLOR}"),
+    (
+        "pen_changePenColorParamBy",
+        "change pen {COLOR_PARAM} by {VALUE}",
+    ),
+    ("pen_setPenColorParamTo", "set pen {COLOR_PARAM} to {VALUE}"),
+    ("pen_changePenSizeBy", "change pen size by {SIZE}"),
+    ("pen_setPenSizeTo", "set pen size to {SIZE}"),
+    // Music ===================================================================
+    (
+        "music_playDrumForBeats",
+        "play drum {DRUM} for {BEATS} beats",
+    ),
+    ("music_restForBeats", "rest for {BEATS} beats"),
+    (
+        "music_playNoteForBeats",
+        "play note {NOTE} for {BEATS} beats",
+    ),
+    ("music_setInstrument", "set instrument to {INSTRUMENT}"),
+    ("music_setTempo", "set tempo to {TEMPO}"),
+    ("music_changeTempo", "change tempo by {TEMPO}"),
+    ("music_getTempo", "tempo"),
+    // Video Sensing ===========================================================
+    (
+        "videoSensing_whenMotionGreaterThan",
+        "when video motion > {REFERENCE}",
+    ),
+    ("videoSensing_videoOn", "video {ATTRIBUTE} on {SUBJECT}"),
+    ("videoSensing_videoToggle", "turn video {VIDEO_STATE}"),
+    (
+        "videoSensing_setVideoTransparency",
+        "set video transparency to {TRANSPARENCY}",
+    ),
+    // Text to Speech ==========================================================
+    ("text2speech_speakAndWait", "speak {WORDS}"),
+    ("text2speech_setVoice", "set voice to {VOICE}"),
+    ("text2speech_setLanguage", "set language to {LANGUAGE}"),
+    // Translate ===============================================================
+    ("translate_getTranslate", "translate {WORDS} to {LANGUAGE}"),
+    ("translate_getViewerLanguage", "language"),
+];
+
+/// Menu values that Scratch shows with another name.
+const MENU_NAMES: &[(&str, &str)] = &[
+    ("_mouse_", "mouse-pointer"),
+    ("_random_", "random position"),
+    ("_edge_", "edge"),
+    ("_myself_", "myself"),
+    ("_stage_", "Stage"),
+];
+
+/// Fields which values are in capitals in project.json but not in Scratch.
+const CAPITAL_FIELDS: &[&str] = &["EFFECT", "CURRENTMENU", "WHENGREATERTHANMENU"];
+
+const INDENT: &str = "    ";
+
+/// Text of the block with the opcode, see [`TEMPLATES`].
+pub fn template(opcode: &str) -> Option<&'static str> {
+    TEMPLATES
+        .iter()
+        .find(|(op, _)| *op == opcode)
+        .map(|(_, template)| *template)
+}
+
+impl Target {
+    /// Every script of the target as scratchblocks, separated by an empty line.
+    pub fn to_scratchblocks(&self) -> Result<String, Error> {
+        let texts = render_scripts(self)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(texts.join("\n"))
+    }
+}
+
+/// Scratchblocks of every script of the target that shows in Scratch, one by one.
+pub(crate) fn render_scripts(target: &Target) -> Vec<Result<String, Error>> {
+    let procedures = procedures(&target.blocks);
+    scripts(&target.blocks)
+        .into_iter()
+        // Shadows left alone don't show in Scratch
+        .filter(|script| !matches!(script.top().block(), Block::Normal(b) if b.shadow))
+        .map(|script| render(script, &procedures))
+        .collect()
+}
+
+/// The script as scratchblocks, one block per line ending with a new line.
+/// Blocks in C mouths are indented by 4 spaces.
+/// Every custom block call is marked `:: custom`,
+/// [`Target::to_scratchblocks`] leaves it out for the custom blocks the target defines.
+pub fn render_script(script: Script) -> Result<String, Error> {
+    render(script, &HashSet::new())
+}
+
+fn render(script: Script, procedures: &HashSet<String>) -> Result<String, Error> {
+    let mut renderer = Renderer {
+        procedures,
+        visited: HashSet::new(),
+        lines: vec![],
+    };
+    renderer.stack(script.top(), 0)?;
+    let mut text = renderer.lines.join("\n");
+    text.push('\n');
+    Ok(text)
+}
+
+/// Proccodes of the custom blocks defined in the blocks.
+fn procedures(blocks: &StringHashMap<Block>) -> HashSet<String> {
+    blocks
+        .0
+        .values()
+        .filter_map(|b| match b {
+            Block::Normal(b) => match &b.mutation.as_ref()?.mutation_enum {
+                BlockMutationEnum::ProceduresPrototype { proccode, .. } => Some(proccode.clone()),
+                _ => None,
+            },
+            Block::VarList(_) => None,
+        })
+        .collect()
+}
+
+struct Renderer<'a> {
+    /// Custom blocks defined in the target, calls to other custom blocks are marked `:: custom`
+    procedures: &'a HashSet<String>,
+    /// Blocks already written, for inputs that loop back
+    visited: HashSet<Uid>,
+    lines: Vec<String>,
+}
+
+impl Renderer<'_> {
+    fn stack(&mut self, first: BlockRef, depth: usize) -> Result<(), Error> {
+        for block in first.stack() {
+            let block = block?;
+            self.visit(block)?;
+            let indent = INDENT.repeat(depth);
+            let shape = block
+                .opcode()
+                .map(KnownOpcode::from)
+                .and_then(|op| op.shape());
+            let text = match shape {
+                Some(Shape::Reporter | Shape::Boolean) => self.reporter(block)?,
+                // A variable or list reporter on its own
+                _ if block.normal().is_none() => self.reporter(block)?,
+                _ => self.text(block)?,
+            };
+            self.lines.push(format!("{indent}{text}"));
+
+            let is_c = matches!(
+                block
+                    .opcode()
+                    .map(KnownOpcode::from)
+                    .and_then(|op| op.shape()),
+                Some(Shape::C)
+            );
+            if !is_c {
+                continue;
+            }
+            if let Some(substack) = block.substack("SUBSTACK")? {
+                self.stack(substack, depth + 1)?;
+            }
+            if block.opcode() == Some("control_if_else") {
+                self.lines.push(format!("{indent}else"));
+                if let Some(substack) = block.substack("SUBSTACK2")? {
+                    self.stack(substack, depth + 1)?;
+                }
+            }
+            self.lines.push(format!("{indent}end"));
+        }
+        Ok(())
+    }
+
+    /// Text of the block, without the brackets of its shape.
+    fn text(&mut self, block: BlockRef) -> Result<String, Error> {
+        let Some(normal) = block.normal() else {
+            return self.reporter(block);
+        };
+        let opcode = normal.opcode.as_str();
+        match opcode {
+            "procedures_definition" => {
+                let Some(InputRef::Block(prototype)) = block.input("custom_block")? else {
+                    return Ok("define".to_owned());
+                };
+                self.visit(prototype)?;
+                return Ok(self.definition(prototype));
+            }
+            "procedures_call" => return self.call(block),
+            "data_variable" => return Ok(variable(&field_text(block, "VARIABLE"))),
+            "data_listcontents" => return Ok(list(&field_text(block, "LIST"))),
+            "argument_reporter_string_number" | "argument_reporter_boolean" => {
+                return Ok(escape(&field_text(block, "VALUE")))
+            }
+            _ => {}
+        }
+
+        let Some(template) = template(opcode) else {
+            return Ok(format!("{opcode} :: grey"));
+        };
+        let mut text = String::new();
+        for piece in pieces(template) {
+            match piece {
+                Piece::Text(t) => text.push_str(t),
+                Piece::Input(name) => text.push_str(&self.input(block, name, false)?),
+                Piece::Boolean(name) => text.push_str(&self.input(block, name, true)?),
+                Piece::Field(name) => text.push_str(&field(block, name)),
+            }
+        }
+        Ok(text)
+    }
+
+    /// The block with the brackets of its shape.
+    fn reporter(&mut self, block: BlockRef) -> Result<String, Error> {
+        let Some(normal) = block.normal() else {
+            return Ok(match block.block() {
+                Block::VarList(v) => match v.kind {
+                    ListOrVariable::Variable => format!("({})", variable(&v.name)),
+                    ListOrVariable::List => format!("({})", list(&v.name)),
+                },
+                Block::Normal(_) => unreachable!(),
+            });
+        };
+        // Menus
+        if normal.shadow && normal.inputs.0.is_empty() && normal.fields.0.len() == 1 {
+            let (name, _) = normal.fields.0.iter().next().unwrap();
+            return Ok(format!("({} v)", escape(&menu_text(block, name))));
+        }
+        let text = self.text(block)?;
+        let boolean = match normal.opcode.as_str() {
+            "procedures_call" => false,
+            opcode => KnownOpcode::from(opcode).shape() == Some(Shape::Boolean),
+        };
+        Ok(match boolean {
+            true => format!("<{text}>"),
+            false => format!("({text})"),
+        })
+    }
+
+    fn input(&mut self, block: BlockRef, name: &str, boolean: bool) -> Result<String, Error> {
+        match block.input(name)? {
+            Some(InputRef::Block(reporter)) => {
+                self.visit(reporter)?;
+                self.reporter(reporter)
+            }
+            Some(InputRef::Value(value)) => Ok(literal(value)),
+            None if boolean => Ok("<>".to_owned()),
+            None => Ok("()".to_owned()),
+        }
+    }
+
+    /// `define` and the text of the prototype.
+    fn definition(&self, prototype: BlockRef) -> String {
+        let Some(BlockMutationEnum::ProceduresPrototype {
+            proccode,
+            argumentnames,
+            warp,
+            ..
+        }) = prototype
+            .normal()
+            .and_then(|b| b.mutation.as_ref())
+            .map(|m| &m.mutation_enum)
+        else {
+            return "define".to_owned();
+        };
+        let mut names = argumentnames.iter();
+        let mut text = "define".to_owned();
+        for part in proccode_parts(proccode) {
+            text.push(' ');
+            match part {
+                ProccodePart::Label(label) => text.push_str(&escape(label)),
+                ProccodePart::Arg(boolean) => {
+                    let name = escape(names.next().map_or("", |n| n.as_str()));
+                    match boolean {
+                        true => text.push_str(&format!("<{name}>")),
+                        false => text.push_str(&format!("({name})")),
+                    }
+                }
+            }
+        }
+        if *warp == Some(true) {
+            text.push_str(" // run without screen refresh");
+        }
+        text
+    }
+
+    fn call(&mut self, block: BlockRef) -> Result<String, Error> {
+        let Some(BlockMutationEnum::ProceduresCall {
+            proccode,
+            argumentids,
+            ..
+        }) = block
+            .normal()
+            .and_then(|b| b.mutation.as_ref())
+            .map(|m| &m.mutation_enum)
+        else {
+            return Ok("procedures_call :: grey".to_owned());
+        };
+        let mut ids = argumentids.iter();
+        let mut parts = vec![];
+        for part in proccode_parts(proccode) {
+            parts.push(match part {
+                ProccodePart::Label(label) => escape(label),
+                ProccodePart::Arg(boolean) => match ids.next() {
+                    Some(id) => self.input(block, id, boolean)?,
+                    None if boolean => "<>".to_owned(),
+                    None => "()".to_owned(),
+                },
+            });
+        }
+        let mut text = parts.join(" ");
+        if !self.procedures.contains(proccode) {
+            text.push_str(" :: custom");
+        }
+        Ok(text)
+    }
+
+    fn visit(&mut self, block: BlockRef) -> Result<(), Error> {
+        match self.visited.insert(block.id().clone()) {
+            true => Ok(()),
+            false => Err(Error::Cycle {
+                id: block.id().clone(),
+            }),
+        }
+    }
+}
+
+/// Part of a template, see [`TEMPLATES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Piece<'a> {
+    Text(&'a str),
+    Input(&'a str),
+    Boolean(&'a str),
+    Field(&'a str),
+}
+
+fn pieces(template: &str) -> Vec<Piece<'_>> {
+    let mut pieces = vec![];
+    let mut text_start = 0;
+    let mut i = 0;
+    while i < template.len() {
+        let rest = &template[i..];
+        let close = match rest.as_bytes()[0] {
+            b'{' => '}',
+            b'<' => '>',
+            b'[' => ']',
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        // An opening bracket followed by a name and the closing bracket is a slot, like `<CONDITION>`
+        let name_len = rest[1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len() - 1);
+        if name_len == 0 || !rest[1 + name_len..].starts_with(close) {
+            i += 1;
+            continue;
+        }
+        if text_start < i {
+            pieces.push(Piece::Text(&template[text_start..i]));
+        }
+        let name = &rest[1..1 + name_len];
+        pieces.push(match close {
+            '}' => Piece::Input(name),
+            '>' => Piece::Boolean(name),
+            _ => Piece::Field(name),
+        });
+        i += name_len + 2;
+        text_start = i;
+    }
+    if text_start < template.len() {
+        pieces.push(Piece::Text(&template[text_start..]));
+    }
+    pieces
+}
+
+enum ProccodePart<'a> {
+    Label(&'a str),
+    /// True for a boolean argument
+    Arg(bool),
+}
+
+/// Words and arguments of a proccode, such as `jump %s times`.
+fn proccode_parts(proccode: &str) -> Vec<ProccodePart<'_>> {
+    proccode
+        .split_whitespace()
+        .map(|word| match word {
+            "%s" | "%n" => ProccodePart::Arg(false),
+            "%b" => ProccodePart::Arg(true),
+            label => ProccodePart::Label(label),
+        })
+        .collect()
+}
+
+fn literal(value: &BlockInputValue) -> String {
+    match value {
+        BlockInputValue::Number { value }
+        | BlockInputValue::PositiveNumber { value }
+        | BlockInputValue::PositiveInteger { value }
+        | BlockInputValue::Integer { value }
+        | BlockInputValue::Angle { value } => format!("({})", escape(&value.to_scratch_string())),
+        BlockInputValue::Color { value } | BlockInputValue::String { value } => {
+            format!("[{}]", escape(&value.to_scratch_string()))
+        }
+        BlockInputValue::Broadcast { name, .. } => format!("({} v)", escape(name)),
+        BlockInputValue::Variable { name, .. } => format!("({})", variable(name)),
+        BlockInputValue::List { name, .. } => format!("({})", list(name)),
+    }
+}
+
+/// Variable reporter without its brackets, marked as a variable if it has the name of another reporter.
+fn variable(name: &str) -> String {
+    let taken = TEMPLATES.iter().any(|(_, template)| *template == name);
+    match taken {
+        true => format!("{} :: variables", escape(name)),
+        false => escape(name),
+    }
+}
+
+fn list(name: &str) -> String {
+    format!("{} :: list", escape(name))
+}
+
+fn field(block: BlockRef, name: &str) -> String {
+    format!("[{} v]", escape(&menu_text(block, name)))
+}
+
+/// Value of the field as Scratch shows it.
+fn menu_text(block: BlockRef, name: &str) -> String {
+    let text = field_text(block, name);
+    if let Some((_, shown)) = MENU_NAMES.iter().find(|(value, _)| *value == text) {
+        return (*shown).to_owned();
+    }
+    match CAPITAL_FIELDS.contains(&name) {
+        true => text.to_lowercase(),
+        false => text,
+    }
+}
+
+fn field_text(block: BlockRef, name: &str) -> String {
+    match block.normal().and_then(|b| b.fields.0.get(name)) {
+        Some(BlockField::WithId { value, .. } | BlockField::NoId { value }) => {
+            value.to_scratch_string()
+        }
+        None => String::new(),
+    }
+}
+
+/// Escape the characters that mean something in scratchblocks with a backslash.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '(' | ')' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    // ` v` at the end would make it a menu
+    if escaped.ends_with(" v") {
+        escaped.insert(escaped.len() - 1, '\\');
+    }
+    escaped
+}
+
+// Parse impl ==================================================================
+
+/// Error when reading scratchblocks text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line of the error, starting at 1, 0 when no target has the name
+    pub line: usize,
+
+    /// Column of the error in characters, starting at 1
+    pub column: usize,
+
+    /// What is wrong
+    pub message: String,
+}
+
+impl Project {
+    /// Add the scripts of the scratchblocks text to the target with the name, below the scripts it has.
+    ///
+    /// Scripts are separated by an empty line, C mouths end with `end` and indentation doesn't matter.
+    /// Custom blocks can be called before or without their `define`, calls to blocks defined nowhere need `:: custom`.
+    /// Variables and lists are looked up by name in the target then in the stage,
+    /// those that are declared nowhere are declared in the stage, like broadcasts.
+    pub fn add_scratchblocks(&mut self, target_name: &str, text: &str) -> Result<(), ParseError> {
+        let stacks = parse(text)?;
+        let target = (self.targets.iter())
+            .position(|t| t.target().name == target_name)
+            .ok_or_else(|| ParseError {
+                line: 0,
+                column: 0,
+                message: format!("no target is named `{target_name}`"),
+            })?;
+        builder::add_stacks(self, target, stacks);
+        Ok(())
+    }
+}
+
+/// What an input of a block takes.
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Literal(LiteralKind),
+    /// A menu, the opcode of its shadow block and the name of the field of it
+    Menu(&'static str, &'static str),
+    Broadcast,
+}
+
+fn slot(opcode: &str, input: &str) -> Slot {
+    match (opcode, input) {
+        ("motion_goto", "TO") => Slot::Menu("motion_goto_menu", "TO"),
+        ("motion_glideto", "TO") => Slot::Menu("motion_glideto_menu", "TO"),
+        (_, "TOWARDS") => Slot::Menu("motion_pointtowards_menu", "TOWARDS"),
+        (_, "COSTUME") => Slot::Menu("looks_costume", "COSTUME"),
+        (_, "BACKDROP") => Slot::Menu("looks_backdrops", "BACKDROP"),
+        (_, "SOUND_MENU") => Slot::Menu("sound_sounds_menu", "SOUND_MENU"),
+        (_, "CLONE_OPTION") => Slot::Menu("control_create_clone_of_menu", "CLONE_OPTION"),
+        (_, "TOUCHINGOBJECTMENU") => Slot::Menu("sensing_touchingobjectmenu", "TOUCHINGOBJECTMENU"),
+        (_, "DISTANCETOMENU") => Slot::Menu("sensing_distancetomenu", "DISTANCETOMENU"),
+        (_, "KEY_OPTION") => Slot::Menu("sensing_keyoptions", "KEY_OPTION"),
+        (_, "OBJECT") => Slot::Menu("sensing_of_object_menu", "OBJECT"),
+        (_, "COLOR_PARAM") => Slot::Menu("pen_menu_colorParam", "colorParam"),
+        (_, "DRUM") => Slot::Menu("music_menu_DRUM", "DRUM"),
+        (_, "INSTRUMENT") => Slot::Menu("music_menu_INSTRUMENT", "INSTRUMENT"),
+        (_, "NOTE") => Slot::Menu("note", "NOTE"),
+        (_, "ATTRIBUTE") => Slot::Menu("videoSensing_menu_ATTRIBUTE", "ATTRIBUTE"),
+        (_, "SUBJECT") => Slot::Menu("videoSensing_menu_SUBJECT", "SUBJECT"),
+        (_, "VIDEO_STATE") => Slot::Menu("videoSensing_menu_VIDEO_STATE", "VIDEO_STATE"),
+        (_, "VOICE") => Slot::Menu("text2speech_menu_voices", "voices"),
+        ("text2speech_setLanguage", "LANGUAGE") => {
+            Slot::Menu("text2speech_menu_languages", "languages")
+        }
+        (_, "LANGUAGE") => Slot::Menu("translate_menu_languages", "languages"),
+        (_, "BROADCAST_INPUT") => Slot::Broadcast,
+        (_, "DIRECTION") => Slot::Literal(LiteralKind::Angle),
+        (_, "DURATION") => Slot::Literal(LiteralKind::PositiveNumber),
+        (_, "TIMES" | "LETTER") | ("control_for_each", "VALUE") => {
+            Slot::Literal(LiteralKind::PositiveInteger)
+        }
+        (_, "INDEX") | ("looks_goforwardbackwardlayers", "NUM") => {
+            Slot::Literal(LiteralKind::Integer)
+        }
+        (_, "COLOR" | "COLOR2") => Slot::Literal(LiteralKind::Color),
+        (
+            _,
+            "MESSAGE" | "QUESTION" | "STRING" | "STRING1" | "STRING2" | "OPERAND1" | "OPERAND2"
+            | "ITEM" | "WORDS",
+        )
+        | ("data_setvariableto", "VALUE") => Slot::Literal(LiteralKind::String),
+        _ => Slot::Literal(LiteralKind::Number),
+    }
+}
+
+/// Options of `[OPERATOR] of {NUM}`, which has the same text as `[PROPERTY] of {OBJECT}`.
+const MATHOPS: &[&str] = &[
+    "abs", "floor", "ceiling", "sqrt", "sin", "cos", "tan", "asin", "acos", "atan", "ln", "log",
+    "e ^", "10 ^",
+];
+
+/// A word or a bracket of a line of scratchblocks.
+#[derive(Debug, Clone)]
+struct Node {
+    kind: NodeKind,
+    /// Column of the first character
+    column: usize,
+    /// Column after the last character
+    end: usize,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    Word(String),
+    /// `(...)`, a menu when it ends with ` v`
+    Round {
+        nodes: Vec<Node>,
+        text: String,
+        menu: bool,
+    },
+    /// `[...]`, a dropdown when it ends with ` v`
+    Square {
+        text: String,
+        menu: bool,
+    },
+    /// `<...>`
+    Angle {
+        nodes: Vec<Node>,
+        text: String,
+    },
+}
+
+struct Lexer<'a> {
+    chars: &'a [char],
+    pos: usize,
+    line: usize,
+}
+
+impl Lexer<'_> {
+    /// Nodes of the line and the comment at the end of it.
+    fn line(&mut self) -> Result<(Vec<Node>, Option<String>), ParseError> {
+        let nodes = self.nodes(None)?;
+        let comment = (self.pos < self.chars.len()).then(|| {
+            self.chars[self.pos + 2..]
+                .iter()
+                .collect::<String>()
+                .trim()
+                .to_owned()
+        });
+        Ok((nodes, comment))
+    }
+
+    /// Nodes up to the closing bracket, or up to the end of the line or a comment when there is none.
+    fn nodes(&mut self, close: Option<(char, usize)>) -> Result<Vec<Node>, ParseError> {
+        let mut nodes = vec![];
+        let mut word: Option<(String, usize)> = None;
+        let flush = |nodes: &mut Vec<Node>, word: &mut Option<(String, usize)>, end: usize| {
+            if let Some((text, column)) = word.take() {
+                nodes.push(Node {
+                    kind: NodeKind::Word(text),
+                    column,
+                    end,
+                });
+            }
+        };
+        loop {
+            let column = self.pos + 1;
+            let Some(c) = self.at(self.pos) else {
+                flush(&mut nodes, &mut word, column);
+                return match close {
+                    Some((close, column)) => Err(self.error(column, format!("missing `{close}`"))),
+                    None => Ok(nodes),
+                };
+            };
+            // `<` and `>` with spaces around are the operators, not brackets
+            let spaced = self.pos > 0
+                && self.at(self.pos - 1) == Some(' ')
+                && self.at(self.pos + 1) == Some(' ');
+            match c {
+                ' ' | '\t' => {
+                    flush(&mut nodes, &mut word, column);
+                    self.pos += 1;
+                }
+                ')' | ']' | '>' if close.map(|(c, _)| c) == Some(c) && !(c == '>' && spaced) => {
+                    flush(&mut nodes, &mut word, column);
+                    self.pos += 1;
+                    return Ok(nodes);
+                }
+                '(' | '[' | '<' if !(c == '<' && spaced) => {
+                    flush(&mut nodes, &mut word, column);
+                    nodes.push(self.bracket(c)?);
+                }
+                ')' | ']' => return Err(self.error(column, format!("unexpected `{c}`"))),
+                '/' if close.is_none() && self.at(self.pos + 1) == Some('/') => {
+                    flush(&mut nodes, &mut word, column);
+                    return Ok(nodes);
+                }
+                _ => {
+                    let (c, len) = match (c, self.at(self.pos + 1)) {
+                        ('\\', Some(escaped)) => (escaped, 2),
+                        _ => (c, 1),
+                    };
+                    word.get_or_insert_with(|| (String::new(), column))
+                        .0
+                        .push(c);
+                    self.pos += len;
+                }
+            }
+        }
+    }
+
+    fn bracket(&mut self, open: char) -> Result<Node, ParseError> {
+        let column = self.pos + 1;
+        self.pos += 1;
+        let start = self.pos;
+        let kind = match open {
+            '[' => {
+                // Text has no brackets inside
+                loop {
+                    match self.at(self.pos) {
+                        None => return Err(self.error(column, "missing `]`")),
+                        Some('\\') => self.pos += 2,
+                        Some(']') => break,
+                        Some(_) => self.pos += 1,
+                    }
+                }
+                let (text, menu) = self.text(start, self.pos);
+                self.pos += 1;
+                NodeKind::Square { text, menu }
+            }
+            '(' => {
+                let nodes = self.nodes(Some((')', column)))?;
+                let (text, menu) = self.text(start, self.pos - 1);
+                NodeKind::Round { nodes, text, menu }
+            }
+            _ => {
+                let nodes = self.nodes(Some(('>', column)))?;
+                let (text, _) = self.text(start, self.pos - 1);
+                NodeKind::Angle { nodes, text }
+            }
+        };
+        Ok(Node {
+            kind,
+            column,
+            end: self.pos + 1,
+        })
+    }
+
+    /// Text between the columns without its escapes, and whether it ends with a ` v` that is not escaped.
+    fn text(&self, start: usize, end: usize) -> (String, bool) {
+        let mut text = String::new();
+        let mut escaped = false;
+        let mut i = start;
+        while i < end {
+            escaped = self.chars[i] == '\\' && i + 1 < end;
+            if escaped {
+                i += 1;
+            }
+            text.push(self.chars[i]);
+            i += 1;
+        }
+        match text.strip_suffix(" v") {
+            Some(option) if !escaped => (option.to_owned(), true),
+            _ => (text, false),
+        }
+    }
+
+    fn at(&self, pos: usize) -> Option<char> {
+        self.chars.get(pos).copied()
+    }
+
+    fn error(&self, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+/// Custom block defined or called in the text.
+struct Procedure {
+    argumentids: Vec<Uid>,
+    warp: bool,
+}
+
+/// What a `define` line says.
+struct Prototype {
+    proccode: String,
+    /// Names of the arguments, true for a boolean one
+    arguments: Vec<(Name, bool)>,
+    warp: bool,
+}
+
+/// A C block which mouths are being filled.
+struct Frame {
+    block: BlockBuilder,
+    mouths: Vec<StackBuilder>,
+}
+
+struct Parser {
+    /// Custom blocks by proccode
+    procedures: HashMap<String, Procedure>,
+    /// Arguments of the custom block being defined, true for a boolean one
+    arguments: Vec<(Name, bool)>,
+    /// Characters of the line being read
+    source: Vec<char>,
+    line: usize,
+}
+
+/// Read scratchblocks text into stacks of blocks.
+fn parse(text: &str) -> Result<Vec<StackBuilder>, ParseError> {
+    let mut lines = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let (nodes, comment) = Lexer {
+            chars: &chars,
+            pos: 0,
+            line: i + 1,
+        }
+        .line()?;
+        lines.push((chars, nodes, comment));
+    }
+
+    let mut parser = Parser {
+        procedures: HashMap::new(),
+        arguments: vec![],
+        source: vec![],
+        line: 0,
+    };
+    // Custom blocks can be called above their definition
+    for (i, (chars, nodes, comment)) in lines.iter().enumerate() {
+        parser.line = i + 1;
+        parser.source.clone_from(chars);
+        if is_word(nodes.first(), "define") {
+            let prototype = parser.prototype(nodes, comment.as_deref())?;
+            parser
+                .procedures
+                .entry(prototype.proccode)
+                .or_insert_with(|| Procedure {
+                    argumentids: prototype
+                        .arguments
+                        .iter()
+                        .map(|_| uid::generate())
+                        .collect(),
+                    warp: prototype.warp,
+                });
+        }
+    }
+
+    let mut stacks = vec![];
+    let mut stack = StackBuilder::new();
+    let mut frames: Vec<Frame> = vec![];
+    for (i, (chars, nodes, comment)) in lines.into_iter().enumerate() {
+        parser.line = i + 1;
+        parser.source = chars;
+        let column = nodes.first().map_or(1, |n| n.column);
+
+        if nodes.is_empty() {
+            // An empty line ends the script, a line with only a comment doesn't
+            if comment.is_none() {
+                finish(&mut stacks, &mut stack, &mut frames);
+                parser.arguments.clear();
+            }
+            continue;
+        }
+        if nodes.len() == 1 && is_word(nodes.first(), "end") {
+            let Some(frame) = frames.pop() else {
+                return Err(parser.error(column, "`end` without a C block to close"));
+            };
+            push(&mut stack, &mut frames, close(frame));
+            continue;
+        }
+        if nodes.len() == 1 && is_word(nodes.first(), "else") {
+            match frames.last_mut() {
+                Some(frame) if frame.block.opcode == "control_if" => {
+                    frame.block.opcode = "control_if_else".to_owned();
+                    frame.mouths.push(StackBuilder::new());
+                }
+                _ => return Err(parser.error(column, "`else` outside of an if")),
+            }
+            continue;
+        }
+        if is_word(nodes.first(), "define") {
+            finish(&mut stacks, &mut stack, &mut frames);
+            let prototype = parser.prototype(&nodes, comment.as_deref())?;
+            stack = StackBuilder::new().then(parser.definition(&prototype));
+            parser.arguments = prototype.arguments;
+            continue;
+        }
+
+        let block = parser.stack_block(&nodes)?;
+        match KnownOpcode::from(block.opcode.as_str()).shape() {
+            Some(Shape::C) => frames.push(Frame {
+                block,
+                mouths: vec![StackBuilder::new()],
+            }),
+            _ => push(&mut stack, &mut frames, block),
+        }
+    }
+    finish(&mut stacks, &mut stack, &mut frames);
+    Ok(stacks)
+}
+
+/// Add the block to the mouth being filled, or to the script if there is none.
+fn push(stack: &mut StackBuilder, frames: &mut [Frame], block: BlockBuilder) {
+    let stack = match frames.last_mut() {
+        Some(frame) => frame.mouths.last_mut().unwrap(),
+        None => stack,
+    };
+    *stack = std::mem::take(stack).then(block);
+}
+
+/// Close the C blocks left open and add the script to the stacks.
+fn finish(stacks: &mut Vec<StackBuilder>, stack: &mut StackBuilder, frames: &mut Vec<Frame>) {
+    while let Some(frame) = frames.pop() {
+        push(stack, frames, close(frame));
+    }
+    let stack = std::mem::take(stack);
+    if stack != StackBuilder::new() {
+        stacks.push(stack);
+    }
+}
+
+fn close(frame: Frame) -> BlockBuilder {
+    let mut mouths = frame.mouths.into_iter();
+    let mut block = frame
+        .block
+        .substack("SUBSTACK", mouths.next().unwrap_or_default());
+    if let Some(mouth) = mouths.next() {
+        block = block.substack("SUBSTACK2", mouth);
+    }
+    block
+}
+
+impl Parser {
+    fn prototype(&self, nodes: &[Node], comment: Option<&str>) -> Result<Prototype, ParseError> {
+        let mut parts = vec![];
+        let mut arguments = vec![];
+        for node in &nodes[1..] {
+            match &node.kind {
+                NodeKind::Word(label) => parts.push(label.clone()),
+                NodeKind::Round { text, .. } => {
+                    parts.push("%s".to_owned());
+                    arguments.push((text.clone(), false));
+                }
+                NodeKind::Angle { text, .. } => {
+                    parts.push("%b".to_owned());
+                    arguments.push((text.clone(), true));
+                }
+                NodeKind::Square { .. } => {
+                    return Err(self.error(node.column, "expected a label or an argument"))
+                }
+            }
+        }
+        if parts.is_empty() {
+            return Err(self.error(nodes[0].end, "expected the text of the custom block"));
+        }
+        Ok(Prototype {
+            proccode: parts.join(" "),
+            arguments,
+            warp: comment == Some("run without screen refresh"),
+        })
+    }
+
+    /// `procedures_definition` with the prototype in it.
+    fn definition(&self, prototype: &Prototype) -> BlockBuilder {
+        let Prototype {
+            proccode,
+            arguments,
+            ..
+        } = prototype;
+        let procedure = &self.procedures[proccode];
+        let mut block = BlockBuilder::new("procedures_prototype").mutation(mutation(
+            BlockMutationEnum::ProceduresPrototype {
+                proccode: proccode.clone(),
+                argumentids: procedure.argumentids.clone(),
+                argumentnames: arguments.iter().map(|(name, _)| name.clone()).collect(),
+                argumentdefaults: arguments
+                    .iter()
+                    .map(|(_, boolean)| match boolean {
+                        true => ValueWithBool::Text("false".to_owned()),
+                        false => ValueWithBool::Text(String::new()),
+                    })
+                    .collect(),
+                warp: Some(procedure.warp),
+            },
+        ));
+        for ((name, boolean), id) in arguments.iter().zip(&procedure.argumentids) {
+            block = block.shadow_input(id, argument_reporter(name, *boolean));
+        }
+        BlockBuilder::new("procedures_definition").shadow_input("custom_block", block)
+    }
+
+    /// Block of a line that is not `define`, `else` or `end`.
+    fn stack_block(&mut self, nodes: &[Node]) -> Result<BlockBuilder, ParseError> {
+        let (inner, hint) = split_hint(nodes);
+        match (hint.as_deref(), inner) {
+            (Some("custom"), _) => return self.custom_call(nodes, inner),
+            (Some("grey"), [node]) => {
+                if let NodeKind::Word(opcode) = &node.kind {
+                    return Ok(BlockBuilder::new(opcode.as_str()));
+                }
+            }
+            _ => {}
+        }
+        // A reporter on its own
+        if let [node] = nodes {
+            if matches!(node.kind, NodeKind::Round { .. } | NodeKind::Angle { .. }) {
+                return match self.reporter(node)? {
+                    Arg::Block(block) => Ok(block),
+                    Arg::Variable(name) => Ok(
+                        BlockBuilder::new("data_variable").data_field(DataKind::Variable, &name)
+                    ),
+                    Arg::List(name) => Ok(
+                        BlockBuilder::new("data_listcontents").data_field(DataKind::List, &name)
+                    ),
+                    Arg::Value(_) => Err(self.error(node.column, "expected a block")),
+                };
+            }
+        }
+        if let Some(call) = self.call(nodes, false)? {
+            return Ok(call);
+        }
+        match self.template_block(nodes)? {
+            Some(block) => Ok(block),
+            None => Err(self.unknown(nodes)),
+        }
+    }
+
+    /// What a round or angle bracket that is not a menu puts in an input.
+    fn reporter(&mut self, node: &Node) -> Result<Arg, ParseError> {
+        let (nodes, text, boolean) = match &node.kind {
+            NodeKind::Round { nodes, text, .. } => (nodes, text, false),
+            NodeKind::Angle { nodes, text } => (nodes, text, true),
+            NodeKind::Word(text) | NodeKind::Square { text, .. } => {
+                return Ok(Arg::Value(Value::Text(text.clone())))
+            }
+        };
+        let (inner, hint) = split_hint(nodes);
+        let name = match text.rsplit_once(" :: ") {
+            Some((name, _)) if hint.is_some() => name,
+            _ => text.as_str(),
+        };
+        match (hint.as_deref(), inner) {
+            (Some("list"), _) => return Ok(Arg::List(name.to_owned())),
+            (Some("variables"), _) => return Ok(Arg::Variable(name.to_owned())),
+            (Some("custom"), _) => return Ok(Arg::Block(self.custom_call(nodes, inner)?)),
+            (Some("grey"), [node]) => {
+                if let NodeKind::Word(opcode) = &node.kind {
+                    return Ok(Arg::Block(BlockBuilder::new(opcode.as_str())));
+                }
+            }
+            _ => {}
+        }
+        if inner.is_empty() {
+            return Ok(Arg::Value(Value::Text(String::new())));
+        }
+        if is_plain(inner) && self.arguments.contains(&(name.to_owned(), boolean)) {
+            return Ok(Arg::Block(argument_reporter(name, boolean)));
+        }
+        if let Some(block) = self.template_block(inner)? {
+            return Ok(Arg::Block(block));
+        }
+        match !boolean && is_plain(inner) {
+            true => Ok(Arg::Variable(name.to_owned())),
+            false => Err(self.unknown(inner)),
+        }
+    }
+
+    /// What a bracket puts in an input that takes a typed in value.
+    fn literal(&mut self, node: &Node) -> Result<Arg, ParseError> {
+        match &node.kind {
+            NodeKind::Round {
+                nodes,
+                text,
+                menu: false,
+            } if is_plain(nodes)
+                && !self.arguments.contains(&(text.clone(), false))
+                && (text.is_empty() || !js_number(text).is_nan()) =>
+            {
+                Ok(Arg::Value(Value::Text(text.clone())))
+            }
+            NodeKind::Round {
+                text, menu: true, ..
+            } => Ok(Arg::Value(Value::Text(text.clone()))),
+            _ => self.reporter(node),
+        }
+    }
+
+    /// What a bracket puts in a menu input, the field of the menu is used to read its option.
+    fn menu(&mut self, node: &Node, field: Option<&str>) -> Result<Arg, ParseError> {
+        match &node.kind {
+            NodeKind::Round {
+                text, menu: true, ..
+            }
+            | NodeKind::Square { text, .. } => Ok(Arg::Value(Value::Text(match field {
+                Some(field) => field_value(field, text),
+                None => text.clone(),
+            }))),
+            _ => self.reporter(node),
+        }
+    }
+
+    /// Block of the first template the nodes fit, see [`TEMPLATES`].
+    fn template_block(&mut self, nodes: &[Node]) -> Result<Option<BlockBuilder>, ParseError> {
+        let fitting: Vec<_> = TEMPLATES
+            .iter()
+            // `if` becomes `if else` when the `else` line comes
+            .filter(|(opcode, _)| *opcode != "control_if_else")
+            .filter(|(opcode, template)| fits(opcode, template, nodes))
+            .collect();
+        // Some blocks have the same text, the option of their dropdown tells which one it is
+        let Some((opcode, template)) = fitting
+            .iter()
+            .find(|(opcode, template)| prefers(opcode, template, nodes))
+            .or(fitting.first())
+        else {
+            return Ok(None);
+        };
+
+        let mut block = BlockBuilder::new(*opcode);
+        for (pattern, node) in patterns(template).into_iter().zip(nodes) {
+            let text = match &node.kind {
+                NodeKind::Square { text, .. } => text.as_str(),
+                _ => "",
+            };
+            block = match pattern {
+                Pattern::Word(_) => block,
+                Pattern::Slot(Piece::Field("STOP_OPTION")) => control::stop(text),
+                Pattern::Slot(Piece::Field(name)) => match DataKind::from_field_name(name) {
+                    Some(kind) => block.data_field(kind, text),
+                    None => block.field(name, field_value(name, text)),
+                },
+                Pattern::Slot(Piece::Boolean(name)) => match self.reporter(node)? {
+                    Arg::Block(reporter) => block.boolean_input(name, reporter),
+                    Arg::Value(_) => block,
+                    Arg::Variable(_) | Arg::List(_) => {
+                        return Err(self.error(node.column, "expected a boolean block"))
+                    }
+                },
+                Pattern::Slot(Piece::Input(name)) => match slot(opcode, name) {
+                    Slot::Literal(kind) => block.input(name, kind, self.literal(node)?),
+                    Slot::Menu(menu, field) => {
+                        block.menu(name, menu, field, self.menu(node, Some(field))?)
+                    }
+                    Slot::Broadcast => block.broadcast_input(name, self.menu(node, None)?),
+                },
+                Pattern::Slot(Piece::Text(_)) => unreachable!(),
+            };
+        }
+        Ok(Some(block))
+    }
+
+    /// Call of the custom block marked `:: custom`, `inner` being the nodes before the `::`.
+    fn custom_call(&mut self, nodes: &[Node], inner: &[Node]) -> Result<BlockBuilder, ParseError> {
+        match self.call(inner, true)? {
+            Some(call) => Ok(call),
+            None => Err(self.error(
+                nodes[inner.len()].column,
+                "expected the text of the custom block",
+            )),
+        }
+    }
+
+    /// Call of the custom block the nodes make.
+    /// [`None`] if it is defined nowhere, unless `declare` is set.
+    fn call(&mut self, nodes: &[Node], declare: bool) -> Result<Option<BlockBuilder>, ParseError> {
+        if nodes.is_empty() {
+            return Ok(None);
+        }
+        let proccode = nodes
+            .iter()
+            .map(|node| match &node.kind {
+                NodeKind::Word(label) => label.as_str(),
+                NodeKind::Angle { .. } => "%b",
+                NodeKind::Round { .. } | NodeKind::Square { .. } => "%s",
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !self.procedures.contains_key(&proccode) {
+            if !declare {
+                return Ok(None);
+            }
+            let argumentids = nodes
+                .iter()
+                .filter(|n| !matches!(n.kind, NodeKind::Word(_)))
+                .map(|_| uid::generate())
+                .collect();
+            self.procedures.insert(
+                proccode.clone(),
+                Procedure {
+                    argumentids,
+                    warp: false,
+                },
+            );
+        }
+        let procedure = &self.procedures[&proccode];
+        let argumentids = procedure.argumentids.clone();
+        let mut block = BlockBuilder::new("procedures_call").mutation(mutation(
+            BlockMutationEnum::ProceduresCall {
+                proccode,
+                argumentids: argumentids.clone(),
+                warp: Some(procedure.warp),
+            },
+        ));
+        let arguments = nodes
+            .iter()
+            .filter(|n| !matches!(n.kind, NodeKind::Word(_)));
+        for (node, id) in arguments.zip(&argumentids) {
+            block = match &node.kind {
+                NodeKind::Angle { .. } => match self.reporter(node)? {
+                    Arg::Block(reporter) => block.boolean_input(id, reporter),
+                    _ => block,
+                },
+                _ => block.input(id, LiteralKind::String, self.literal(node)?),
+            };
+        }
+        Ok(Some(block))
+    }
+
+    fn unknown(&self, nodes: &[Node]) -> ParseError {
+        let start = nodes[0].column;
+        let end = nodes[nodes.len() - 1].end;
+        let text: String = self.source[start - 1..end - 1].iter().collect();
+        self.error(start, format!("unknown block `{text}`"))
+    }
+
+    fn error(&self, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+/// Part of a template as it is matched against nodes.
+#[derive(Debug, Clone, Copy)]
+enum Pattern<'a> {
+    Word(&'a str),
+    Slot(Piece<'a>),
+}
+
+fn patterns(template: &str) -> Vec<Pattern<'_>> {
+    pieces(template)
+        .into_iter()
+        .flat_map(|piece| match piece {
+            Piece::Text(text) => text.split_whitespace().map(Pattern::Word).collect(),
+            slot => vec![Pattern::Slot(slot)],
+        })
+        .collect()
+}
+
+/// Whether the nodes have the words of the template and brackets that go in its slots.
+fn fits(opcode: &str, template: &str, nodes: &[Node]) -> bool {
+    let patterns = patterns(template);
+    patterns.len() == nodes.len()
+        && patterns
+            .iter()
+            .zip(nodes)
+            .all(|(pattern, node)| match (pattern, &node.kind) {
+                (Pattern::Word(word), NodeKind::Word(text)) => word == text,
+                (Pattern::Slot(Piece::Field(_)), NodeKind::Square { menu, .. }) => *menu,
+                (Pattern::Slot(Piece::Boolean(_)), NodeKind::Angle { .. }) => true,
+                (Pattern::Slot(Piece::Input(name)), kind) => match (slot(opcode, name), kind) {
+                    (_, NodeKind::Angle { .. }) => true,
+                    (Slot::Literal(_), NodeKind::Round { menu, .. })
+                    | (Slot::Literal(_), NodeKind::Square { menu, .. }) => !menu,
+                    (_, NodeKind::Round { .. }) => true,
+                    (_, NodeKind::Square { menu, .. }) => *menu,
+                    (_, NodeKind::Word(_)) => false,
+                },
+                _ => false,
+            })
+}
+
+/// Whether the options in the dropdowns of the nodes belong to the block with the template.
+fn prefers(opcode: &str, template: &str, nodes: &[Node]) -> bool {
+    patterns(template).iter().zip(nodes).all(|(pattern, node)| {
+        let (Pattern::Slot(Piece::Field(name)), NodeKind::Square { text, .. }) =
+            (pattern, &node.kind)
+        else {
+            return true;
+        };
+        match *name {
+            "EFFECT" => {
+                let sound = matches!(field_value(name, text).as_str(), "PITCH" | "PAN");
+                opcode.starts_with("sound_") == sound
+            }
+            "OPERATOR" => MATHOPS.contains(&text.as_str()),
+            "PROPERTY" => !MATHOPS.contains(&text.as_str()),
+            _ => true,
+        }
+    })
+}
+
+/// The nodes before `::` and the words after it, such as `list` in `(items :: list)`.
+fn split_hint(nodes: &[Node]) -> (&[Node], Option<String>) {
+    let Some(i) = nodes
+        .iter()
+        .rposition(|n| matches!(&n.kind, NodeKind::Word(w) if w == "::"))
+    else {
+        return (nodes, None);
+    };
+    let hint = nodes[i + 1..]
+        .iter()
+        .filter_map(|n| match &n.kind {
+            NodeKind::Word(w) => Some(w.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    (&nodes[..i], Some(hint))
+}
+
+fn is_word(node: Option<&Node>, word: &str) -> bool {
+    matches!(node, Some(Node { kind: NodeKind::Word(w), .. }) if w == word)
+}
+
+/// Whether the nodes are only words, such as the name of a variable.
+fn is_plain(nodes: &[Node]) -> bool {
+    nodes.iter().all(|n| matches!(n.kind, NodeKind::Word(_)))
+}
+
+/// Value of a field as it is in project.json, see [`menu_text`].
+fn field_value(name: &str, text: &str) -> String {
+    if let Some((value, _)) = MENU_NAMES.iter().find(|(_, shown)| *shown == text) {
+        return (*value).to_owned();
+    }
+    match CAPITAL_FIELDS.contains(&name) {
+        true => text.to_uppercase(),
+        false => text.to_owned(),
+    }
+}
+
+fn argument_reporter(name: &str, boolean: bool) -> BlockBuilder {
+    let opcode = match boolean {
+        true => "argument_reporter_boolean",
+        false => "argument_reporter_string_number",
+    };
+    BlockBuilder::new(opcode).field("VALUE", name)
+}
+
+fn mutation(mutation_enum: BlockMutationEnum) -> BlockMutation {
+    BlockMutation {
+        tag_name: "mutation".to_owned(),
+        children: vec![],
+        mutation_enum,
+    }
+}
+
+// Error impl ==================================================================
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            return f.write_str(&self.message);
+        }
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,337 @@
+//! Module to walk scripts
+//!
+//! [`Target::blocks`] is a flat map of blocks linked to each other by Id.
+//! This module gives a read-only tree view over it, starting from the top level blocks.
+//! Ids that point at nothing and links that loop back are reported as [`Error`].
+
+use crate::block::{Block, BlockInputValue, BlockNormal, UidOrValue};
+use crate::prelude::*;
+use crate::target::Target;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A script, the stack of blocks starting from a top level block.
+#[derive(Debug, Clone, Copy)]
+pub struct Script<'a> {
+    top: BlockRef<'a>,
+}
+
+/// A block in a blocks map, which can follow links to other blocks.
+#[derive(Debug, Clone, Copy)]
+pub struct BlockRef<'a> {
+    blocks: &'a StringHashMap<Block>,
+    id: &'a Uid,
+    block: &'a Block,
+}
+
+/// What is put inside an input.
+#[derive(Debug, Clone, Copy)]
+pub enum InputRef<'a> {
+    /// A block, such as a reporter, a menu or the first block of a C mouth
+    Block(BlockRef<'a>),
+
+    /// A literal value
+    Value(&'a BlockInputValue),
+}
+
+/// Iterator over a stack of blocks, see [`BlockRef::stack`]
+#[derive(Debug, Clone)]
+pub struct Stack<'a> {
+    next: Option<Result<BlockRef<'a>, Error>>,
+    visited: HashSet<&'a Uid>,
+}
+
+/// Iterator over the parent chain of a block, see [`BlockRef::parents`]
+#[derive(Debug, Clone)]
+pub struct Parents<'a> {
+    next: Option<Result<BlockRef<'a>, Error>>,
+    visited: HashSet<&'a Uid>,
+}
+
+/// Error when following links between blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A block refers to an Id that is not in the blocks map
+    Dangling {
+        /// Id of the block that has the link
+        from: Uid,
+        /// Id that points at nothing
+        to: Uid,
+    },
+
+    /// Following links came back to a block that was already visited
+    Cycle {
+        /// Id of the block visited twice
+        id: Uid,
+    },
+}
+
+impl Target {
+    /// Every script in the target, one for each top level block.
+    pub fn scripts(&self) -> Vec<Script<'_>> {
+        scripts(&self.blocks)
+    }
+}
+
+/// Every script in the blocks map, one for each top level block.
+pub fn scripts(blocks: &StringHashMap<Block>) -> Vec<Script<'_>> {
+    blocks
+        .0
+        .iter()
+        .filter(|(_, block)| match block {
+            Block::Normal(b) => b.top_level,
+            Block::VarList(_) => true,
+        })
+        .map(|(id, block)| Script {
+            top: BlockRef { blocks, id, block },
+        })
+        .collect()
+}
+
+impl<'a> Script<'a> {
+    /// The top level block.
+    pub fn top(&self) -> BlockRef<'a> {
+        self.top
+    }
+
+    /// Id of the top level block.
+    pub fn id(&self) -> &'a Uid {
+        self.top.id
+    }
+
+    /// The blocks of the script in order, without the blocks inside C mouths and inputs.
+    pub fn stack(&self) -> Stack<'a> {
+        self.top.stack()
+    }
+
+    /// Every block of the script depth first, including the blocks inside C mouths and inputs.
+    pub fn walk(&self) -> Result<Vec<BlockRef<'a>>, Error> {
+        self.top.walk()
+    }
+}
+
+impl<'a> BlockRef<'a> {
+    /// Block with the Id in the blocks map.
+    pub fn new(blocks: &'a StringHashMap<Block>, id: &Uid) -> Option<BlockRef<'a>> {
+        let (id, block) = blocks.0.get_key_value(id)?;
+        Some(BlockRef { blocks, id, block })
+    }
+
+    /// Id of the block.
+    pub fn id(&self) -> &'a Uid {
+        self.id
+    }
+
+    /// The block.
+    pub fn block(&self) -> &'a Block {
+        self.block
+    }
+
+    /// The block, if it's [`Block::Normal`]
+    pub fn normal(&self) -> Option<&'a BlockNormal> {
+        match self.block {
+            Block::Normal(b) => Some(b),
+            Block::VarList(_) => None,
+        }
+    }
+
+    /// Opcode of the block, [`None`] if it's [`Block::VarList`]
+    pub fn opcode(&self) -> Option<&'a str> {
+        self.normal().map(|b| b.opcode.as_str())
+    }
+
+    /// The next block in the stack.
+    pub fn next(&self) -> Result<Option<BlockRef<'a>>, Error> {
+        self.follow(self.normal().and_then(|b| b.next.as_ref()))
+    }
+
+    /// The parent block. See [`BlockNormal::parent`]
+    pub fn parent(&self) -> Result<Option<BlockRef<'a>>, Error> {
+        self.follow(self.normal().and_then(|b| b.parent.as_ref()))
+    }
+
+    /// This block and the blocks after it.
+    pub fn stack(&self) -> Stack<'a> {
+        Stack {
+            next: Some(Ok(*self)),
+            visited: HashSet::new(),
+        }
+    }
+
+    /// The parent, the parent of the parent and so on until the top level block.
+    pub fn parents(&self) -> Parents<'a> {
+        let mut visited = HashSet::new();
+        visited.insert(self.id);
+        Parents {
+            next: self.parent().transpose(),
+            visited,
+        }
+    }
+
+    /// What is put inside the input with the name.
+    /// If a reporter is obscuring a shadow, this is the reporter.
+    pub fn input(&self, name: &str) -> Result<Option<InputRef<'a>>, Error> {
+        let Some(input) = self.normal().and_then(|b| b.inputs.0.get(name)) else {
+            return Ok(None);
+        };
+        match input.inputs.first() {
+            Some(Some(v)) => self.input_ref(v).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The shadow obscured by a reporter in the input with the name.
+    pub fn obscured_shadow(&self, name: &str) -> Result<Option<InputRef<'a>>, Error> {
+        let Some(input) = self.normal().and_then(|b| b.inputs.0.get(name)) else {
+            return Ok(None);
+        };
+        match input.inputs.get(1) {
+            Some(Some(v)) => self.input_ref(v).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Every input with what is put inside it.
+    pub fn inputs(&self) -> Result<Vec<(&'a str, InputRef<'a>)>, Error> {
+        let Some(block) = self.normal() else {
+            return Ok(vec![]);
+        };
+        let mut inputs = vec![];
+        for name in block.inputs.0.keys() {
+            if let Some(input) = self.input(name)? {
+                inputs.push((name.as_str(), input));
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// First block in the C mouth with the name, such as `SUBSTACK` or `SUBSTACK2`.
+    pub fn substack(&self, name: &str) -> Result<Option<BlockRef<'a>>, Error> {
+        match self.input(name)? {
+            Some(InputRef::Block(b)) => Ok(Some(b)),
+            _ => Ok(None),
+        }
+    }
+
+    /// First blocks of every C mouth, `SUBSTACK` first then `SUBSTACK2`.
+    pub fn substacks(&self) -> Result<Vec<BlockRef<'a>>, Error> {
+        let mut substacks = vec![];
+        for name in ["SUBSTACK", "SUBSTACK2"] {
+            substacks.extend(self.substack(name)?);
+        }
+        Ok(substacks)
+    }
+
+    /// This block and every block after it, including the blocks inside C mouths and inputs, depth first.
+    pub fn walk(&self) -> Result<Vec<BlockRef<'a>>, Error> {
+        let mut blocks = vec![];
+        let mut visited = HashSet::new();
+        self.walk_into(&mut blocks, &mut visited)?;
+        Ok(blocks)
+    }
+
+    fn walk_into(
+        &self,
+        blocks: &mut Vec<BlockRef<'a>>,
+        visited: &mut HashSet<&'a Uid>,
+    ) -> Result<(), Error> {
+        for block in self.stack() {
+            let block = block?;
+            if !visited.insert(block.id) {
+                return Err(Error::Cycle {
+                    id: block.id.clone(),
+                });
+            }
+            blocks.push(block);
+            let Some(normal) = block.normal() else {
+                continue;
+            };
+            for input in normal.inputs.0.values() {
+                for value in input.inputs.iter().flatten() {
+                    if let InputRef::Block(b) = block.input_ref(value)? {
+                        b.walk_into(blocks, visited)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn input_ref(&self, value: &'a UidOrValue) -> Result<InputRef<'a>, Error> {
+        match value {
+            UidOrValue::Uid(id) => Ok(InputRef::Block(self.follow(Some(id))?.unwrap())),
+            UidOrValue::Value(v) => Ok(InputRef::Value(v)),
+        }
+    }
+
+    fn follow(&self, id: Option<&Uid>) -> Result<Option<BlockRef<'a>>, Error> {
+        let Some(id) = id else {
+            return Ok(None);
+        };
+        match BlockRef::new(self.blocks, id) {
+            Some(b) => Ok(Some(b)),
+            None => Err(Error::Dangling {
+                from: self.id.clone(),
+                to: id.clone(),
+            }),
+        }
+    }
+}
+
+impl PartialEq for BlockRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.blocks, other.blocks) && self.id == other.id
+    }
+}
+
+impl<'a> Iterator for Stack<'a> {
+    type Item = Result<BlockRef<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        if let Ok(block) = &current {
+            if !self.visited.insert(block.id) {
+                return Some(Err(Error::Cycle {
+                    id: block.id.clone(),
+                }));
+            }
+            self.next = block.next().transpose();
+        }
+        Some(current)
+    }
+}
+
+impl<'a> Iterator for Parents<'a> {
+    type Item = Result<BlockRef<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        if let Ok(block) = &current {
+            if !self.visited.insert(block.id) {
+                return Some(Err(Error::Cycle {
+                    id: block.id.clone(),
+                }));
+            }
+            self.next = block.parent().transpose();
+        }
+        Some(current)
+    }
+}
+
+// Error impl ==================================================================
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Dangling { from, to } => {
+                write!(
+                    f,
+                    "block `{from}` refers to block `{to}` which does not exist"
+                )
+            }
+            Error::Cycle { id } => write!(f, "block `{id}` is linked in a cycle"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,17 +1,75 @@
 //! Module to deal with Serde map
 
 use crate::prelude::*;
+use indexmap::IndexMap;
 use serde::de::MapAccess;
 use serde::ser::SerializeMap;
-use std::{collections::HashMap, fmt, marker::PhantomData};
+use std::{
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
 
-/// HashMap<String, V>
+/// Map of String to V that keeps the order of its entries.
+///
+/// Entries are serialized in the order they were deserialized or inserted,
+/// so a project round-trips with its keys in the original order.
+/// Use [`StringHashMap::sort_keys`] for a canonical order instead.
+/// Equality does not depend on the order.
+///
+/// Flattened into the `extra` field of the objects of project.json, it holds the keys
+/// this crate does not know about, so they are written back out instead of being dropped.
 #[derive(Debug, PartialEq, Clone, Eq)]
-pub struct StringHashMap<V>(pub HashMap<String, V>);
+pub struct StringHashMap<V>(pub IndexMap<String, V>);
+
+impl<V> StringHashMap<V> {
+    /// Sort the entries by key.
+    pub fn sort_keys(&mut self) {
+        self.0.sort_keys()
+    }
+}
 
 impl<V> Default for StringHashMap<V> {
     fn default() -> Self {
-        StringHashMap(HashMap::default())
+        StringHashMap(IndexMap::default())
+    }
+}
+
+impl<V> Deref for StringHashMap<V> {
+    type Target = IndexMap<String, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<V> DerefMut for StringHashMap<V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<V> FromIterator<(String, V)> for StringHashMap<V> {
+    fn from_iter<T: IntoIterator<Item = (String, V)>>(iter: T) -> Self {
+        StringHashMap(IndexMap::from_iter(iter))
+    }
+}
+
+impl<V> IntoIterator for StringHashMap<V> {
+    type Item = (String, V);
+    type IntoIter = indexmap::map::IntoIter<String, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a StringHashMap<V> {
+    type Item = (&'a String, &'a V);
+    type IntoIter = indexmap::map::Iter<'a, String, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
     }
 }
 
@@ -43,7 +101,7 @@ where
     where
         M: MapAccess<'de>,
     {
-        let map = HashMap::with_capacity(access.size_hint().unwrap_or(0));
+        let map = IndexMap::with_capacity(access.size_hint().unwrap_or(0));
         let mut map = StringHashMap(map);
 
         while let Some((key, value)) = access.next_entry::<String, _>()? {
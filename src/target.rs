@@ -48,6 +48,11 @@ pub struct Target {
 
     /// The volume
     pub volume: Number,
+
+    /// Unknown keys, see [`StringHashMap`]
+    /// This includes unknown keys of the [`Sprite`] or [`Stage`] this target belongs to.
+    #[serde(flatten)]
+    pub extra: StringHashMap<Json>,
 }
 
 /// Scratch's Stage.
@@ -70,9 +75,10 @@ pub struct Stage {
     /// or if the project does not use an extension with video input.
     pub video_transparency: Number,
 
-    /// The language of the Text to Speech extension. Defaults to the editor language.
-    // TODO: Create TextToSpeechLangage struct
-    pub text_to_speech_language: Option<Json>,
+    /// The language of the Text to Speech extension.
+    /// [`None`] (`null`) until a script sets it, Scratch then uses the editor language.
+    /// See [`TextToSpeechLanguage`]
+    pub text_to_speech_language: Option<TextToSpeechLanguage>,
 
     /// Always true for stage when serialize
     /// I wonder what happend if i insert false in here
@@ -121,6 +127,29 @@ pub enum SpriteOrStage {
     Sprite(Sprite),
 }
 
+impl SpriteOrStage {
+    /// See [`Target`]
+    pub fn target(&self) -> &Target {
+        match self {
+            SpriteOrStage::Stage(s) => &s.target,
+            SpriteOrStage::Sprite(s) => &s.target,
+        }
+    }
+
+    /// See [`Target`]
+    pub fn target_mut(&mut self) -> &mut Target {
+        match self {
+            SpriteOrStage::Stage(s) => &mut s.target,
+            SpriteOrStage::Sprite(s) => &mut s.target,
+        }
+    }
+
+    /// True if this is the stage.
+    pub fn is_stage(&self) -> bool {
+        matches!(self, SpriteOrStage::Stage(_))
+    }
+}
+
 /// Determines if video is visible on the stage and if it is flipped.
 /// Has no effect if the project does not use an extension with video input.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -162,6 +191,124 @@ pub enum RotationStyle {
     DontRotate,
 }
 
+/// A language of the Text to Speech extension, by its locale code.
+/// Used by [`Stage::text_to_speech_language`] and by the `languages` field of
+/// `text2speech_menu_languages`, the menu of `text2speech_setLanguage`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TextToSpeechLanguage {
+    /// Arabic, `ar`
+    Arabic,
+    /// Chinese (Mandarin), `zh-cn`
+    ChineseMandarin,
+    /// Danish, `da`
+    Danish,
+    /// Dutch, `nl`
+    Dutch,
+    /// English, `en`
+    English,
+    /// French, `fr`
+    French,
+    /// German, `de`
+    German,
+    /// Hindi, `hi`
+    Hindi,
+    /// Icelandic, `is`
+    Icelandic,
+    /// Italian, `it`
+    Italian,
+    /// Japanese, `ja`
+    Japanese,
+    /// Korean, `ko`
+    Korean,
+    /// Norwegian, `nb`
+    Norwegian,
+    /// Polish, `pl`
+    Polish,
+    /// Portuguese (Brazilian), `pt-br`
+    PortugueseBrazilian,
+    /// Portuguese, `pt`
+    Portuguese,
+    /// Romanian, `ro`
+    Romanian,
+    /// Russian, `ru`
+    Russian,
+    /// Spanish, `es`
+    Spanish,
+    /// Spanish (Latin American), `es-419`
+    LatinAmericanSpanish,
+    /// Swedish, `sv`
+    Swedish,
+    /// Turkish, `tr`
+    Turkish,
+    /// Welsh, `cy`
+    Welsh,
+    /// A code the extension does not know, Scratch falls back to English.
+    Other(String),
+}
+
+impl TextToSpeechLanguage {
+    /// The locale code, as it is in project.json.
+    pub fn as_str(&self) -> &str {
+        match self {
+            TextToSpeechLanguage::Arabic => "ar",
+            TextToSpeechLanguage::ChineseMandarin => "zh-cn",
+            TextToSpeechLanguage::Danish => "da",
+            TextToSpeechLanguage::Dutch => "nl",
+            TextToSpeechLanguage::English => "en",
+            TextToSpeechLanguage::French => "fr",
+            TextToSpeechLanguage::German => "de",
+            TextToSpeechLanguage::Hindi => "hi",
+            TextToSpeechLanguage::Icelandic => "is",
+            TextToSpeechLanguage::Italian => "it",
+            TextToSpeechLanguage::Japanese => "ja",
+            TextToSpeechLanguage::Korean => "ko",
+            TextToSpeechLanguage::Norwegian => "nb",
+            TextToSpeechLanguage::Polish => "pl",
+            TextToSpeechLanguage::PortugueseBrazilian => "pt-br",
+            TextToSpeechLanguage::Portuguese => "pt",
+            TextToSpeechLanguage::Romanian => "ro",
+            TextToSpeechLanguage::Russian => "ru",
+            TextToSpeechLanguage::Spanish => "es",
+            TextToSpeechLanguage::LatinAmericanSpanish => "es-419",
+            TextToSpeechLanguage::Swedish => "sv",
+            TextToSpeechLanguage::Turkish => "tr",
+            TextToSpeechLanguage::Welsh => "cy",
+            TextToSpeechLanguage::Other(code) => code,
+        }
+    }
+}
+
+impl From<&str> for TextToSpeechLanguage {
+    fn from(code: &str) -> Self {
+        match code {
+            "ar" => TextToSpeechLanguage::Arabic,
+            "zh-cn" => TextToSpeechLanguage::ChineseMandarin,
+            "da" => TextToSpeechLanguage::Danish,
+            "nl" => TextToSpeechLanguage::Dutch,
+            "en" => TextToSpeechLanguage::English,
+            "fr" => TextToSpeechLanguage::French,
+            "de" => TextToSpeechLanguage::German,
+            "hi" => TextToSpeechLanguage::Hindi,
+            "is" => TextToSpeechLanguage::Icelandic,
+            "it" => TextToSpeechLanguage::Italian,
+            "ja" => TextToSpeechLanguage::Japanese,
+            "ko" => TextToSpeechLanguage::Korean,
+            "nb" => TextToSpeechLanguage::Norwegian,
+            "pl" => TextToSpeechLanguage::Polish,
+            "pt-br" => TextToSpeechLanguage::PortugueseBrazilian,
+            "pt" => TextToSpeechLanguage::Portuguese,
+            "ro" => TextToSpeechLanguage::Romanian,
+            "ru" => TextToSpeechLanguage::Russian,
+            "es" => TextToSpeechLanguage::Spanish,
+            "es-419" => TextToSpeechLanguage::LatinAmericanSpanish,
+            "sv" => TextToSpeechLanguage::Swedish,
+            "tr" => TextToSpeechLanguage::Turkish,
+            "cy" => TextToSpeechLanguage::Welsh,
+            code => TextToSpeechLanguage::Other(code.to_owned()),
+        }
+    }
+}
+
 // Serde impl ==================================================================
 
 impl<'de> Deserialize<'de> for SpriteOrStage {
@@ -205,3 +352,22 @@ impl<'de> Deserialize<'de> for SpriteOrStage {
         }
     }
 }
+
+impl Serialize for TextToSpeechLanguage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for TextToSpeechLanguage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let code = String::deserialize(deserializer)?;
+        Ok(TextToSpeechLanguage::from(code.as_str()))
+    }
+}
@@ -0,0 +1,246 @@
+//! Module to deal with Scratch Ids
+
+use crate::block::{Block, BlockField, BlockMutationEnum, UidOrValue};
+use crate::prelude::*;
+use crate::project::Project;
+use crate::target::Target;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
+
+/// Characters Scratch uses to generate Ids.
+pub const SOUP: &str =
+    "!#%()*+,-./:;=?@[]^_`{|}~ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Length of an Id generated by Scratch.
+pub const LENGTH: usize = 20;
+
+/// Generate a random Id out of the same 87 characters and of the same length 20 as Scratch's.
+/// The randomness comes from a randomly keyed hasher, not from the same generator as Scratch.
+pub fn generate() -> Uid {
+    let soup = SOUP.as_bytes();
+    let mut hasher = RandomState::new().build_hasher();
+    (0..LENGTH)
+        .map(|i| {
+            hasher.write_usize(i);
+            soup[(hasher.finish() % soup.len() as u64) as usize] as char
+        })
+        .collect()
+}
+
+/// Generate a random Id that `is_used` says is not used yet.
+pub fn generate_unused<F>(mut is_used: F) -> Uid
+where
+    F: FnMut(&str) -> bool,
+{
+    loop {
+        let id = generate();
+        if !is_used(&id) {
+            return id;
+        }
+    }
+}
+
+/// How [`Project::remap_ids`] picks the new Ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdStyle {
+    /// Random Ids like Scratch makes, see [`generate`]
+    Random,
+    /// The shortest Ids there are, in order: `!`, `#`, ..., `9`, `!!`, `!#`, ...
+    /// Always the same for the same project, and makes project.json smaller.
+    Short,
+}
+
+/// Generate the nth shortest Id, see [`IdStyle::Short`]
+pub fn short(mut n: usize) -> Uid {
+    let soup = SOUP.as_bytes();
+    let mut id = vec![];
+    loop {
+        id.push(soup[n % soup.len()]);
+        n /= soup.len();
+        if n == 0 {
+            break;
+        }
+        n -= 1;
+    }
+    id.iter().rev().map(|&c| c as char).collect()
+}
+
+impl Project {
+    /// Give new Ids to every block, variable, list, broadcast, comment and custom block argument,
+    /// and to the monitors of variables and lists, then rename every reference to them.
+    ///
+    /// New Ids are unique in the whole project,
+    /// which also fixes Ids used twice, such as a local variable with the Id of a global one.
+    /// Monitors of things like the timer keep their Ids.
+    pub fn remap_ids(&mut self, style: IdStyle) {
+        let mut used: HashSet<Uid> = self.monitors.iter().map(|m| m.id.clone()).collect();
+        let mut next = 0;
+        let mut fresh = || loop {
+            let id = match style {
+                IdStyle::Random => generate(),
+                IdStyle::Short => {
+                    next += 1;
+                    short(next - 1)
+                }
+            };
+            if used.insert(id.clone()) {
+                return id;
+            }
+        };
+
+        // Blocks of sprites refer to their own Ids first, then to the Ids of the stage
+        let stage = self.targets.iter().find(|t| t.is_stage());
+        let stage_map: HashMap<Uid, Uid> = stage
+            .map(|stage| target_ids(stage.target()))
+            .into_iter()
+            .flatten()
+            .map(|id| (id, fresh()))
+            .collect();
+        let mut maps = vec![];
+        for target in &self.targets {
+            if target.is_stage() {
+                maps.push(stage_map.clone());
+                continue;
+            }
+            let mut map = stage_map.clone();
+            map.extend(target_ids(target.target()).map(|id| (id, fresh())));
+            maps.push(map);
+        }
+
+        for monitor in &mut self.monitors {
+            let map = match &monitor.sprite_name {
+                Some(name) => (self.targets.iter())
+                    .position(|t| !t.is_stage() && &t.target().name == name)
+                    .map(|i| &maps[i]),
+                None => Some(&stage_map),
+            };
+            if let Some(id) = map.and_then(|map| map.get(&monitor.id)) {
+                monitor.id = id.clone();
+            }
+        }
+        for (target, map) in self.targets.iter_mut().zip(&maps) {
+            remap_target(target.target_mut(), map);
+        }
+    }
+}
+
+/// Ids the target declares, the argument Ids of its custom blocks last.
+fn target_ids(target: &Target) -> impl Iterator<Item = Uid> + '_ {
+    let mut arguments = vec![];
+    for block in target.blocks.0.values() {
+        if let Some(argumentids) = block_argumentids(block) {
+            for id in argumentids {
+                if !arguments.contains(id) {
+                    arguments.push(id.clone());
+                }
+            }
+        }
+    }
+    (target.variables.0.keys())
+        .chain(target.lists.0.keys())
+        .chain(target.broadcasts.0.keys())
+        .chain(target.comments.0.keys())
+        .chain(target.blocks.0.keys())
+        .cloned()
+        .chain(arguments)
+}
+
+/// Argument Ids of a custom block definition or call.
+fn block_argumentids(block: &Block) -> Option<&Vec<Uid>> {
+    let Block::Normal(block) = block else {
+        return None;
+    };
+    match &block.mutation.as_ref()?.mutation_enum {
+        BlockMutationEnum::ProceduresPrototype { argumentids, .. }
+        | BlockMutationEnum::ProceduresCall { argumentids, .. } => Some(argumentids),
+        _ => None,
+    }
+}
+
+/// Rename Ids in the target according to `map`.
+/// Renames the keys of blocks, variables, lists, broadcasts and comments,
+/// and every reference to them inside blocks and comments.
+/// Argument Ids of custom blocks are renamed too, along with the inputs named after them.
+/// Ids that are not in `map` are left as is.
+pub(crate) fn remap_target(target: &mut Target, map: &HashMap<Uid, Uid>) {
+    if map.is_empty() {
+        return;
+    }
+    let remap = |id: &mut Uid| {
+        if let Some(new_id) = map.get(id) {
+            *id = new_id.clone();
+        }
+    };
+
+    remap_keys(&mut target.variables, map);
+    remap_keys(&mut target.lists, map);
+    remap_keys(&mut target.broadcasts, map);
+    remap_keys(&mut target.comments, map);
+    remap_keys(&mut target.blocks, map);
+
+    for comment in target.comments.0.values_mut() {
+        if let Some(block_id) = &mut comment.block_id {
+            remap(block_id);
+        }
+    }
+    for block in target.blocks.0.values_mut() {
+        let block = match block {
+            Block::Normal(block) => block,
+            Block::VarList(b) => {
+                remap(&mut b.id);
+                continue;
+            }
+        };
+        for id in [&mut block.next, &mut block.parent, &mut block.comment]
+            .into_iter()
+            .flatten()
+        {
+            remap(id);
+        }
+        for field in block.fields.0.values_mut() {
+            if let BlockField::WithId { id: Some(id), .. } = field {
+                remap(id);
+            }
+        }
+        for input in block.inputs.0.values_mut() {
+            for value in input.inputs.iter_mut().flatten() {
+                match value {
+                    UidOrValue::Uid(id) => remap(id),
+                    UidOrValue::Value(v) => {
+                        if let Some((_, _, id)) = v.data_reference_mut() {
+                            remap(id);
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(mutation) = &mut block.mutation {
+            if let BlockMutationEnum::ProceduresPrototype { argumentids, .. }
+            | BlockMutationEnum::ProceduresCall { argumentids, .. } = &mut mutation.mutation_enum
+            {
+                // Inputs of custom blocks are named after the argument Ids
+                let inputs = argumentids.clone();
+                argumentids.iter_mut().for_each(remap);
+                if inputs.iter().any(|id| map.contains_key(id)) {
+                    block.inputs.0 = std::mem::take(&mut block.inputs.0)
+                        .into_iter()
+                        .map(|(k, v)| match map.get(&k) {
+                            Some(new_id) if inputs.contains(&k) => (new_id.clone(), v),
+                            _ => (k, v),
+                        })
+                        .collect();
+                }
+            }
+        }
+    }
+}
+fn remap_keys<V>(map: &mut StringHashMap<V>, ids: &HashMap<Uid, Uid>) {
+    if !map.0.keys().any(|k| ids.contains_key(k)) {
+        return;
+    }
+    map.0 = std::mem::take(&mut map.0)
+        .into_iter()
+        .map(|(k, v)| (ids.get(&k).cloned().unwrap_or(k), v))
+        .collect();
+}
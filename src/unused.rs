@@ -0,0 +1,613 @@
+//! Module to find what a project does not use
+//!
+//! Projects pile up scripts left lying in the code area, custom blocks nobody calls anymore,
+//! and costumes and sounds that were imported once and forgotten.
+//! [`Project::unused`] lists them, each with the target and the Id it's about.
+//!
+//! Switching costumes, playing sounds or broadcasting by a reporter can use anything by name,
+//! so nothing is reported as unused of what such a block could use.
+//!
+//! [`Project::prune`] removes blocks that are not part of any script,
+//! and with [`PruneOptions`] what nothing refers to.
+
+use crate::archive::AssetKind;
+use crate::block::{Block, BlockField, BlockMutationEnum, DataKind, UidOrValue};
+use crate::diff::{asset_name, data, data_name};
+use crate::monitor::Parameter;
+use crate::opcode::Shape;
+use crate::prelude::*;
+use crate::project::Project;
+use crate::script::{BlockRef, InputRef};
+use crate::target::Target;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Something a project does not use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unused {
+    /// Name of the target that has it.
+    pub target: Name,
+
+    /// See [`UnusedKind`]
+    pub kind: UnusedKind,
+}
+
+/// What is not used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnusedKind {
+    /// A script that does not start with a hat block, so never runs.
+    FloatingScript {
+        /// Id of the top block
+        id: Uid,
+    },
+
+    /// A custom block that is never called, other than by itself.
+    UncalledProcedure {
+        /// Id of the `procedures_definition` block
+        id: Uid,
+        /// The proccode of the custom block
+        proccode: String,
+    },
+
+    /// A variable or list that no block reads and no monitor shows.
+    UnreadData {
+        /// Variable or list
+        kind: DataKind,
+        /// Its Id
+        id: Uid,
+        /// Its name
+        name: Name,
+    },
+
+    /// A broadcast that no block sends.
+    UnsentBroadcast {
+        /// Its Id
+        id: Uid,
+        /// Its name
+        name: Name,
+    },
+
+    /// A broadcast that no hat block receives.
+    UnreceivedBroadcast {
+        /// Its Id
+        id: Uid,
+        /// Its name
+        name: Name,
+    },
+
+    /// A costume that is not worn and never switched to, or a sound that is never played.
+    UnusedAsset {
+        /// Costume or sound
+        kind: AssetKind,
+        /// Its name
+        name: Name,
+        /// Its asset Id, the MD5 of the file
+        asset_id: Uid,
+    },
+}
+
+/// Blocks that change a variable or list without reading it.
+const WRITES: [&str; 11] = [
+    "data_setvariableto",
+    "data_changevariableby",
+    "data_showvariable",
+    "data_hidevariable",
+    "data_addtolist",
+    "data_deleteoflist",
+    "data_deletealloflist",
+    "data_insertatlist",
+    "data_replaceitemoflist",
+    "data_showlist",
+    "data_hidelist",
+];
+
+/// Blocks that switch costumes or play sounds, with their input and the menu that goes in it.
+const SWITCHES: [(&str, &str, &str); 5] = [
+    ("looks_switchcostumeto", "COSTUME", "looks_costume"),
+    ("looks_switchbackdropto", "BACKDROP", "looks_backdrops"),
+    (
+        "looks_switchbackdroptoandwait",
+        "BACKDROP",
+        "looks_backdrops",
+    ),
+    ("sound_play", "SOUND_MENU", "sound_sounds_menu"),
+    ("sound_playuntildone", "SOUND_MENU", "sound_sounds_menu"),
+];
+
+impl Project {
+    /// Everything the project does not use, target by target.
+    pub fn unused(&self) -> Vec<Unused> {
+        let mut uses = Uses::default();
+        for target in &self.targets {
+            uses.add(target.target());
+        }
+        for monitor in self.monitors.iter().filter(|m| m.visible) {
+            match &monitor.params {
+                Parameter::Variable(name) => uses.shown.insert((DataKind::Variable, name.clone())),
+                Parameter::List(name) => uses.shown.insert((DataKind::List, name.clone())),
+                _ => false,
+            };
+        }
+
+        let mut unused = vec![];
+        for target in &self.targets {
+            let stage = target.is_stage();
+            let target = target.target();
+            let mut push = |kind| {
+                unused.push(Unused {
+                    target: target.name.clone(),
+                    kind,
+                })
+            };
+
+            for script in target.scripts() {
+                let shape = match script.top().block() {
+                    Block::Normal(b) if b.shadow => continue,
+                    Block::Normal(b) => b.known_opcode().shape(),
+                    Block::VarList(_) => Some(Shape::Reporter),
+                };
+                // Hats of extensions this crate does not know may be anything
+                if shape.is_some_and(|shape| shape != Shape::Hat) {
+                    let id = script.id().clone();
+                    push(UnusedKind::FloatingScript { id });
+                }
+            }
+
+            let calls = calls(target);
+            for (id, block) in &target.blocks.0 {
+                let Block::Normal(block) = block else {
+                    continue;
+                };
+                let Some(BlockMutationEnum::ProceduresPrototype { proccode, .. }) =
+                    block.mutation.as_ref().map(|m| &m.mutation_enum)
+                else {
+                    continue;
+                };
+                if block.opcode == "procedures_prototype" && !calls.contains(proccode) {
+                    push(UnusedKind::UncalledProcedure {
+                        id: block.parent.clone().unwrap_or_else(|| id.clone()),
+                        proccode: proccode.clone(),
+                    });
+                }
+            }
+
+            for kind in [DataKind::Variable, DataKind::List] {
+                for (id, name) in data(target, kind) {
+                    let read = uses.read.contains(&(kind, id.clone()))
+                        || uses.shown.contains(&(kind, name.clone()))
+                        || (kind == DataKind::Variable && uses.properties.contains(&name));
+                    if !read {
+                        push(UnusedKind::UnreadData { kind, id, name });
+                    }
+                }
+            }
+
+            for (id, broadcast) in &target.broadcasts.0 {
+                let (id, name) = (id.clone(), broadcast.name.clone());
+                if !uses.dynamic.broadcasts && !uses.sent.contains(&id) {
+                    push(UnusedKind::UnsentBroadcast {
+                        id: id.clone(),
+                        name: name.clone(),
+                    });
+                }
+                if !uses.received.contains(&id) {
+                    push(UnusedKind::UnreceivedBroadcast { id, name });
+                }
+            }
+
+            let (costumes, dynamic) = match stage {
+                true => (&uses.backdrops, uses.dynamic.backdrops),
+                false => (
+                    &local(target, "looks_costume", "COSTUME"),
+                    dynamic_switch(target, "looks_switchcostumeto"),
+                ),
+            };
+            for (i, costume) in target.costumes.iter().enumerate() {
+                let worn = i as Int == target.current_costume;
+                if !worn && !dynamic && !costumes.contains(&costume.asset.name) {
+                    push(UnusedKind::UnusedAsset {
+                        kind: AssetKind::Costume,
+                        name: costume.asset.name.clone(),
+                        asset_id: costume.asset.asset_id.clone(),
+                    });
+                }
+            }
+
+            let sounds = local(target, "sound_sounds_menu", "SOUND_MENU");
+            let dynamic = dynamic_switch(target, "sound_play")
+                || dynamic_switch(target, "sound_playuntildone");
+            for sound in &target.sounds {
+                if !dynamic && !sounds.contains(&sound.asset.name) {
+                    push(UnusedKind::UnusedAsset {
+                        kind: AssetKind::Sound,
+                        name: sound.asset.name.clone(),
+                        asset_id: sound.asset.asset_id.clone(),
+                    });
+                }
+            }
+        }
+        unused
+    }
+}
+
+/// What the blocks of every target use.
+#[derive(Default)]
+struct Uses {
+    /// Variables and lists read by Id
+    read: HashSet<(DataKind, Uid)>,
+    /// Variables and lists shown by a monitor
+    shown: HashSet<(DataKind, Name)>,
+    /// Variables of other sprites read with the `of` sensing block
+    properties: HashSet<Name>,
+    sent: HashSet<Uid>,
+    received: HashSet<Uid>,
+    /// Backdrops switched to or waited for by name
+    backdrops: HashSet<Name>,
+    dynamic: Dynamic,
+}
+
+/// Whether a block could use anything by a name from a reporter.
+#[derive(Default)]
+struct Dynamic {
+    broadcasts: bool,
+    backdrops: bool,
+}
+
+impl Uses {
+    fn add(&mut self, target: &Target) {
+        for (id, block) in &target.blocks.0 {
+            let block = match block {
+                Block::Normal(block) => block,
+                Block::VarList(b) => {
+                    self.read.insert((b.kind.data_kind(), b.id.clone()));
+                    continue;
+                }
+            };
+            let opcode = block.opcode.as_str();
+            for (field_name, field) in &block.fields.0 {
+                let value = field.value().to_scratch_string();
+                match (DataKind::from_field_name(field_name), field) {
+                    (Some(DataKind::Broadcast), BlockField::WithId { id: Some(id), .. }) => {
+                        match opcode {
+                            "event_whenbroadcastreceived" => self.received.insert(id.clone()),
+                            _ => self.sent.insert(id.clone()),
+                        };
+                    }
+                    (Some(kind), BlockField::WithId { id: Some(id), .. }) => {
+                        if !WRITES.contains(&opcode) {
+                            self.read.insert((kind, id.clone()));
+                        }
+                    }
+                    _ => match (opcode, field_name.as_str()) {
+                        ("looks_backdrops" | "event_whenbackdropswitchesto", "BACKDROP") => {
+                            // Next, previous and random backdrop
+                            if opcode == "looks_backdrops" && !is_backdrop_name(&value) {
+                                self.dynamic.backdrops = true;
+                            }
+                            self.backdrops.insert(value);
+                        }
+                        ("sensing_of", "PROPERTY") => {
+                            self.properties.insert(value);
+                        }
+                        _ => {}
+                    },
+                }
+            }
+            for input in block.inputs.0.values() {
+                for value in input.inputs.iter().flatten() {
+                    let UidOrValue::Value(value) = value else {
+                        continue;
+                    };
+                    if let Some((kind, _, id)) = value.data_reference() {
+                        match kind {
+                            DataKind::Broadcast => self.sent.insert(id.clone()),
+                            _ => self.read.insert((kind, id.clone())),
+                        };
+                    }
+                }
+            }
+            let this = BlockRef::new(&target.blocks, id).expect("the block is in the map");
+            match opcode {
+                "event_broadcast" | "event_broadcastandwait" => {
+                    if let Ok(Some(InputRef::Block(b))) = this.input("BROADCAST_INPUT") {
+                        if b.opcode() != Some("event_broadcast_menu") {
+                            self.dynamic.broadcasts = true;
+                        }
+                    }
+                }
+                "looks_nextbackdrop" => self.dynamic.backdrops = true,
+                _ => {}
+            }
+        }
+        self.dynamic.backdrops |= dynamic_switch(target, "looks_switchbackdropto")
+            || dynamic_switch(target, "looks_switchbackdroptoandwait");
+    }
+}
+
+fn is_backdrop_name(value: &str) -> bool {
+    !matches!(
+        value,
+        "next backdrop" | "previous backdrop" | "random backdrop"
+    )
+}
+
+/// Values of the field of the menu blocks of the target, such as the costumes it switches to.
+fn local(target: &Target, menu: &str, field: &str) -> HashSet<Name> {
+    (target.blocks.0.values())
+        .filter_map(|block| match block {
+            Block::Normal(b) if b.opcode == menu => b.fields.0.get(field),
+            _ => None,
+        })
+        .map(|field| field.value().to_scratch_string())
+        .collect()
+}
+
+/// Whether a block with the opcode in the target uses what a reporter gives instead of a menu,
+/// or a costume switch goes to the next one.
+fn dynamic_switch(target: &Target, opcode: &str) -> bool {
+    if opcode == "looks_switchcostumeto" && has_opcode(target, "looks_nextcostume") {
+        return true;
+    }
+    let Some(&(_, input, menu)) = SWITCHES.iter().find(|(o, _, _)| *o == opcode) else {
+        return false;
+    };
+    target.blocks.0.keys().any(|id| {
+        let block = BlockRef::new(&target.blocks, id).expect("the block is in the map");
+        block.opcode() == Some(opcode)
+            && !matches!(block.input(input), Ok(Some(InputRef::Block(b))) if b.opcode() == Some(menu))
+    })
+}
+
+fn has_opcode(target: &Target, opcode: &str) -> bool {
+    (target.blocks.0.values()).any(|b| matches!(b, Block::Normal(b) if b.opcode == opcode))
+}
+
+/// Proccodes of the custom blocks called from outside their own definition.
+fn calls(target: &Target) -> HashSet<String> {
+    let mut calls = HashSet::new();
+    for script in target.scripts() {
+        let defines =
+            (script.top().input("custom_block").ok().flatten()).and_then(
+                |prototype| match prototype {
+                    InputRef::Block(b) => proccode(b.block()),
+                    InputRef::Value(_) => None,
+                },
+            );
+        let Ok(blocks) = script.walk() else {
+            continue;
+        };
+        for block in blocks {
+            if block.opcode() != Some("procedures_call") {
+                continue;
+            }
+            if let Some(called) = proccode(block.block()) {
+                if Some(called) != defines {
+                    calls.insert(called.clone());
+                }
+            }
+        }
+    }
+    calls
+}
+
+fn proccode(block: &Block) -> Option<&String> {
+    let Block::Normal(block) = block else {
+        return None;
+    };
+    match &block.mutation.as_ref()?.mutation_enum {
+        BlockMutationEnum::ProceduresPrototype { proccode, .. }
+        | BlockMutationEnum::ProceduresCall { proccode, .. } => Some(proccode),
+        _ => None,
+    }
+}
+
+// Prune impl ==================================================================
+
+/// What [`Project::prune`] removes besides orphaned blocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneOptions {
+    /// Remove variables, lists and broadcasts that no block and no monitor refers to.
+    pub data: bool,
+
+    /// Remove comments attached to blocks that do not exist, such as orphaned blocks.
+    pub comments: bool,
+
+    /// Remove costumes and sounds nothing refers to, see [`UnusedKind::UnusedAsset`].
+    /// A target keeps its first costume when none of them is used, Scratch needs one.
+    pub assets: bool,
+}
+
+impl PruneOptions {
+    /// Remove everything that can be removed.
+    pub fn all() -> PruneOptions {
+        PruneOptions {
+            data: true,
+            comments: true,
+            assets: true,
+        }
+    }
+}
+
+impl Project {
+    /// Remove orphaned blocks, the blocks whose parents never lead to a top level block
+    /// and that no script links to, then what the options say.
+    pub fn prune(&mut self, options: PruneOptions) {
+        for target in &mut self.targets {
+            prune_blocks(target.target_mut());
+        }
+
+        if options.data {
+            let mut referred = HashSet::new();
+            let mut properties = HashSet::new();
+            for target in &self.targets {
+                for block in target.target().blocks.0.values() {
+                    for (kind, _, id) in block.data_references() {
+                        referred.insert((kind, id.clone()));
+                    }
+                    if let Block::Normal(b) = block {
+                        if b.opcode == "sensing_of" {
+                            properties.extend(
+                                b.fields
+                                    .0
+                                    .get("PROPERTY")
+                                    .map(|f| f.value().to_scratch_string()),
+                            );
+                        }
+                    }
+                }
+            }
+            let monitored: HashSet<(Option<&Name>, DataKind, &Name)> = (self.monitors.iter())
+                .filter_map(|m| match &m.params {
+                    Parameter::Variable(name) => {
+                        Some((m.sprite_name.as_ref(), DataKind::Variable, name))
+                    }
+                    Parameter::List(name) => Some((m.sprite_name.as_ref(), DataKind::List, name)),
+                    _ => None,
+                })
+                .collect();
+            for target in &mut self.targets {
+                let sprite_name = (!target.is_stage()).then(|| target.target().name.clone());
+                let target = target.target_mut();
+                let kept = |kind, id: &Uid, name: &Name| {
+                    referred.contains(&(kind, id.clone()))
+                        || monitored.contains(&(sprite_name.as_ref(), kind, name))
+                };
+                (target.variables.0).retain(|id, v| {
+                    kept(DataKind::Variable, id, &v.name) || properties.contains(&v.name)
+                });
+                (target.lists.0).retain(|id, l| kept(DataKind::List, id, &l.name));
+                (target.broadcasts.0).retain(|id, b| kept(DataKind::Broadcast, id, &b.name));
+            }
+        }
+
+        if options.comments {
+            for target in &mut self.targets {
+                let target = target.target_mut();
+                let blocks = &target.blocks;
+                (target.comments.0).retain(|_, c| match &c.block_id {
+                    Some(id) => blocks.0.contains_key(id),
+                    None => true,
+                });
+            }
+        }
+
+        if options.assets {
+            let mut unused: HashMap<Name, Vec<(AssetKind, Name)>> = HashMap::new();
+            for u in self.unused() {
+                if let UnusedKind::UnusedAsset { kind, name, .. } = u.kind {
+                    unused.entry(u.target).or_default().push((kind, name));
+                }
+            }
+            for target in &mut self.targets {
+                let target = target.target_mut();
+                let Some(unused) = unused.get(&target.name) else {
+                    continue;
+                };
+                let is_unused =
+                    |kind, name: &Name| unused.iter().any(|(k, n)| *k == kind && n == name);
+                let wearing = usize::try_from(target.current_costume)
+                    .ok()
+                    .and_then(|i| target.costumes.get(i))
+                    .map(|c| c.asset.name.clone());
+                let mut costumes = target.costumes.clone();
+                costumes.retain(|c| !is_unused(AssetKind::Costume, &c.asset.name));
+                if costumes.is_empty() {
+                    costumes = target.costumes.iter().take(1).cloned().collect();
+                }
+                target.costumes = costumes;
+                target.current_costume = (target.costumes.iter())
+                    .position(|c| Some(&c.asset.name) == wearing.as_ref())
+                    .unwrap_or(0) as Int;
+                (target.sounds).retain(|s| !is_unused(AssetKind::Sound, &s.asset.name));
+            }
+        }
+    }
+}
+
+/// Remove the blocks that are not part of a script.
+fn prune_blocks(target: &mut Target) {
+    let blocks = &target.blocks.0;
+    let is_top = |block: &Block| match block {
+        Block::Normal(b) => b.top_level,
+        Block::VarList(_) => true,
+    };
+    // Blocks whose parents lead to a top level block, and what they link to
+    let mut stack: Vec<&Uid> = (blocks.keys())
+        .filter(|id| {
+            let mut id = *id;
+            for _ in 0..blocks.len() {
+                match blocks.get(id) {
+                    Some(block) if is_top(block) => return true,
+                    Some(Block::Normal(b)) => match &b.parent {
+                        Some(parent) => id = parent,
+                        None => return false,
+                    },
+                    _ => return false,
+                }
+            }
+            false
+        })
+        .collect();
+    let mut kept = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if !kept.insert(id.clone()) {
+            continue;
+        }
+        let Some(Block::Normal(block)) = blocks.get(id) else {
+            continue;
+        };
+        stack.extend(&block.next);
+        for input in block.inputs.0.values() {
+            for value in input.inputs.iter().flatten() {
+                if let UidOrValue::Uid(id) = value {
+                    stack.push(id);
+                }
+            }
+        }
+    }
+    target.blocks.0.retain(|id, _| kept.contains(id));
+}
+
+// Display impl ================================================================
+
+impl fmt::Display for Unused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.target, self.kind)
+    }
+}
+
+impl fmt::Display for UnusedKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use UnusedKind::*;
+
+        match self {
+            FloatingScript { id } => write!(f, "script `{id}` does not start with a hat block"),
+            UncalledProcedure { id, proccode } => {
+                write!(f, "custom block `{proccode}` (`{id}`) is never called")
+            }
+            UnreadData { kind, id, name } => {
+                write!(f, "{} `{name}` (`{id}`) is never read", data_name(*kind))
+            }
+            UnsentBroadcast { id, name } => write!(f, "broadcast `{name}` (`{id}`) is never sent"),
+            UnreceivedBroadcast { id, name } => {
+                write!(f, "broadcast `{name}` (`{id}`) is never received")
+            }
+            UnusedAsset {
+                kind,
+                name,
+                asset_id,
+            } => {
+                let never = match kind {
+                    AssetKind::Costume => "switched to",
+                    AssetKind::Sound => "played",
+                };
+                write!(
+                    f,
+                    "{} `{name}` (`{asset_id}`) is never {never}",
+                    asset_name(*kind)
+                )
+            }
+        }
+    }
+}
@@ -4,6 +4,11 @@ use serde::{
 };
 use serde_json::Value as Json;
 
+/// Escape a JSON pointer reference token.
+pub fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
 pub fn is_false(v: &bool) -> bool {
     !v
 }
@@ -55,3 +60,19 @@ where
 {
     ser.serialize_str(&serde_json::to_string(s).unwrap())
 }
+
+/// A name that `is_used` says is not used yet, the way Scratch picks one.
+/// Trailing digits are stripped and the lowest number from 2 that makes the name unused is appended.
+pub fn unused_name<F>(name: &str, is_used: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    if !is_used(name) {
+        return name.to_owned();
+    }
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    (2..)
+        .map(|i| format!("{base}{i}"))
+        .find(|n| !is_used(n))
+        .unwrap()
+}
@@ -0,0 +1,449 @@
+//! Module to check a project for structural problems
+//!
+//! A project can deserialize fine but still fail to load in the Scratch editor,
+//! or load with broken scripts. [`Project::validate`] finds those problems.
+
+use crate::block::{Block, DataKind, UidOrValue};
+use crate::extension::Extension;
+use crate::monitor::Parameter;
+use crate::prelude::*;
+use crate::project::Project;
+use crate::target::{SpriteOrStage, Stage, Target, TextToSpeechLanguage};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A problem found in a project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// JSON pointer to the offending value in project.json, such as `/targets/1/blocks/<id>/next`.
+    pub path: String,
+
+    /// See [`DiagnosticKind`]
+    pub kind: DiagnosticKind,
+}
+
+/// What is wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    /// A block links to a block Id that is not in the blocks map.
+    DanglingBlock {
+        /// The Id that points at nothing
+        id: Uid,
+    },
+
+    /// The block's next block says it has a different parent.
+    NextParentMismatch {
+        /// Id of the next block
+        next: Uid,
+        /// Parent of the next block
+        next_parent: Option<Uid>,
+    },
+
+    /// The block's parent neither has it as next nor in any input.
+    /// Not reported for shadow blocks.
+    ParentNotLinked {
+        /// Id of the parent block
+        parent: Uid,
+    },
+
+    /// The block is top level but has a parent.
+    TopLevelWithParent,
+
+    /// The block is not top level but has no parent.
+    NotTopLevelWithoutParent,
+
+    /// The block is top level but has no x or y position.
+    TopLevelMissingPosition,
+
+    /// A field or input refers to a variable, list or broadcast that is not declared in scope,
+    /// neither by the target nor by the stage.
+    UndeclaredReference {
+        /// Variable, list or broadcast
+        kind: DataKind,
+        /// Name in the reference
+        name: Name,
+        /// Id in the reference
+        id: Uid,
+    },
+
+    /// `currentCostume` is not an index into the costumes.
+    CostumeOutOfRange {
+        /// The costume number
+        current_costume: Int,
+        /// How many costumes there are
+        costume_count: usize,
+    },
+
+    /// A project must have exactly one stage.
+    StageCount {
+        /// How many stages there are
+        count: usize,
+    },
+
+    /// Two targets have the same name.
+    DuplicateTargetName {
+        /// The name
+        name: Name,
+    },
+
+    /// A monitor shows a variable or list that does not exist.
+    MonitorMissingData {
+        /// Variable or list
+        kind: DataKind,
+        /// Name in the monitor parameters
+        name: Name,
+    },
+
+    /// A monitor shows a variable or list by a name that exists, but with an Id that does not.
+    MonitorIdMismatch {
+        /// Variable or list
+        kind: DataKind,
+        /// Name in the monitor parameters
+        name: Name,
+        /// Id of the monitor
+        id: Uid,
+    },
+
+    /// A monitor belongs to a sprite that does not exist.
+    MonitorMissingSprite {
+        /// Name of the sprite
+        sprite_name: Name,
+    },
+
+    /// Blocks use an extension the project does not load.
+    /// See [`Project::add_missing_extensions`]
+    MissingExtension {
+        /// The extension
+        extension: Extension,
+    },
+
+    /// The Text to Speech language of the stage or of a `text2speech_menu_languages`
+    /// menu is not one the extension knows.
+    UnknownTextToSpeechLanguage {
+        /// The locale code
+        language: String,
+    },
+}
+
+impl Project {
+    /// Check the project for problems that make it fail to load or corrupt its scripts.
+    /// An empty list means no problem was found.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let stage = self
+            .targets
+            .iter()
+            .find(|t| t.is_stage())
+            .map(|t| t.target());
+
+        let stage_count = self.targets.iter().filter(|t| t.is_stage()).count();
+        if stage_count != 1 {
+            diagnostics.push(Diagnostic::new(
+                "/targets".to_owned(),
+                DiagnosticKind::StageCount { count: stage_count },
+            ));
+        }
+
+        let mut names = HashMap::new();
+        for (i, target) in self.targets.iter().enumerate() {
+            let path = format!("/targets/{i}");
+            if let SpriteOrStage::Stage(Stage {
+                text_to_speech_language: Some(TextToSpeechLanguage::Other(language)),
+                ..
+            }) = target
+            {
+                diagnostics.push(Diagnostic::new(
+                    format!("{path}/textToSpeechLanguage"),
+                    DiagnosticKind::UnknownTextToSpeechLanguage {
+                        language: language.clone(),
+                    },
+                ));
+            }
+            let target = target.target();
+            if names.insert(&target.name, i).is_some() {
+                diagnostics.push(Diagnostic::new(
+                    format!("{path}/name"),
+                    DiagnosticKind::DuplicateTargetName {
+                        name: target.name.clone(),
+                    },
+                ));
+            }
+            validate_target(target, stage, &path, &mut diagnostics);
+        }
+
+        for (i, monitor) in self.monitors.iter().enumerate() {
+            let (kind, name) = match &monitor.params {
+                Parameter::Variable(name) => (DataKind::Variable, name),
+                Parameter::List(name) => (DataKind::List, name),
+                _ => continue,
+            };
+            let path = format!("/monitors/{i}");
+            let target = match &monitor.sprite_name {
+                Some(sprite_name) => {
+                    match self
+                        .targets
+                        .iter()
+                        .find(|t| &t.target().name == sprite_name)
+                    {
+                        Some(t) => Some(t.target()),
+                        None => {
+                            diagnostics.push(Diagnostic::new(
+                                format!("{path}/spriteName"),
+                                DiagnosticKind::MonitorMissingSprite {
+                                    sprite_name: sprite_name.clone(),
+                                },
+                            ));
+                            continue;
+                        }
+                    }
+                }
+                None => stage,
+            };
+            let (has_id, has_name) = target.map_or((false, false), |t| match kind {
+                DataKind::Variable => (
+                    t.variables.0.contains_key(&monitor.id),
+                    t.variables.0.values().any(|v| &v.name == name),
+                ),
+                _ => (
+                    t.lists.0.contains_key(&monitor.id),
+                    t.lists.0.values().any(|l| &l.name == name),
+                ),
+            });
+            if has_id {
+                continue;
+            }
+            if has_name {
+                diagnostics.push(Diagnostic::new(
+                    format!("{path}/id"),
+                    DiagnosticKind::MonitorIdMismatch {
+                        kind,
+                        name: name.clone(),
+                        id: monitor.id.clone(),
+                    },
+                ));
+            } else {
+                diagnostics.push(Diagnostic::new(
+                    format!("{path}/params"),
+                    DiagnosticKind::MonitorMissingData {
+                        kind,
+                        name: name.clone(),
+                    },
+                ));
+            }
+        }
+
+        for extension in self.missing_extensions() {
+            diagnostics.push(Diagnostic::new(
+                "/extensions".to_owned(),
+                DiagnosticKind::MissingExtension { extension },
+            ));
+        }
+
+        diagnostics
+    }
+}
+
+fn validate_target(
+    target: &Target,
+    stage: Option<&Target>,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let costume_count = target.costumes.len();
+    if target.current_costume < 0 || target.current_costume as usize >= costume_count {
+        diagnostics.push(Diagnostic::new(
+            format!("{path}/currentCostume"),
+            DiagnosticKind::CostumeOutOfRange {
+                current_costume: target.current_costume,
+                costume_count,
+            },
+        ));
+    }
+
+    let declares = |kind: DataKind, id: &Uid| {
+        [Some(target), stage]
+            .into_iter()
+            .flatten()
+            .any(|t| match kind {
+                DataKind::Variable => t.variables.0.contains_key(id),
+                DataKind::List => t.lists.0.contains_key(id),
+                DataKind::Broadcast => t.broadcasts.0.contains_key(id),
+            })
+    };
+
+    for (id, block) in &target.blocks.0 {
+        let path = format!("{path}/blocks/{}", utils::escape_pointer(id));
+        let mut push = |p: &str, kind| {
+            diagnostics.push(Diagnostic::new(format!("{path}{p}"), kind));
+        };
+
+        for (p, kind, name, data_id) in block.data_reference_paths() {
+            if !declares(kind, data_id) {
+                push(
+                    &p,
+                    DiagnosticKind::UndeclaredReference {
+                        kind,
+                        name,
+                        id: data_id.clone(),
+                    },
+                );
+            }
+        }
+
+        let Block::Normal(block) = block else {
+            continue;
+        };
+
+        if block.opcode == "text2speech_menu_languages" {
+            if let Some(field) = block.fields.0.get("languages") {
+                let language = field.value().to_scratch_string();
+                if let TextToSpeechLanguage::Other(language) =
+                    TextToSpeechLanguage::from(language.as_str())
+                {
+                    push(
+                        "/fields/languages",
+                        DiagnosticKind::UnknownTextToSpeechLanguage { language },
+                    );
+                }
+            }
+        }
+
+        match (block.top_level, &block.parent) {
+            (true, Some(_)) => push("/topLevel", DiagnosticKind::TopLevelWithParent),
+            (false, None) => push("/topLevel", DiagnosticKind::NotTopLevelWithoutParent),
+            _ => {}
+        }
+        if block.top_level {
+            if block.x.is_none() {
+                push("/x", DiagnosticKind::TopLevelMissingPosition);
+            } else if block.y.is_none() {
+                push("/y", DiagnosticKind::TopLevelMissingPosition);
+            }
+        }
+
+        if let Some(next) = &block.next {
+            match target.blocks.0.get(next) {
+                None => push("/next", DiagnosticKind::DanglingBlock { id: next.clone() }),
+                Some(Block::Normal(next_block)) if next_block.parent.as_ref() != Some(id) => push(
+                    "/next",
+                    DiagnosticKind::NextParentMismatch {
+                        next: next.clone(),
+                        next_parent: next_block.parent.clone(),
+                    },
+                ),
+                Some(_) => {}
+            }
+        }
+
+        if let Some(parent) = &block.parent {
+            match target.blocks.0.get(parent) {
+                None => push(
+                    "/parent",
+                    DiagnosticKind::DanglingBlock { id: parent.clone() },
+                ),
+                Some(Block::Normal(parent_block)) => {
+                    let linked = parent_block.next.as_ref() == Some(id)
+                        || parent_block.inputs.0.values().any(|input| {
+                            input
+                                .inputs
+                                .iter()
+                                .flatten()
+                                .any(|v| matches!(v, UidOrValue::Uid(i) if i == id))
+                        });
+                    // Projects converted from Scratch 2.0 are full of orphaned shadow blocks
+                    // left behind by custom block prototypes, Scratch ignores them.
+                    if !linked && !block.shadow {
+                        push(
+                            "/parent",
+                            DiagnosticKind::ParentNotLinked {
+                                parent: parent.clone(),
+                            },
+                        );
+                    }
+                }
+                Some(Block::VarList(_)) => push(
+                    "/parent",
+                    DiagnosticKind::ParentNotLinked {
+                        parent: parent.clone(),
+                    },
+                ),
+            }
+        }
+
+        for (input_name, input) in &block.inputs.0 {
+            for value in input.inputs.iter().flatten() {
+                if let UidOrValue::Uid(input_id) = value {
+                    if !target.blocks.0.contains_key(input_id) {
+                        push(
+                            &format!("/inputs/{}", utils::escape_pointer(input_name)),
+                            DiagnosticKind::DanglingBlock {
+                                id: input_id.clone(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Diagnostic {
+    fn new(path: String, kind: DiagnosticKind) -> Diagnostic {
+        Diagnostic { path, kind }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use DiagnosticKind::*;
+
+        match self {
+            DanglingBlock { id } => write!(f, "refers to block `{id}` which does not exist"),
+            NextParentMismatch { next, next_parent } => match next_parent {
+                Some(p) => write!(f, "next block `{next}` has `{p}` as its parent"),
+                None => write!(f, "next block `{next}` has no parent"),
+            },
+            ParentNotLinked { parent } => write!(
+                f,
+                "parent block `{parent}` does not have this block as next or as an input"
+            ),
+            TopLevelWithParent => write!(f, "top level block has a parent"),
+            NotTopLevelWithoutParent => write!(f, "block is not top level but has no parent"),
+            TopLevelMissingPosition => write!(f, "top level block is missing its x or y"),
+            UndeclaredReference { kind, name, id } => {
+                write!(f, "{kind:?} `{name}` (`{id}`) is not declared in scope")
+            }
+            CostumeOutOfRange {
+                current_costume,
+                costume_count,
+            } => write!(
+                f,
+                "costume number {current_costume} is out of range for {costume_count} costumes"
+            ),
+            StageCount { count } => write!(f, "project has {count} stages instead of 1"),
+            DuplicateTargetName { name } => write!(f, "another target is already named `{name}`"),
+            MonitorMissingData { kind, name } => write!(f, "{kind:?} `{name}` does not exist"),
+            MonitorIdMismatch { kind, name, id } => {
+                write!(f, "{kind:?} `{name}` exists but not with Id `{id}`")
+            }
+            MonitorMissingSprite { sprite_name } => {
+                write!(f, "sprite `{sprite_name}` does not exist")
+            }
+            MissingExtension { extension } => {
+                write!(
+                    f,
+                    "extension `{extension}` is used by blocks but not loaded"
+                )
+            }
+            UnknownTextToSpeechLanguage { language } => {
+                write!(f, "unknown Text to Speech language `{language}`")
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! Module to deal with Scratch value
 
 use crate::prelude::*;
+use std::cmp::Ordering;
 
 pub type Int = i64;
 pub type Float = f64;
@@ -61,6 +62,18 @@ impl From<Int> for Value {
     }
 }
 
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Value::Number(Number::Int(v.into()))
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Text(v.to_owned())
+    }
+}
+
 impl From<Float> for Value {
     fn from(v: Float) -> Self {
         Value::Number(Number::Float(v))
@@ -93,3 +106,397 @@ impl Default for ValueWithBool {
         ValueWithBool::Number(Default::default())
     }
 }
+
+// Cast impl ===================================================================
+//
+// Scratch's `Cast` module and operator blocks, see
+// https://github.com/scratchfoundation/scratch-vm/blob/develop/src/util/cast.js
+
+impl Number {
+    /// The number as a float.
+    pub fn to_float(&self) -> Float {
+        match self {
+            Number::Int(i) => *i as Float,
+            Number::Float(f) => *f,
+        }
+    }
+
+    /// [`Number::Int`] when the float is a whole number, [`Number::Float`] otherwise.
+    pub fn normalize(n: Float) -> Number {
+        // -0 stays a float so it's not lost
+        if n.fract() == 0.0 && n.abs() < 9e15 && !(n == 0.0 && n.is_sign_negative()) {
+            Number::Int(n as Int)
+        } else {
+            Number::Float(n)
+        }
+    }
+
+    /// The number written like JavaScript does, such as `0.1`, `1e+21` or `Infinity`.
+    pub fn to_scratch_string(&self) -> Text {
+        match self {
+            Number::Int(i) => i.to_string(),
+            Number::Float(f) => format_number(*f),
+        }
+    }
+}
+
+/// Borrowed view of a [`Value`] or [`ValueWithBool`] to cast from.
+#[derive(Clone, Copy)]
+enum Cast<'a> {
+    Number(Number),
+    Text(&'a str),
+    Bool(bool),
+}
+
+impl Cast<'_> {
+    /// JavaScript's `Number(value)`, NaN when it's not a number.
+    fn js_number(self) -> Float {
+        match self {
+            Cast::Number(n) => n.to_float(),
+            Cast::Text(t) => js_number(t),
+            Cast::Bool(b) => b as u8 as Float,
+        }
+    }
+
+    fn to_number(self) -> Float {
+        let n = self.js_number();
+        if n.is_nan() {
+            0.0
+        } else {
+            n
+        }
+    }
+
+    fn to_bool(self) -> bool {
+        match self {
+            Cast::Number(n) => {
+                let n = n.to_float();
+                n != 0.0 && !n.is_nan()
+            }
+            Cast::Text(t) => !(t.is_empty() || t == "0" || t.to_lowercase() == "false"),
+            Cast::Bool(b) => b,
+        }
+    }
+
+    fn to_text(self) -> Text {
+        match self {
+            Cast::Number(n) => n.to_scratch_string(),
+            Cast::Text(t) => t.to_owned(),
+            Cast::Bool(b) => b.to_string(),
+        }
+    }
+
+    fn is_whitespace(self) -> bool {
+        matches!(self, Cast::Text(t) if t.trim().is_empty())
+    }
+
+    fn is_int(self) -> bool {
+        match self {
+            Cast::Number(n) => {
+                let n = n.to_float();
+                n.is_nan() || n.fract() == 0.0
+            }
+            // Strings with a dot are decimals, even `1.0`
+            Cast::Text(t) => !t.contains('.'),
+            Cast::Bool(_) => true,
+        }
+    }
+
+    fn compare(self, other: Cast) -> Ordering {
+        let mut n1 = self.js_number();
+        let mut n2 = other.js_number();
+        if n1 == 0.0 && self.is_whitespace() {
+            n1 = Float::NAN;
+        } else if n2 == 0.0 && other.is_whitespace() {
+            n2 = Float::NAN;
+        }
+        if n1.is_nan() || n2.is_nan() {
+            let s1 = self.to_text().to_lowercase();
+            let s2 = other.to_text().to_lowercase();
+            return s1.cmp(&s2);
+        }
+        if n1.is_infinite() && n1 == n2 {
+            return Ordering::Equal;
+        }
+        n1.partial_cmp(&n2).unwrap_or(Ordering::Equal)
+    }
+}
+
+macro_rules! impl_cast {
+    ($($ty:ident)*) => {
+        $(
+            impl $ty {
+                /// The value as a number, like Scratch's `Cast.toNumber`.
+                /// Text that isn't a number is 0, `Infinity` and hexadecimal like `0x1F` are numbers.
+                pub fn to_number(&self) -> Float {
+                    self.cast().to_number()
+                }
+
+                /// The value as a boolean, like Scratch's `Cast.toBoolean`.
+                /// `""`, `"0"`, `"false"` in any case and 0 are false.
+                pub fn to_bool(&self) -> bool {
+                    self.cast().to_bool()
+                }
+
+                /// The value as text, like Scratch's `Cast.toString`.
+                pub fn to_scratch_string(&self) -> Text {
+                    self.cast().to_text()
+                }
+
+                /// True if the value is text that is empty or only whitespace.
+                pub fn is_whitespace(&self) -> bool {
+                    self.cast().is_whitespace()
+                }
+
+                /// True if the value has no decimal part, like Scratch's `Cast.isInt`.
+                /// Text counts as a whole number as long as it has no dot.
+                pub fn is_int(&self) -> bool {
+                    self.cast().is_int()
+                }
+
+                /// Compare like Scratch's `Cast.compare`.
+                /// Numbers and numeric text compare as numbers, anything else as case insensitive text.
+                pub fn scratch_compare(&self, other: &Self) -> Ordering {
+                    self.cast().compare(other.cast())
+                }
+
+                /// Equal like the `=` block, so `"ABC"` equals `"abc"` and `"1.0"` equals `1`.
+                pub fn scratch_eq(&self, other: &Self) -> bool {
+                    self.scratch_compare(other) == Ordering::Equal
+                }
+
+                /// `(a + b)`
+                pub fn scratch_add(&self, other: &Self) -> Number {
+                    Number::normalize(self.to_number() + other.to_number())
+                }
+
+                /// `(a - b)`
+                pub fn scratch_sub(&self, other: &Self) -> Number {
+                    Number::normalize(self.to_number() - other.to_number())
+                }
+
+                /// `(a * b)`
+                pub fn scratch_mul(&self, other: &Self) -> Number {
+                    Number::normalize(self.to_number() * other.to_number())
+                }
+
+                /// `(a / b)`, dividing by 0 gives infinity or NaN.
+                pub fn scratch_div(&self, other: &Self) -> Number {
+                    Number::normalize(self.to_number() / other.to_number())
+                }
+
+                /// `(a mod b)`, which has the sign of `b`.
+                pub fn scratch_mod(&self, other: &Self) -> Number {
+                    let n = self.to_number();
+                    let modulus = other.to_number();
+                    let mut result = n % modulus;
+                    if result / modulus < 0.0 {
+                        result += modulus;
+                    }
+                    Number::normalize(result)
+                }
+
+                /// `(round ())`, halves round up.
+                pub fn scratch_round(&self) -> Number {
+                    Number::normalize(js_round(self.to_number()))
+                }
+
+                /// `([operator] of ())` where the operator is one of `abs`, `floor`, `ceiling`,
+                /// `sqrt`, `sin`, `cos`, `tan`, `asin`, `acos`, `atan`, `ln`, `log`, `e ^` or `10 ^`.
+                /// Unknown operators give 0 like in Scratch.
+                pub fn mathop(&self, operator: &str) -> Number {
+                    Number::normalize(mathop(operator, self.to_number()))
+                }
+
+                /// `(join () ())`
+                pub fn join(&self, other: &Self) -> Text {
+                    self.to_scratch_string() + &other.to_scratch_string()
+                }
+
+                /// `(letter () of ())` on this value with the 1 based index.
+                /// Counts UTF-16 code units like JavaScript,
+                /// half of a surrogate pair becomes U+FFFD.
+                pub fn letter_of(&self, letter: &Self) -> Text {
+                    let index = letter.to_number() - 1.0;
+                    if index < 0.0 {
+                        return Text::new();
+                    }
+                    let text = self.to_scratch_string();
+                    match text.encode_utf16().nth(index as usize) {
+                        Some(unit) => String::from_utf16_lossy(&[unit]),
+                        None => Text::new(),
+                    }
+                }
+
+                /// `(length of ())` in UTF-16 code units like JavaScript.
+                pub fn length(&self) -> Int {
+                    self.to_scratch_string().encode_utf16().count() as Int
+                }
+
+                /// `<() contains ()?>`, case insensitive.
+                pub fn contains(&self, other: &Self) -> bool {
+                    let text = self.to_scratch_string().to_lowercase();
+                    text.contains(&other.to_scratch_string().to_lowercase())
+                }
+            }
+        )*
+    }
+}
+
+impl_cast! { Value ValueWithBool }
+
+impl Value {
+    fn cast(&self) -> Cast<'_> {
+        match self {
+            Value::Number(n) => Cast::Number(*n),
+            Value::Text(t) => Cast::Text(t),
+        }
+    }
+}
+
+impl ValueWithBool {
+    fn cast(&self) -> Cast<'_> {
+        match self {
+            ValueWithBool::Number(n) => Cast::Number(*n),
+            ValueWithBool::Text(t) => Cast::Text(t),
+            ValueWithBool::Bool(b) => Cast::Bool(*b),
+        }
+    }
+}
+
+/// JavaScript's `Number(string)`, NaN when it's not a number.
+pub(crate) fn js_number(s: &str) -> Float {
+    let s = s.trim();
+    if s.is_empty() {
+        return 0.0;
+    }
+    for (prefix, radix) in [
+        ("0x", 16),
+        ("0X", 16),
+        ("0o", 8),
+        ("0O", 8),
+        ("0b", 2),
+        ("0B", 2),
+    ] {
+        if let Some(digits) = s.strip_prefix(prefix) {
+            if digits.is_empty() {
+                return Float::NAN;
+            }
+            return digits
+                .chars()
+                .try_fold(0.0, |n, c| {
+                    Some(n * radix as Float + c.to_digit(radix)? as Float)
+                })
+                .unwrap_or(Float::NAN);
+        }
+    }
+    match s {
+        "Infinity" | "+Infinity" => return Float::INFINITY,
+        "-Infinity" => return Float::NEG_INFINITY,
+        _ => {}
+    }
+
+    // Rust also parses things like `inf` and `NaN`, JavaScript only takes digits
+    let unsigned = s.strip_prefix(['+', '-']).unwrap_or(s);
+    let mantissa = match unsigned.find(['e', 'E']) {
+        Some(i) => {
+            let exponent = &unsigned[i + 1..];
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if exponent.is_empty() || !exponent.chars().all(|c| c.is_ascii_digit()) {
+                return Float::NAN;
+            }
+            &unsigned[..i]
+        }
+        None => unsigned,
+    };
+    let valid = mantissa.chars().any(|c| c.is_ascii_digit())
+        && mantissa.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && mantissa.matches('.').count() <= 1;
+    match valid {
+        true => s.parse().unwrap_or(Float::NAN),
+        false => Float::NAN,
+    }
+}
+
+/// JavaScript's `Number.prototype.toString`.
+fn format_number(n: Float) -> Text {
+    if n.is_nan() {
+        return "NaN".to_owned();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "Infinity" } else { "-Infinity" }.to_owned();
+    }
+    if n == 0.0 {
+        return "0".to_owned();
+    }
+
+    // Shortest digits that round trip, and where the point goes
+    let exponential = format!("{:e}", n.abs());
+    let (mantissa, exponent) = exponential.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let point = exponent.parse::<i32>().unwrap() + 1;
+
+    let text = if k <= point && point <= 21 {
+        digits + &"0".repeat((point - k) as usize)
+    } else if 0 < point && point <= 21 {
+        format!(
+            "{}.{}",
+            &digits[..point as usize],
+            &digits[point as usize..]
+        )
+    } else if -6 < point && point <= 0 {
+        format!("0.{}{digits}", "0".repeat(-point as usize))
+    } else {
+        let exponent = point - 1;
+        let sign = if exponent >= 0 { "+" } else { "-" };
+        match k {
+            1 => format!("{digits}e{sign}{}", exponent.abs()),
+            _ => format!("{}.{}e{sign}{}", &digits[..1], &digits[1..], exponent.abs()),
+        }
+    };
+    match n < 0.0 {
+        true => format!("-{text}"),
+        false => text,
+    }
+}
+
+/// JavaScript's `Math.round`, which rounds halves up.
+pub(crate) fn js_round(n: Float) -> Float {
+    let floor = n.floor();
+    if n - floor >= 0.5 {
+        floor + 1.0
+    } else {
+        floor
+    }
+}
+
+fn mathop(operator: &str, n: Float) -> Float {
+    use std::f64::consts::{LN_10, PI};
+    // Scratch rounds trigonometry to 10 decimals so `sin of 180` is 0
+    let round10 = |n: Float| (n * 1e10).round() / 1e10;
+    // Same formulas as Scratch so the last digits agree
+    let radians = |n: Float| PI * n / 180.0;
+    let degrees = |n: Float| n * 180.0 / PI;
+    match operator {
+        "abs" => n.abs(),
+        "floor" => n.floor(),
+        "ceiling" => n.ceil(),
+        "sqrt" => n.sqrt(),
+        "sin" => round10(radians(n).sin()),
+        "cos" => round10(radians(n).cos()),
+        "tan" => match n % 360.0 {
+            a if a == -270.0 || a == 90.0 => Float::INFINITY,
+            a if a == -90.0 || a == 270.0 => Float::NEG_INFINITY,
+            _ => round10(radians(n).tan()),
+        },
+        "asin" => degrees(n.asin()),
+        "acos" => degrees(n.acos()),
+        "atan" => degrees(n.atan()),
+        "ln" => n.ln(),
+        "log" => n.ln() / LN_10,
+        "e ^" => n.exp(),
+        "10 ^" => (10.0 as Float).powf(n),
+        _ => 0.0,
+    }
+}
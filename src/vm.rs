@@ -0,0 +1,1777 @@
+//! Module to run projects headlessly
+//!
+//! [`Vm`] runs the scripts of a [`Project`] frame by frame without rendering anything,
+//! so tests can check what a project does by looking at its variables, lists and sprites afterwards.
+//!
+//! Scripts run as threads the way Scratch runs them.
+//! A thread runs until it yields, which happens at the end of every loop iteration
+//! unless it's inside a custom block that runs without screen refresh, and while waiting.
+//! Hats are started by [`Vm::green_flag`], broadcasts, clones, [`Vm::press_key`] and [`Vm::click`].
+//!
+//! Where it differs from Scratch:
+//!  - Every frame steps each thread once, like Scratch does when the screen needs a redraw.
+//!    Scratch may step threads more than once in a frame when nothing visible changed,
+//!    which depends on how fast the computer is.
+//!  - Time is counted in frames at [`FRAME_RATE`] frames per second.
+//!  - Nothing is rendered, so touching blocks report false and sprites are not kept on stage.
+//!  - Custom blocks that run without screen refresh yield after [`WARP_ITERATIONS`] loop iterations
+//!    in a frame, where Scratch yields after they ran for half a second.
+//!  - Opcodes the VM doesn't know do nothing and report an empty string. See [`Vm::unsupported_opcodes`]
+
+use crate::block::{
+    Block, BlockField, BlockInputValue, BlockMutationEnum, BlockNormal, DataKind, UidOrValue,
+};
+use crate::list::List;
+use crate::prelude::*;
+use crate::project::Project;
+use crate::target::{RotationStyle, Sprite, SpriteOrStage, Stage};
+use crate::value::{js_number, js_round};
+use crate::variable::Variable;
+use indexmap::IndexMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Frames per second.
+pub const FRAME_RATE: u32 = 30;
+
+/// Most clones there can be at once.
+pub const MAX_CLONES: usize = 300;
+
+/// Most items a list can hold.
+pub const MAX_LIST_LENGTH: usize = 200000;
+
+/// Loop iterations and recursive calls a thread runs without screen refresh before it yields
+/// for the frame anyway. Stands for the 500 ms `WARP_TIME` of Scratch, as time is counted in frames.
+pub const WARP_ITERATIONS: u32 = 100_000;
+
+const STAGE_WIDTH: f64 = 480.0;
+const STAGE_HEIGHT: f64 = 360.0;
+
+/// Runs a [`Project`]. See the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct Vm {
+    project: Project,
+    runtime: Runtime,
+}
+
+type InstanceId = u64;
+
+#[derive(Debug, Clone)]
+struct Runtime {
+    /// The stage, the sprites and their clones, from the back layer to the front.
+    instances: IndexMap<InstanceId, Instance>,
+    stage: InstanceId,
+    threads: Vec<Thread>,
+    next_id: u64,
+    frame: u64,
+    timer_start: u64,
+    keys: HashSet<String>,
+    answer: Text,
+    counter: Int,
+    rng: u64,
+    unsupported: BTreeSet<OpCode>,
+}
+
+/// The stage, a sprite or a clone.
+#[derive(Debug, Clone)]
+struct Instance {
+    /// Index of the target in the project, which has the blocks
+    source: usize,
+    /// State of the target, without blocks
+    target: SpriteOrStage,
+    is_clone: bool,
+    bubble: Option<Text>,
+}
+
+#[derive(Debug, Clone)]
+struct Thread {
+    id: u64,
+    instance: InstanceId,
+    top: Uid,
+    stack: Vec<Frame>,
+    restart: bool,
+}
+
+/// A stack of blocks being run, such as a script, a C mouth or a custom block.
+#[derive(Debug, Clone, Default)]
+struct Frame {
+    block: Option<Uid>,
+    /// State of `block`, for blocks that take more than one step
+    state: Option<State>,
+    /// The frame is the mouth of a loop
+    is_loop: bool,
+    warp: bool,
+    /// Proccode and arguments when the frame is the body of a custom block
+    procedure: Option<(String, HashMap<Name, ValueWithBool>)>,
+}
+
+#[derive(Debug, Clone)]
+enum State {
+    Counter(f64),
+    Timer {
+        start: u64,
+        secs: f64,
+    },
+    Glide {
+        start: u64,
+        secs: f64,
+        from: (f64, f64),
+        to: (f64, f64),
+    },
+    Say {
+        start: u64,
+        secs: f64,
+        message: Text,
+    },
+    Waiting(Vec<u64>),
+}
+
+/// What to do after running a block.
+enum Action {
+    Next,
+    Yield,
+    Branch(Option<Uid>, bool),
+    Call {
+        first: Option<Uid>,
+        proccode: String,
+        args: HashMap<Name, ValueWithBool>,
+        warp: bool,
+    },
+    Return,
+    Stop,
+}
+
+impl Vm {
+    /// VM ready to run the project, nothing runs before [`Vm::green_flag`] or another event.
+    pub fn new(project: Project) -> Vm {
+        let mut instances = vec![];
+        for (source, target) in project.targets.iter().enumerate() {
+            let mut target = target.clone();
+            let t = target.target_mut();
+            t.blocks = StringHashMap::default();
+            t.comments = StringHashMap::default();
+            instances.push(Instance {
+                source,
+                target,
+                is_clone: false,
+                bubble: None,
+            });
+        }
+        instances.sort_by_key(|i| (!i.target.is_stage(), i.target.target().layer_order));
+        let instances: IndexMap<_, _> = (0..).zip(instances).collect();
+        let stage = instances
+            .iter()
+            .find(|(_, i)| i.target.is_stage())
+            .map(|(id, _)| *id)
+            .unwrap_or_default();
+
+        Vm {
+            project,
+            runtime: Runtime {
+                next_id: instances.len() as u64,
+                instances,
+                stage,
+                threads: vec![],
+                frame: 0,
+                timer_start: 0,
+                keys: HashSet::new(),
+                answer: Text::new(),
+                counter: 0,
+                rng: 0x2545_f491_4f6c_dd1d,
+                unsupported: BTreeSet::new(),
+            },
+        }
+    }
+
+    /// The project being run, as it was given.
+    pub fn project(&self) -> &Project {
+        &self.project
+    }
+
+    /// Stop everything and start the green flag scripts.
+    pub fn green_flag(&mut self) {
+        self.runtime.stop_all();
+        self.runtime.timer_start = self.runtime.frame;
+        self.runtime
+            .start_hats(&self.project, "event_whenflagclicked", None, |_| true);
+    }
+
+    /// Start the scripts that receive the broadcast.
+    pub fn broadcast(&mut self, broadcast: &str) {
+        self.runtime.broadcast(&self.project, broadcast);
+    }
+
+    /// Hold down the key, such as `space`, `a` or `left arrow`, and start the scripts waiting for it.
+    pub fn press_key(&mut self, key: &str) {
+        let key = key.to_lowercase();
+        self.runtime.keys.insert(key.clone());
+        self.runtime
+            .start_hats(&self.project, "event_whenkeypressed", None, |b| {
+                let option = field_text(b, "KEY_OPTION").to_lowercase();
+                option == key || option == "any"
+            });
+    }
+
+    /// Let go of the key.
+    pub fn release_key(&mut self, key: &str) {
+        self.runtime.keys.remove(&key.to_lowercase());
+    }
+
+    /// Click the sprite with the name, or the stage.
+    pub fn click(&mut self, target_name: &str) {
+        let Some((id, instance)) = self
+            .runtime
+            .instances
+            .iter()
+            .find(|(_, i)| !i.is_clone && i.target.target().name == target_name)
+        else {
+            return;
+        };
+        let opcode = match instance.target.is_stage() {
+            true => "event_whenstageclicked",
+            false => "event_whenthisspriteclicked",
+        };
+        let id = *id;
+        self.runtime
+            .start_hats(&self.project, opcode, Some(id), |_| true);
+    }
+
+    /// What the ask and wait block gets as answer.
+    pub fn set_answer(&mut self, answer: &str) {
+        self.runtime.answer = answer.to_owned();
+    }
+
+    /// Seed of the random numbers, for when a test needs other numbers than the default seed gives.
+    pub fn set_seed(&mut self, seed: u64) {
+        // Xorshift gets stuck on 0
+        self.runtime.rng = seed.max(1);
+    }
+
+    /// Stop every script and delete every clone, like the stop sign.
+    pub fn stop(&mut self) {
+        self.runtime.stop_all();
+    }
+
+    /// Run one frame.
+    pub fn step(&mut self) {
+        self.runtime.step(&self.project);
+    }
+
+    /// Run the number of frames.
+    pub fn step_frames(&mut self, frames: u64) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    /// Run until no script is running or `max_frames` have run.
+    /// True if every script has finished.
+    pub fn run(&mut self, max_frames: u64) -> bool {
+        for _ in 0..max_frames {
+            if !self.is_running() {
+                return true;
+            }
+            self.step();
+        }
+        !self.is_running()
+    }
+
+    /// True if any script is running.
+    pub fn is_running(&self) -> bool {
+        !self.runtime.threads.is_empty()
+    }
+
+    /// How many frames have run.
+    pub fn frame(&self) -> u64 {
+        self.runtime.frame
+    }
+
+    /// The stage, with the current global variables, lists and backdrop.
+    pub fn stage(&self) -> Option<&Stage> {
+        match &self.runtime.instances.get(&self.runtime.stage)?.target {
+            SpriteOrStage::Stage(s) => Some(s),
+            SpriteOrStage::Sprite(_) => None,
+        }
+    }
+
+    /// The sprite with the name, with its current position, looks, variables and lists.
+    pub fn sprite(&self, name: &str) -> Option<&Sprite> {
+        self.sprites()
+            .find(|(s, is_clone)| !is_clone && s.target.name == name)
+            .map(|(s, _)| s)
+    }
+
+    /// The clones of the sprite with the name, from the back layer to the front.
+    pub fn clones(&self, name: &str) -> Vec<&Sprite> {
+        self.sprites()
+            .filter(|(s, is_clone)| *is_clone && s.target.name == name)
+            .map(|(s, _)| s)
+            .collect()
+    }
+
+    /// Value of the variable of the target with the name, `Stage` for global variables.
+    pub fn variable(&self, target_name: &str, name: &str) -> Option<&ValueWithBool> {
+        self.target(target_name)?
+            .variables
+            .0
+            .values()
+            .find(|v| v.name == name)
+            .map(|v| &v.value)
+    }
+
+    /// Items of the list of the target with the name, `Stage` for global lists.
+    pub fn list(&self, target_name: &str, name: &str) -> Option<&[ValueWithBool]> {
+        self.target(target_name)?
+            .lists
+            .0
+            .values()
+            .find(|l| l.name == name)
+            .map(|l| l.values.as_slice())
+    }
+
+    /// What the sprite with the name is saying or thinking.
+    pub fn bubble(&self, sprite_name: &str) -> Option<&str> {
+        self.runtime
+            .instances
+            .values()
+            .find(|i| !i.is_clone && i.target.target().name == sprite_name)?
+            .bubble
+            .as_deref()
+    }
+
+    /// Opcodes that were run but the VM doesn't know.
+    pub fn unsupported_opcodes(&self) -> &BTreeSet<OpCode> {
+        &self.runtime.unsupported
+    }
+
+    fn target(&self, name: &str) -> Option<&crate::target::Target> {
+        self.runtime
+            .instances
+            .values()
+            .find(|i| !i.is_clone && i.target.target().name == name)
+            .map(|i| i.target.target())
+    }
+
+    fn sprites(&self) -> impl Iterator<Item = (&Sprite, bool)> {
+        self.runtime
+            .instances
+            .values()
+            .filter_map(|i| match &i.target {
+                SpriteOrStage::Sprite(s) => Some((s, i.is_clone)),
+                SpriteOrStage::Stage(_) => None,
+            })
+    }
+}
+
+impl Runtime {
+    fn step(&mut self, code: &Project) {
+        // Threads started during the frame are added at the end and run in the same frame
+        let mut i = 0;
+        while i < self.threads.len() {
+            self.step_thread(code, i);
+            i += 1;
+        }
+        self.threads.retain(|t| !t.stack.is_empty() || t.restart);
+        self.frame += 1;
+    }
+
+    fn step_thread(&mut self, code: &Project, ti: usize) {
+        let thread = &mut self.threads[ti];
+        if thread.restart {
+            thread.restart = false;
+            thread.stack = vec![Frame {
+                block: Some(thread.top.clone()),
+                ..Default::default()
+            }];
+        }
+
+        let mut warp_iterations = 0;
+        let mut warp_timed_out = || {
+            warp_iterations += 1;
+            warp_iterations >= WARP_ITERATIONS
+        };
+        loop {
+            let thread = &mut self.threads[ti];
+            let Some(frame) = thread.stack.last() else {
+                return;
+            };
+            let Some(id) = frame.block.clone() else {
+                let finished = thread.stack.pop().unwrap();
+                let Some(parent) = thread.stack.last_mut() else {
+                    return;
+                };
+                if !finished.is_loop {
+                    // The branch of an if or the body of a custom block, go on after it
+                    advance(code, self.instances[&thread.instance].source, parent);
+                } else if !parent.warp || warp_timed_out() {
+                    // The loop block runs again next frame
+                    return;
+                }
+                continue;
+            };
+            let source = self.instances[&thread.instance].source;
+            let block = match blocks(code, source).0.get(&id) {
+                Some(Block::Normal(block)) => block,
+                _ => {
+                    thread.stack.last_mut().unwrap().block = None;
+                    continue;
+                }
+            };
+
+            match self.execute(code, ti, block) {
+                Action::Next => {
+                    let frame = self.threads[ti].stack.last_mut().unwrap();
+                    advance(code, source, frame);
+                }
+                Action::Yield => return,
+                Action::Branch(substack, is_loop) => {
+                    let thread = &mut self.threads[ti];
+                    let frame = thread.stack.last_mut().unwrap();
+                    let warp = frame.warp;
+                    match substack {
+                        Some(first) => thread.stack.push(Frame {
+                            block: Some(first),
+                            is_loop,
+                            warp,
+                            ..Default::default()
+                        }),
+                        // An empty loop still yields every iteration
+                        None if is_loop => {
+                            if !warp || warp_timed_out() {
+                                return;
+                            }
+                        }
+                        None => advance(code, source, frame),
+                    }
+                }
+                Action::Call {
+                    first,
+                    proccode,
+                    args,
+                    warp,
+                } => {
+                    let thread = &mut self.threads[ti];
+                    let recursive = thread
+                        .stack
+                        .iter()
+                        .any(|f| matches!(&f.procedure, Some((p, _)) if *p == proccode));
+                    let warp = warp || thread.stack.last().unwrap().warp;
+                    thread.stack.push(Frame {
+                        block: first,
+                        warp,
+                        procedure: Some((proccode, args)),
+                        ..Default::default()
+                    });
+                    if recursive && (!warp || warp_timed_out()) {
+                        return;
+                    }
+                }
+                Action::Return => {
+                    let thread = &mut self.threads[ti];
+                    while let Some(frame) = thread.stack.pop() {
+                        if frame.procedure.is_some() {
+                            break;
+                        }
+                    }
+                    match thread.stack.last_mut() {
+                        Some(caller) => advance(code, source, caller),
+                        None => return,
+                    }
+                }
+                Action::Stop => {
+                    self.threads[ti].stack.clear();
+                    return;
+                }
+            }
+        }
+    }
+
+    fn execute(&mut self, code: &Project, ti: usize, block: &BlockNormal) -> Action {
+        use Action::*;
+
+        let opcode = block.opcode.as_str();
+        match opcode {
+            // Hats only start scripts, there's nothing to do when running one
+            "event_whenflagclicked"
+            | "event_whenbroadcastreceived"
+            | "event_whenkeypressed"
+            | "event_whenthisspriteclicked"
+            | "event_whenstageclicked"
+            | "event_whenbackdropswitchesto"
+            | "control_start_as_clone"
+            | "procedures_definition" => Next,
+
+            // Events ==========================================================
+            "event_broadcast" => {
+                let broadcast = self.input(code, ti, block, "BROADCAST_INPUT");
+                self.broadcast(code, &broadcast.to_scratch_string());
+                Next
+            }
+            "event_broadcastandwait" => {
+                if self.state(ti).is_none() {
+                    let broadcast = self.input(code, ti, block, "BROADCAST_INPUT");
+                    let started = self.broadcast(code, &broadcast.to_scratch_string());
+                    if started.is_empty() {
+                        return Next;
+                    }
+                    self.set_state(ti, State::Waiting(started));
+                }
+                self.wait_for_threads(ti)
+            }
+
+            // Control =========================================================
+            "control_wait" => match self.state(ti) {
+                None => {
+                    let secs = self.input(code, ti, block, "DURATION").to_number();
+                    self.set_state(
+                        ti,
+                        State::Timer {
+                            start: self.frame,
+                            secs,
+                        },
+                    );
+                    Yield
+                }
+                Some(State::Timer { start, secs }) if self.elapsed(*start) < *secs => Yield,
+                Some(_) => Next,
+            },
+            "control_repeat" => {
+                let count = match self.state(ti) {
+                    Some(State::Counter(n)) => *n,
+                    _ => self.input(code, ti, block, "TIMES").to_number().round(),
+                };
+                self.set_state(ti, State::Counter(count - 1.0));
+                match count - 1.0 >= 0.0 {
+                    true => Branch(substack(block, "SUBSTACK"), true),
+                    false => Next,
+                }
+            }
+            "control_forever" => Branch(substack(block, "SUBSTACK"), true),
+            "control_if" => match self.input(code, ti, block, "CONDITION").to_bool() {
+                true => Branch(substack(block, "SUBSTACK"), false),
+                false => Next,
+            },
+            "control_if_else" => match self.input(code, ti, block, "CONDITION").to_bool() {
+                true => Branch(substack(block, "SUBSTACK"), false),
+                false => Branch(substack(block, "SUBSTACK2"), false),
+            },
+            "control_repeat_until" => match self.input(code, ti, block, "CONDITION").to_bool() {
+                true => Next,
+                false => Branch(substack(block, "SUBSTACK"), true),
+            },
+            "control_while" => match self.input(code, ti, block, "CONDITION").to_bool() {
+                true => Branch(substack(block, "SUBSTACK"), true),
+                false => Next,
+            },
+            "control_wait_until" => match self.input(code, ti, block, "CONDITION").to_bool() {
+                true => Next,
+                false => Yield,
+            },
+            "control_for_each" => {
+                let value = self.input(code, ti, block, "VALUE").to_number();
+                let index = match self.state(ti) {
+                    Some(State::Counter(n)) => *n,
+                    _ => 0.0,
+                };
+                if index < value {
+                    self.set_state(ti, State::Counter(index + 1.0));
+                    let (id, name) = data_field(block, DataKind::Variable);
+                    *self.variable_mut(ti, &id, &name) = number(index + 1.0);
+                    Branch(substack(block, "SUBSTACK"), true)
+                } else {
+                    Next
+                }
+            }
+            "control_all_at_once" => Branch(substack(block, "SUBSTACK"), false),
+            "control_stop" => match field_text(block, "STOP_OPTION").as_str() {
+                "all" => {
+                    self.stop_all();
+                    Stop
+                }
+                "this script" => Return,
+                _ => {
+                    let instance = self.threads[ti].instance;
+                    let id = self.threads[ti].id;
+                    for thread in &mut self.threads {
+                        if thread.instance == instance && thread.id != id {
+                            thread.stack.clear();
+                            thread.restart = false;
+                        }
+                    }
+                    Next
+                }
+            },
+            "control_create_clone_of" => {
+                let option = self
+                    .input(code, ti, block, "CLONE_OPTION")
+                    .to_scratch_string();
+                let original = match option.as_str() {
+                    "_myself_" => Some(self.threads[ti].instance),
+                    name => self.find_sprite(name),
+                };
+                if let Some(original) = original {
+                    self.create_clone(code, original);
+                }
+                Next
+            }
+            "control_delete_this_clone" => {
+                let instance = self.threads[ti].instance;
+                if !self.instances[&instance].is_clone {
+                    return Next;
+                }
+                self.instances.shift_remove(&instance);
+                for thread in &mut self.threads {
+                    if thread.instance == instance {
+                        thread.stack.clear();
+                        thread.restart = false;
+                    }
+                }
+                Stop
+            }
+            "control_incr_counter" => {
+                self.counter += 1;
+                Next
+            }
+            "control_clear_counter" => {
+                self.counter = 0;
+                Next
+            }
+
+            // Data ============================================================
+            "data_setvariableto" => {
+                let value = self.input(code, ti, block, "VALUE");
+                let (id, name) = data_field(block, DataKind::Variable);
+                *self.variable_mut(ti, &id, &name) = value;
+                Next
+            }
+            "data_changevariableby" => {
+                let value = self.input(code, ti, block, "VALUE").to_number();
+                let (id, name) = data_field(block, DataKind::Variable);
+                let variable = self.variable_mut(ti, &id, &name);
+                *variable = number(variable.to_number() + value);
+                Next
+            }
+            "data_addtolist" => {
+                let item = self.input(code, ti, block, "ITEM");
+                let list = self.list_mut(ti, block);
+                if list.len() < MAX_LIST_LENGTH {
+                    list.push(item);
+                }
+                Next
+            }
+            "data_deleteoflist" => {
+                let index = self.input(code, ti, block, "INDEX");
+                let len = self.list_mut(ti, block).len();
+                match self.list_index(&index, len, true) {
+                    ListIndex::All => self.list_mut(ti, block).clear(),
+                    ListIndex::Index(i) => {
+                        self.list_mut(ti, block).remove(i);
+                    }
+                    ListIndex::Invalid => {}
+                }
+                Next
+            }
+            "data_deletealloflist" => {
+                self.list_mut(ti, block).clear();
+                Next
+            }
+            "data_insertatlist" => {
+                let item = self.input(code, ti, block, "ITEM");
+                let index = self.input(code, ti, block, "INDEX");
+                let len = self.list_mut(ti, block).len();
+                if let ListIndex::Index(i) = self.list_index(&index, len + 1, false) {
+                    let list = self.list_mut(ti, block);
+                    if list.len() < MAX_LIST_LENGTH {
+                        list.insert(i, item);
+                    }
+                }
+                Next
+            }
+            "data_replaceitemoflist" => {
+                let item = self.input(code, ti, block, "ITEM");
+                let index = self.input(code, ti, block, "INDEX");
+                let len = self.list_mut(ti, block).len();
+                if let ListIndex::Index(i) = self.list_index(&index, len, false) {
+                    self.list_mut(ti, block)[i] = item;
+                }
+                Next
+            }
+            "data_showvariable" | "data_hidevariable" | "data_showlist" | "data_hidelist" => Next,
+
+            // Procedures ======================================================
+            "procedures_call" => self.call(code, ti, block),
+
+            // Motion ==========================================================
+            "motion_movesteps" => {
+                let steps = self.input(code, ti, block, "STEPS").to_number();
+                if let Some(sprite) = self.sprite_mut(ti) {
+                    let radians = (90.0 - sprite.direction.to_float()).to_radians();
+                    let x = sprite.x.to_float() + steps * radians.cos();
+                    let y = sprite.y.to_float() + steps * radians.sin();
+                    set_xy(sprite, x, y);
+                }
+                Next
+            }
+            "motion_turnright" | "motion_turnleft" => {
+                let mut degrees = self.input(code, ti, block, "DEGREES").to_number();
+                if opcode == "motion_turnleft" {
+                    degrees = -degrees;
+                }
+                if let Some(sprite) = self.sprite_mut(ti) {
+                    let direction = sprite.direction.to_float() + degrees;
+                    set_direction(sprite, direction);
+                }
+                Next
+            }
+            "motion_goto" => {
+                let to = self.input(code, ti, block, "TO").to_scratch_string();
+                if let Some((x, y)) = self.position_of(ti, &to) {
+                    if let Some(sprite) = self.sprite_mut(ti) {
+                        set_xy(sprite, x, y);
+                    }
+                }
+                Next
+            }
+            "motion_gotoxy" => {
+                let x = self.input(code, ti, block, "X").to_number();
+                let y = self.input(code, ti, block, "Y").to_number();
+                if let Some(sprite) = self.sprite_mut(ti) {
+                    set_xy(sprite, x, y);
+                }
+                Next
+            }
+            "motion_glidesecstoxy" | "motion_glideto" => self.glide(code, ti, block),
+            "motion_pointindirection" => {
+                let direction = self.input(code, ti, block, "DIRECTION").to_number();
+                if let Some(sprite) = self.sprite_mut(ti) {
+                    set_direction(sprite, direction);
+                }
+                Next
+            }
+            "motion_pointtowards" => {
+                let towards = self.input(code, ti, block, "TOWARDS").to_scratch_string();
+                let target = match towards.as_str() {
+                    "_random_" => Some(((self.random() * 360.0).round() - 180.0, 0.0)),
+                    _ => self.position_of(ti, &towards),
+                };
+                if let (Some((tx, ty)), Some(sprite)) = (target, self.sprite_mut(ti)) {
+                    let dx = tx - sprite.x.to_float();
+                    let dy = ty - sprite.y.to_float();
+                    let direction = match towards.as_str() {
+                        "_random_" => tx,
+                        _ => 90.0 - dy.atan2(dx).to_degrees(),
+                    };
+                    set_direction(sprite, direction);
+                }
+                Next
+            }
+            "motion_changexby" | "motion_setx" | "motion_changeyby" | "motion_sety" => {
+                let name = match opcode {
+                    "motion_changexby" => "DX",
+                    "motion_changeyby" => "DY",
+                    "motion_setx" => "X",
+                    _ => "Y",
+                };
+                let value = self.input(code, ti, block, name).to_number();
+                if let Some(sprite) = self.sprite_mut(ti) {
+                    let (mut x, mut y) = (sprite.x.to_float(), sprite.y.to_float());
+                    match opcode {
+                        "motion_changexby" => x += value,
+                        "motion_changeyby" => y += value,
+                        "motion_setx" => x = value,
+                        _ => y = value,
+                    }
+                    set_xy(sprite, x, y);
+                }
+                Next
+            }
+            "motion_setrotationstyle" => {
+                let style = match field_text(block, "STYLE").as_str() {
+                    "left-right" => Some(RotationStyle::LeftRight),
+                    "don't rotate" => Some(RotationStyle::DontRotate),
+                    "all around" => Some(RotationStyle::AllAround),
+                    _ => None,
+                };
+                if let (Some(style), Some(sprite)) = (style, self.sprite_mut(ti)) {
+                    sprite.rotation_style = style;
+                }
+                Next
+            }
+            // Needs the size of the costume
+            "motion_ifonedgebounce" => Next,
+
+            // Looks ===========================================================
+            "looks_say" | "looks_think" => {
+                let message = self.input(code, ti, block, "MESSAGE").to_scratch_string();
+                self.set_bubble(ti, message);
+                Next
+            }
+            "looks_sayforsecs" | "looks_thinkforsecs" => match self.state(ti) {
+                None => {
+                    let message = self.input(code, ti, block, "MESSAGE").to_scratch_string();
+                    let secs = self.input(code, ti, block, "SECS").to_number();
+                    self.set_bubble(ti, message.clone());
+                    self.set_state(
+                        ti,
+                        State::Say {
+                            start: self.frame,
+                            secs,
+                            message,
+                        },
+                    );
+                    Yield
+                }
+                Some(State::Say { start, secs, .. }) if self.elapsed(*start) < *secs => Yield,
+                Some(State::Say { message, .. }) => {
+                    let message = message.clone();
+                    let instance = self.threads[ti].instance;
+                    let bubble = &mut self.instances[&instance].bubble;
+                    if bubble.as_ref() == Some(&message) {
+                        *bubble = None;
+                    }
+                    Next
+                }
+                Some(_) => Next,
+            },
+            "looks_show" | "looks_hide" => {
+                if let Some(sprite) = self.sprite_mut(ti) {
+                    sprite.visible = opcode == "looks_show";
+                }
+                Next
+            }
+            "looks_switchcostumeto" => {
+                let costume = self.input(code, ti, block, "COSTUME");
+                let instance = self.threads[ti].instance;
+                if !self.instances[&instance].target.is_stage() {
+                    let target = self.instances[&instance].target.target_mut();
+                    set_costume(target, &costume, "costume");
+                }
+                Next
+            }
+            "looks_nextcostume" => {
+                let instance = self.threads[ti].instance;
+                if !self.instances[&instance].target.is_stage() {
+                    let target = self.instances[&instance].target.target_mut();
+                    let next = target.current_costume + 1;
+                    set_costume_index(target, next as f64);
+                }
+                Next
+            }
+            "looks_switchbackdropto" | "looks_switchbackdroptoandwait" | "looks_nextbackdrop" => {
+                if self.state(ti).is_none() {
+                    let backdrop = match opcode {
+                        "looks_nextbackdrop" => ValueWithBool::Text("next backdrop".to_owned()),
+                        _ => self.input(code, ti, block, "BACKDROP"),
+                    };
+                    let started = self.switch_backdrop(code, &backdrop);
+                    if opcode != "looks_switchbackdroptoandwait" || started.is_empty() {
+                        return Next;
+                    }
+                    self.set_state(ti, State::Waiting(started));
+                }
+                self.wait_for_threads(ti)
+            }
+            "looks_changesizeby" | "looks_setsizeto" => {
+                let name = match opcode {
+                    "looks_changesizeby" => "CHANGE",
+                    _ => "SIZE",
+                };
+                let value = self.input(code, ti, block, name).to_number();
+                if let Some(sprite) = self.sprite_mut(ti) {
+                    let size = match opcode {
+                        "looks_changesizeby" => sprite.size.to_float() + value,
+                        _ => value,
+                    };
+                    sprite.size = Number::normalize(size.max(0.0));
+                }
+                Next
+            }
+            "looks_gotofrontback" => {
+                let instance = self.threads[ti].instance;
+                if let Some(from) = self.instances.get_index_of(&instance) {
+                    let to = match field_text(block, "FRONT_BACK").as_str() {
+                        "back" => 1,
+                        _ => self.instances.len() - 1,
+                    };
+                    if from != 0 {
+                        self.instances.move_index(from, to);
+                    }
+                }
+                Next
+            }
+            "looks_goforwardbackwardlayers" => {
+                let layers = self.input(code, ti, block, "NUM").to_number();
+                let layers = match field_text(block, "FORWARD_BACKWARD").as_str() {
+                    "backward" => -layers,
+                    _ => layers,
+                };
+                // Going further than every layer is the same as going to the end, even Infinity
+                let count = self.instances.len() as Float;
+                let layers = layers.clamp(-count, count) as i64;
+                let instance = self.threads[ti].instance;
+                if let Some(from) = self.instances.get_index_of(&instance) {
+                    let last = self.instances.len() as i64 - 1;
+                    let to = (from as i64 + layers).clamp(1, last.max(1)) as usize;
+                    if from != 0 && to < self.instances.len() {
+                        self.instances.move_index(from, to);
+                    }
+                }
+                Next
+            }
+            // Nothing is rendered so graphic effects don't matter
+            "looks_changeeffectby" | "looks_seteffectto" | "looks_cleargraphiceffects" => Next,
+
+            // Sensing =========================================================
+            "sensing_resettimer" => {
+                self.timer_start = self.frame;
+                Next
+            }
+            "sensing_askandwait" => Next,
+
+            _ if is_reporter(opcode) => {
+                // Reporter dropped in a script on its own
+                self.report(code, ti, block);
+                Next
+            }
+            _ => {
+                self.unsupported.insert(block.opcode.clone());
+                Next
+            }
+        }
+    }
+
+    /// Value of a reporter block.
+    fn report(&mut self, code: &Project, ti: usize, block: &BlockNormal) -> ValueWithBool {
+        use ValueWithBool::{Bool, Text};
+
+        let opcode = block.opcode.as_str();
+        match opcode {
+            // Control =========================================================
+            "control_get_counter" => ValueWithBool::Number(Number::Int(self.counter)),
+
+            // Data ============================================================
+            "data_variable" => {
+                let (id, name) = data_field(block, DataKind::Variable);
+                self.variable_mut(ti, &id, &name).clone()
+            }
+            "data_listcontents" => list_contents(self.list_mut(ti, block)),
+            "data_itemoflist" => {
+                let index = self.input(code, ti, block, "INDEX");
+                let len = self.list_mut(ti, block).len();
+                match self.list_index(&index, len, false) {
+                    ListIndex::Index(i) => self.list_mut(ti, block)[i].clone(),
+                    _ => Text(String::new()),
+                }
+            }
+            "data_itemnumoflist" => {
+                let item = self.input(code, ti, block, "ITEM");
+                let list = self.list_mut(ti, block);
+                let index = list.iter().position(|v| v.scratch_eq(&item));
+                number(index.map_or(0.0, |i| (i + 1) as f64))
+            }
+            "data_lengthoflist" => number(self.list_mut(ti, block).len() as f64),
+            "data_listcontainsitem" => {
+                let item = self.input(code, ti, block, "ITEM");
+                let list = self.list_mut(ti, block);
+                Bool(list.contains(&item) || list.iter().any(|v| v.scratch_eq(&item)))
+            }
+
+            // Operators =======================================================
+            "operator_add" | "operator_subtract" | "operator_multiply" | "operator_divide"
+            | "operator_mod" => {
+                let a = self.input(code, ti, block, "NUM1");
+                let b = self.input(code, ti, block, "NUM2");
+                ValueWithBool::Number(match opcode {
+                    "operator_add" => a.scratch_add(&b),
+                    "operator_subtract" => a.scratch_sub(&b),
+                    "operator_multiply" => a.scratch_mul(&b),
+                    "operator_divide" => a.scratch_div(&b),
+                    _ => a.scratch_mod(&b),
+                })
+            }
+            "operator_random" => {
+                let from = self.input(code, ti, block, "FROM");
+                let to = self.input(code, ti, block, "TO");
+                let (a, b) = (from.to_number(), to.to_number());
+                let (low, high) = if a <= b { (a, b) } else { (b, a) };
+                if low == high {
+                    number(low)
+                } else if from.is_int() && to.is_int() {
+                    number(low + (self.random() * (high + 1.0 - low)).floor())
+                } else {
+                    number(self.random() * (high - low) + low)
+                }
+            }
+            "operator_gt" | "operator_lt" | "operator_equals" => {
+                let a = self.input(code, ti, block, "OPERAND1");
+                let b = self.input(code, ti, block, "OPERAND2");
+                let order = a.scratch_compare(&b);
+                Bool(match opcode {
+                    "operator_gt" => order.is_gt(),
+                    "operator_lt" => order.is_lt(),
+                    _ => order.is_eq(),
+                })
+            }
+            "operator_and" | "operator_or" => {
+                let a = self.input(code, ti, block, "OPERAND1").to_bool();
+                let b = self.input(code, ti, block, "OPERAND2").to_bool();
+                Bool(match opcode {
+                    "operator_and" => a && b,
+                    _ => a || b,
+                })
+            }
+            "operator_not" => Bool(!self.input(code, ti, block, "OPERAND").to_bool()),
+            "operator_join" => {
+                let a = self.input(code, ti, block, "STRING1");
+                let b = self.input(code, ti, block, "STRING2");
+                Text(a.join(&b))
+            }
+            "operator_letter_of" => {
+                let letter = self.input(code, ti, block, "LETTER");
+                let string = self.input(code, ti, block, "STRING");
+                Text(string.letter_of(&letter))
+            }
+            "operator_length" => {
+                let string = self.input(code, ti, block, "STRING");
+                ValueWithBool::Number(Number::Int(string.length()))
+            }
+            "operator_contains" => {
+                let a = self.input(code, ti, block, "STRING1");
+                let b = self.input(code, ti, block, "STRING2");
+                Bool(a.contains(&b))
+            }
+            "operator_round" => {
+                ValueWithBool::Number(self.input(code, ti, block, "NUM").scratch_round())
+            }
+            "operator_mathop" => {
+                let n = self.input(code, ti, block, "NUM");
+                ValueWithBool::Number(n.mathop(&field_text(block, "OPERATOR")))
+            }
+
+            // Procedures ======================================================
+            "argument_reporter_string_number" | "argument_reporter_boolean" => {
+                let name = field_text(block, "VALUE");
+                let arg = self.threads[ti]
+                    .stack
+                    .iter()
+                    .rev()
+                    .find_map(|f| f.procedure.as_ref())
+                    .and_then(|(_, args)| args.get(&name));
+                match (arg, opcode) {
+                    (Some(arg), _) => arg.clone(),
+                    (None, "argument_reporter_boolean") => Bool(false),
+                    (None, _) => number(0.0),
+                }
+            }
+
+            // Motion ==========================================================
+            "motion_xposition" | "motion_yposition" | "motion_direction" => {
+                match self.sprite_mut(ti) {
+                    Some(sprite) => number(limit_precision(
+                        match opcode {
+                            "motion_xposition" => sprite.x,
+                            "motion_yposition" => sprite.y,
+                            _ => sprite.direction,
+                        }
+                        .to_float(),
+                    )),
+                    None => number(0.0),
+                }
+            }
+
+            // Looks ===========================================================
+            "looks_costumenumbername" | "looks_backdropnumbername" => {
+                let instance = match opcode {
+                    "looks_costumenumbername" => self.threads[ti].instance,
+                    _ => self.stage,
+                };
+                let target = self.instances[&instance].target.target();
+                match field_text(block, "NUMBER_NAME").as_str() {
+                    "name" => Text(
+                        target
+                            .costumes
+                            .get(target.current_costume as usize)
+                            .map(|c| c.asset.name.clone())
+                            .unwrap_or_default(),
+                    ),
+                    _ => number((target.current_costume + 1) as f64),
+                }
+            }
+            "looks_size" => match self.sprite_mut(ti) {
+                Some(sprite) => number(js_round(sprite.size.to_float())),
+                None => number(100.0),
+            },
+
+            // Sensing =========================================================
+            "sensing_timer" => number(self.elapsed(self.timer_start)),
+            "sensing_answer" => Text(self.answer.clone()),
+            "sensing_keypressed" => {
+                let key = self
+                    .input(code, ti, block, "KEY_OPTION")
+                    .to_scratch_string()
+                    .to_lowercase();
+                Bool(match key.as_str() {
+                    "any" => !self.keys.is_empty(),
+                    key => self.keys.contains(key),
+                })
+            }
+            "sensing_mousex" | "sensing_mousey" => number(0.0),
+            "sensing_mousedown" => Bool(false),
+            "sensing_touchingobject" | "sensing_touchingcolor" | "sensing_coloristouchingcolor" => {
+                Bool(false)
+            }
+            "sensing_distanceto" => {
+                let to = self
+                    .input(code, ti, block, "DISTANCETOMENU")
+                    .to_scratch_string();
+                let target = self.position_of(ti, &to);
+                match (target, self.sprite_mut(ti)) {
+                    (Some((x, y)), Some(sprite)) => {
+                        let dx = sprite.x.to_float() - x;
+                        let dy = sprite.y.to_float() - y;
+                        number((dx * dx + dy * dy).sqrt())
+                    }
+                    _ => number(10000.0),
+                }
+            }
+            "sensing_of" => {
+                let object = self.input(code, ti, block, "OBJECT").to_scratch_string();
+                self.property_of(&object, &field_text(block, "PROPERTY"))
+            }
+            "sensing_username" => Text(String::new()),
+
+            // Menus report their only field
+            _ if block.inputs.0.is_empty() && block.fields.0.len() == 1 => {
+                let field = block.fields.0.values().next().unwrap();
+                let (BlockField::WithId { value, .. } | BlockField::NoId { value }) = field;
+                value.clone().into()
+            }
+            _ => {
+                self.unsupported.insert(block.opcode.clone());
+                Text(String::new())
+            }
+        }
+    }
+
+    /// Value of the input with the name, an empty string if there's none.
+    fn input(
+        &mut self,
+        code: &Project,
+        ti: usize,
+        block: &BlockNormal,
+        name: &str,
+    ) -> ValueWithBool {
+        let value = block
+            .inputs
+            .0
+            .get(name)
+            .and_then(|input| input.inputs.iter().flatten().next());
+        match value {
+            Some(UidOrValue::Uid(id)) => {
+                let source = self.instances[&self.threads[ti].instance].source;
+                match blocks(code, source).0.get(id) {
+                    Some(Block::Normal(reporter)) => self.report(code, ti, reporter),
+                    _ => ValueWithBool::Text(String::new()),
+                }
+            }
+            Some(UidOrValue::Value(value)) => match value {
+                BlockInputValue::Number { value }
+                | BlockInputValue::PositiveNumber { value }
+                | BlockInputValue::PositiveInteger { value }
+                | BlockInputValue::Integer { value }
+                | BlockInputValue::Angle { value }
+                | BlockInputValue::Color { value }
+                | BlockInputValue::String { value } => value.clone().into(),
+                BlockInputValue::Broadcast { name, .. } => ValueWithBool::Text(name.clone()),
+                BlockInputValue::Variable { name, id, .. } => {
+                    self.variable_mut(ti, id, name).clone()
+                }
+                BlockInputValue::List { name, id, .. } => {
+                    let (instance, id) = self.lookup(ti, DataKind::List, id, name);
+                    let list = &self.instances[&instance].target.target().lists.0[&id];
+                    list_contents(&list.values)
+                }
+            },
+            None => ValueWithBool::Text(String::new()),
+        }
+    }
+
+    fn call(&mut self, code: &Project, ti: usize, block: &BlockNormal) -> Action {
+        let Some(BlockMutationEnum::ProceduresCall { proccode, .. }) =
+            block.mutation.as_ref().map(|m| &m.mutation_enum)
+        else {
+            return Action::Next;
+        };
+        let source = self.instances[&self.threads[ti].instance].source;
+        let blocks = blocks(code, source);
+        let definition = blocks.0.values().find_map(|b| {
+            let Block::Normal(definition) = b else {
+                return None;
+            };
+            if definition.opcode != "procedures_definition" {
+                return None;
+            }
+            let prototype = match blocks.0.get(&substack(definition, "custom_block")?) {
+                Some(Block::Normal(prototype)) => prototype,
+                _ => return None,
+            };
+            match &prototype.mutation.as_ref()?.mutation_enum {
+                BlockMutationEnum::ProceduresPrototype {
+                    proccode: p,
+                    argumentids,
+                    argumentnames,
+                    argumentdefaults,
+                    warp,
+                } if p == proccode => Some((
+                    definition,
+                    argumentids,
+                    argumentnames,
+                    argumentdefaults,
+                    warp.unwrap_or(false),
+                )),
+                _ => None,
+            }
+        });
+        // Like Scratch, a call without a definition does nothing
+        let Some((definition, ids, names, defaults, warp)) = definition else {
+            return Action::Next;
+        };
+
+        let mut args = HashMap::new();
+        for (i, (id, name)) in ids.iter().zip(names).enumerate() {
+            let value = match block.inputs.0.contains_key(id) {
+                true => self.input(code, ti, block, id),
+                false => defaults.get(i).cloned().unwrap_or_default(),
+            };
+            args.insert(name.clone(), value);
+        }
+        Action::Call {
+            first: definition.next.clone(),
+            proccode: proccode.clone(),
+            args,
+            warp,
+        }
+    }
+
+    fn glide(&mut self, code: &Project, ti: usize, block: &BlockNormal) -> Action {
+        match self.state(ti) {
+            None => {
+                let secs = self.input(code, ti, block, "SECS").to_number().max(0.0);
+                let to = match block.opcode.as_str() {
+                    "motion_glideto" => {
+                        let to = self.input(code, ti, block, "TO").to_scratch_string();
+                        self.position_of(ti, &to)
+                    }
+                    _ => Some((
+                        self.input(code, ti, block, "X").to_number(),
+                        self.input(code, ti, block, "Y").to_number(),
+                    )),
+                };
+                let (Some(to), Some(sprite)) = (to, self.sprite_mut(ti)) else {
+                    return Action::Next;
+                };
+                let from = (sprite.x.to_float(), sprite.y.to_float());
+                self.set_state(
+                    ti,
+                    State::Glide {
+                        start: self.frame,
+                        secs,
+                        from,
+                        to,
+                    },
+                );
+                Action::Yield
+            }
+            Some(State::Glide {
+                start,
+                secs,
+                from,
+                to,
+            }) => {
+                let (elapsed, secs, from, to) = (self.elapsed(*start), *secs, *from, *to);
+                let sprite = self.sprite_mut(ti).unwrap();
+                if elapsed < secs {
+                    let frac = elapsed / secs;
+                    let x = from.0 + frac * (to.0 - from.0);
+                    let y = from.1 + frac * (to.1 - from.1);
+                    set_xy(sprite, x, y);
+                    Action::Yield
+                } else {
+                    set_xy(sprite, to.0, to.1);
+                    Action::Next
+                }
+            }
+            Some(_) => Action::Next,
+        }
+    }
+
+    fn wait_for_threads(&self, ti: usize) -> Action {
+        let Some(State::Waiting(started)) = self.state(ti) else {
+            return Action::Next;
+        };
+        // Threads that finished are still waited for until the end of the frame, like in Scratch
+        match started
+            .iter()
+            .any(|id| self.threads.iter().any(|t| t.id == *id))
+        {
+            true => Action::Yield,
+            false => Action::Next,
+        }
+    }
+
+    /// Start the hats with the opcode that `matches` in every instance, or only in `only`.
+    /// Returns the Ids of the threads started.
+    fn start_hats<F>(
+        &mut self,
+        code: &Project,
+        opcode: &str,
+        only: Option<InstanceId>,
+        matches: F,
+    ) -> Vec<u64>
+    where
+        F: Fn(&BlockNormal) -> bool,
+    {
+        // These hats don't restart their script if it's still running
+        let restart = !matches!(
+            opcode,
+            "event_whenkeypressed" | "control_start_as_clone" | "event_whenbackdropswitchesto"
+        );
+        let instances: Vec<InstanceId> = match only {
+            Some(id) => vec![id],
+            // Front layer first, like Scratch
+            None => self.instances.keys().rev().copied().collect(),
+        };
+
+        let mut started = vec![];
+        for instance in instances {
+            let source = self.instances[&instance].source;
+            for (id, block) in &blocks(code, source).0 {
+                let Block::Normal(block) = block else {
+                    continue;
+                };
+                if !block.top_level || block.opcode != opcode || !matches(block) {
+                    continue;
+                }
+                let running = self
+                    .threads
+                    .iter_mut()
+                    .find(|t| t.instance == instance && &t.top == id);
+                match running {
+                    Some(thread) => {
+                        if restart {
+                            thread.restart = true;
+                            started.push(thread.id);
+                        }
+                    }
+                    None => {
+                        let thread_id = self.new_id();
+                        self.threads.push(Thread {
+                            id: thread_id,
+                            instance,
+                            top: id.clone(),
+                            stack: vec![Frame {
+                                block: Some(id.clone()),
+                                ..Default::default()
+                            }],
+                            restart: false,
+                        });
+                        started.push(thread_id);
+                    }
+                }
+            }
+        }
+        started
+    }
+
+    fn broadcast(&mut self, code: &Project, broadcast: &str) -> Vec<u64> {
+        let broadcast = broadcast.to_lowercase();
+        self.start_hats(code, "event_whenbroadcastreceived", None, |b| {
+            field_text(b, "BROADCAST_OPTION").to_lowercase() == broadcast
+        })
+    }
+
+    fn switch_backdrop(&mut self, code: &Project, backdrop: &ValueWithBool) -> Vec<u64> {
+        let stage = self.instances[&self.stage].target.target_mut();
+        set_costume(stage, backdrop, "backdrop");
+        if backdrop == &ValueWithBool::Text("random backdrop".to_owned())
+            && stage.costumes.len() > 1
+        {
+            let others = stage.costumes.len() - 1;
+            let current = stage.current_costume;
+            let offset = 1 + (self.random() * others as f64).floor() as Int;
+            let stage = self.instances[&self.stage].target.target_mut();
+            set_costume_index(stage, (current + offset) as f64);
+        }
+
+        let stage = self.instances[&self.stage].target.target();
+        let name = stage
+            .costumes
+            .get(stage.current_costume as usize)
+            .map(|c| c.asset.name.to_lowercase())
+            .unwrap_or_default();
+        self.start_hats(code, "event_whenbackdropswitchesto", None, |b| {
+            field_text(b, "BACKDROP").to_lowercase() == name
+        })
+    }
+
+    fn create_clone(&mut self, code: &Project, original: InstanceId) {
+        let clones = self.instances.values().filter(|i| i.is_clone).count();
+        let Some(index) = self.instances.get_index_of(&original) else {
+            return;
+        };
+        if clones >= MAX_CLONES || self.instances[index].target.is_stage() {
+            return;
+        }
+        let mut clone = self.instances[index].clone();
+        clone.is_clone = true;
+        clone.bubble = None;
+        let id = self.new_id();
+        // Clones go right behind their original
+        self.instances.shift_insert(index, id, clone);
+        self.start_hats(code, "control_start_as_clone", Some(id), |_| true);
+    }
+
+    fn stop_all(&mut self) {
+        for thread in &mut self.threads {
+            thread.stack.clear();
+            thread.restart = false;
+        }
+        self.instances.retain(|_, i| !i.is_clone);
+        for instance in self.instances.values_mut() {
+            instance.bubble = None;
+        }
+    }
+
+    /// Where the menu option `_random_`, `_mouse_` or a sprite name is.
+    fn position_of(&mut self, ti: usize, option: &str) -> Option<(f64, f64)> {
+        match option {
+            "_random_" => Some((
+                (STAGE_WIDTH * (self.random() - 0.5)).round(),
+                (STAGE_HEIGHT * (self.random() - 0.5)).round(),
+            )),
+            "_mouse_" => Some((0.0, 0.0)),
+            name => {
+                let id = self.find_sprite(name)?;
+                if id == self.threads[ti].instance {
+                    return None;
+                }
+                match &self.instances[&id].target {
+                    SpriteOrStage::Sprite(s) => Some((s.x.to_float(), s.y.to_float())),
+                    SpriteOrStage::Stage(_) => None,
+                }
+            }
+        }
+    }
+
+    fn property_of(&self, object: &str, property: &str) -> ValueWithBool {
+        let instance = match object {
+            "_stage_" => self.instances.get(&self.stage),
+            name => self
+                .find_sprite(name)
+                .and_then(|id| self.instances.get(&id)),
+        };
+        let Some(instance) = instance else {
+            return number(0.0);
+        };
+        let target = instance.target.target();
+        let costume_name = || {
+            target
+                .costumes
+                .get(target.current_costume as usize)
+                .map(|c| ValueWithBool::Text(c.asset.name.clone()))
+                .unwrap_or_default()
+        };
+        match (&instance.target, property) {
+            (SpriteOrStage::Sprite(s), "x position") => number(limit_precision(s.x.to_float())),
+            (SpriteOrStage::Sprite(s), "y position") => number(limit_precision(s.y.to_float())),
+            (SpriteOrStage::Sprite(s), "direction") => number(s.direction.to_float()),
+            (SpriteOrStage::Sprite(s), "size") => number(js_round(s.size.to_float())),
+            (SpriteOrStage::Sprite(_), "costume #") | (SpriteOrStage::Stage(_), "backdrop #") => {
+                number((target.current_costume + 1) as f64)
+            }
+            (SpriteOrStage::Sprite(_), "costume name")
+            | (SpriteOrStage::Stage(_), "backdrop name") => costume_name(),
+            (_, "volume") => number(target.volume.to_float()),
+            (_, name) => target
+                .variables
+                .0
+                .values()
+                .find(|v| v.name == name)
+                .map(|v| v.value.clone())
+                .unwrap_or_else(|| number(0.0)),
+        }
+    }
+
+    fn find_sprite(&self, name: &str) -> Option<InstanceId> {
+        self.instances
+            .iter()
+            .find(|(_, i)| !i.is_clone && !i.target.is_stage() && i.target.target().name == name)
+            .map(|(id, _)| *id)
+    }
+
+    /// Instance and Id of the variable or list.
+    /// Looked up by Id then by name in the instance then the stage,
+    /// and made in the instance if it's nowhere, like Scratch does.
+    fn lookup(&mut self, ti: usize, kind: DataKind, id: &Uid, name: &str) -> (InstanceId, Uid) {
+        let own = self.threads[ti].instance;
+        for by_id in [true, false] {
+            for instance in [own, self.stage] {
+                let target = self.instances[&instance].target.target();
+                let found = match (kind, by_id) {
+                    (DataKind::List, true) => target.lists.0.contains_key(id).then(|| id.clone()),
+                    (DataKind::List, false) => target
+                        .lists
+                        .0
+                        .iter()
+                        .find(|(_, l)| l.name == name)
+                        .map(|(id, _)| id.clone()),
+                    (_, true) => target.variables.0.contains_key(id).then(|| id.clone()),
+                    (_, false) => target
+                        .variables
+                        .0
+                        .iter()
+                        .find(|(_, v)| v.name == name)
+                        .map(|(id, _)| id.clone()),
+                };
+                if let Some(found) = found {
+                    return (instance, found);
+                }
+            }
+        }
+
+        let target = self.instances[&own].target.target_mut();
+        let name = name.to_owned();
+        match kind {
+            DataKind::List => {
+                target.lists.0.insert(
+                    id.clone(),
+                    List {
+                        name,
+                        ..Default::default()
+                    },
+                );
+            }
+            _ => {
+                target.variables.0.insert(
+                    id.clone(),
+                    Variable {
+                        name,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        (own, id.clone())
+    }
+
+    fn variable_mut(&mut self, ti: usize, id: &Uid, name: &str) -> &mut ValueWithBool {
+        let (instance, id) = self.lookup(ti, DataKind::Variable, id, name);
+        let target = self.instances[&instance].target.target_mut();
+        &mut target.variables.0.get_mut(&id).unwrap().value
+    }
+
+    /// Items of the list in the `LIST` field of the block.
+    fn list_mut(&mut self, ti: usize, block: &BlockNormal) -> &mut Vec<ValueWithBool> {
+        let (id, name) = data_field(block, DataKind::List);
+        let (instance, id) = self.lookup(ti, DataKind::List, &id, &name);
+        let target = self.instances[&instance].target.target_mut();
+        &mut target.lists.0.get_mut(&id).unwrap().values
+    }
+
+    /// Zero based index into a list of the length, like Scratch's `Cast.toListIndex`.
+    fn list_index(&mut self, index: &ValueWithBool, len: usize, accept_all: bool) -> ListIndex {
+        if let ValueWithBool::Text(text) = index {
+            match text.as_str() {
+                "all" if accept_all => return ListIndex::All,
+                "all" => return ListIndex::Invalid,
+                "last" if len > 0 => return ListIndex::Index(len - 1),
+                "last" => return ListIndex::Invalid,
+                "random" | "any" if len > 0 => {
+                    return ListIndex::Index((self.random() * len as f64).floor() as usize)
+                }
+                "random" | "any" => return ListIndex::Invalid,
+                _ => {}
+            }
+        }
+        let index = index.to_number().floor();
+        if index < 1.0 || index > len as f64 {
+            ListIndex::Invalid
+        } else {
+            ListIndex::Index(index as usize - 1)
+        }
+    }
+
+    fn sprite_mut(&mut self, ti: usize) -> Option<&mut Sprite> {
+        let instance = self.threads[ti].instance;
+        match &mut self.instances.get_mut(&instance)?.target {
+            SpriteOrStage::Sprite(s) => Some(s),
+            SpriteOrStage::Stage(_) => None,
+        }
+    }
+
+    fn set_bubble(&mut self, ti: usize, message: Text) {
+        let instance = self.threads[ti].instance;
+        let instance = &mut self.instances[&instance];
+        if !instance.target.is_stage() {
+            instance.bubble = (!message.is_empty()).then_some(message);
+        }
+    }
+
+    fn state(&self, ti: usize) -> Option<&State> {
+        self.threads[ti].stack.last()?.state.as_ref()
+    }
+
+    fn set_state(&mut self, ti: usize, state: State) {
+        if let Some(frame) = self.threads[ti].stack.last_mut() {
+            frame.state = Some(state);
+        }
+    }
+
+    /// Seconds since the frame.
+    fn elapsed(&self, start: u64) -> f64 {
+        (self.frame - start) as f64 / FRAME_RATE as f64
+    }
+
+    fn new_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Random number from 0 to 1 excluded, xorshift64*.
+    fn random(&mut self) -> f64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let n = self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (n >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+enum ListIndex {
+    Index(usize),
+    All,
+    Invalid,
+}
+
+fn blocks(code: &Project, source: usize) -> &StringHashMap<Block> {
+    &code.targets[source].target().blocks
+}
+
+/// Move the frame to the block after its current one.
+fn advance(code: &Project, source: usize, frame: &mut Frame) {
+    let next = frame
+        .block
+        .as_ref()
+        .and_then(|id| match blocks(code, source).0.get(id) {
+            Some(Block::Normal(b)) => b.next.clone(),
+            _ => None,
+        });
+    frame.block = next;
+    frame.state = None;
+}
+
+/// Id of the first block in the input, such as a C mouth.
+fn substack(block: &BlockNormal, name: &str) -> Option<Uid> {
+    match block.inputs.0.get(name)?.inputs.first()? {
+        Some(UidOrValue::Uid(id)) => Some(id.clone()),
+        _ => None,
+    }
+}
+
+fn field_text(block: &BlockNormal, name: &str) -> Text {
+    match block.fields.0.get(name) {
+        Some(BlockField::WithId { value, .. } | BlockField::NoId { value }) => {
+            value.to_scratch_string()
+        }
+        None => Text::new(),
+    }
+}
+
+/// Id and name of the variable or list in the field of the block.
+fn data_field(block: &BlockNormal, kind: DataKind) -> (Uid, Name) {
+    let name = field_text(block, kind.field_name());
+    let id = match block.fields.0.get(kind.field_name()) {
+        Some(BlockField::WithId { id: Some(id), .. }) => id.clone(),
+        _ => name.clone(),
+    };
+    (id, name)
+}
+
+fn is_reporter(opcode: &str) -> bool {
+    use crate::opcode::{KnownOpcode, Shape};
+
+    matches!(
+        KnownOpcode::from(opcode).shape(),
+        Some(Shape::Reporter | Shape::Boolean)
+    )
+}
+
+fn list_contents(list: &[ValueWithBool]) -> ValueWithBool {
+    let single_letters = list
+        .iter()
+        .all(|v| matches!(v, ValueWithBool::Text(t) if t.chars().count() == 1));
+    let items: Vec<Text> = list.iter().map(ValueWithBool::to_scratch_string).collect();
+    ValueWithBool::Text(match single_letters {
+        true => items.join(""),
+        false => items.join(" "),
+    })
+}
+
+fn set_xy(sprite: &mut Sprite, x: f64, y: f64) {
+    sprite.x = Number::normalize(limit_precision(x));
+    sprite.y = Number::normalize(limit_precision(y));
+}
+
+fn set_direction(sprite: &mut Sprite, direction: f64) {
+    if direction.is_finite() {
+        sprite.direction = Number::normalize(wrap_clamp(direction, -179.0, 180.0));
+    }
+}
+
+/// Switch to the costume with the name or number, or the next or previous one,
+/// like Scratch's `_setCostume` and `_setBackdrop`. `kind` is `costume` or `backdrop`.
+fn set_costume(target: &mut crate::target::Target, requested: &ValueWithBool, kind: &str) {
+    let current = target.current_costume as f64;
+    match requested {
+        ValueWithBool::Number(n) => set_costume_index(target, n.to_float() - 1.0),
+        requested => {
+            let name = requested.to_scratch_string();
+            if let Some(i) = target.costumes.iter().position(|c| c.asset.name == name) {
+                set_costume_index(target, i as f64);
+            } else if name == format!("next {kind}") {
+                set_costume_index(target, current + 1.0);
+            } else if name == format!("previous {kind}") {
+                set_costume_index(target, current - 1.0);
+            } else if !requested.is_whitespace() {
+                let n = js_number(&name);
+                if !n.is_nan() {
+                    set_costume_index(target, n - 1.0);
+                }
+            }
+        }
+    }
+}
+
+fn set_costume_index(target: &mut crate::target::Target, index: f64) {
+    let index = js_round(index);
+    let index = if index.is_finite() { index } else { 0.0 };
+    let last = target.costumes.len().saturating_sub(1) as f64;
+    target.current_costume = wrap_clamp(index, 0.0, last) as Int;
+}
+
+fn wrap_clamp(n: f64, min: f64, max: f64) -> f64 {
+    let range = max - min + 1.0;
+    n - ((n - min) / range).floor() * range
+}
+
+fn limit_precision(n: f64) -> f64 {
+    let rounded = n.round();
+    if (n - rounded).abs() < 1e-9 {
+        rounded
+    } else {
+        n
+    }
+}
+
+fn number(n: f64) -> ValueWithBool {
+    ValueWithBool::Number(Number::normalize(n))
+}
@@ -0,0 +1,268 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    archive::{AssetKind, Error, ImportWarning, Sb3Archive, Sprite3Archive},
+    block::{Block, DataKind},
+    builder::{ProjectBuilder, EMPTY_SVG},
+    target::SpriteOrStage,
+    unused::PruneOptions,
+};
+use std::io::Write;
+
+#[test]
+fn sb3_round_trip() {
+    let mut archive = Sb3Archive::new(simple_project());
+    archive.files.insert(
+        "83a9787d4cb6f3b7632b4ddfebf74367.wav".to_owned(),
+        b"RIFF".to_vec(),
+    );
+
+    let bytes = archive.to_bytes().unwrap();
+    let archive_after = Sb3Archive::from_bytes(&bytes).unwrap();
+
+    assert_eq!(archive, archive_after);
+}
+
+#[test]
+fn sb3_missing_and_unreferenced() {
+    let mut archive = Sb3Archive::new(simple_project());
+    archive.files.insert(
+        "83a9787d4cb6f3b7632b4ddfebf74367.wav".to_owned(),
+        b"RIFF".to_vec(),
+    );
+    archive
+        .files
+        .insert("nobody_uses_me.png".to_owned(), vec![0]);
+
+    assert_eq!(archive.unreferenced_files(), vec!["nobody_uses_me.png"]);
+
+    let missing = archive.missing_assets();
+    assert!(missing
+        .iter()
+        .all(|r| r.file_name != "83a9787d4cb6f3b7632b4ddfebf74367.wav"));
+    assert!(missing
+        .iter()
+        .any(|r| r.kind == AssetKind::Costume && r.asset_name == "costume1"));
+    // The empty backdrop is added by `new`
+    assert!(missing.iter().all(|r| r.asset_name != "backdrop1"));
+    // A costume without md5ext falls back to `<assetId>.<dataFormat>`
+    assert!(missing
+        .iter()
+        .any(|r| r.asset_name == "Apple" && r.file_name.ends_with(".svg")));
+}
+
+#[test]
+fn sb3_built_project() {
+    let mut project = ProjectBuilder::new();
+    project.sprite("Cat");
+    let archive = Sb3Archive::new(project.build());
+    assert!(archive.missing_assets().is_empty());
+    assert_eq!(archive.files.len(), 1);
+    let costume = &archive.project.targets[1].target().costumes[0];
+    assert_eq!(archive.asset_data(&costume.asset), Some(EMPTY_SVG));
+    assert_eq!(
+        format!("{:x}", md5::compute(EMPTY_SVG)),
+        costume.asset.asset_id
+    );
+}
+
+#[test]
+fn sb3_missing_project_json() {
+    let mut archive = Sb3Archive::new(simple_project());
+    archive.files.insert("a.txt".to_owned(), vec![]);
+    let bytes = archive.to_bytes().unwrap();
+
+    // Rewrite the zip without project.json
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+    let mut out = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    out.raw_copy_file(zip.by_name("a.txt").unwrap()).unwrap();
+    let bytes = out.finish().unwrap().into_inner();
+
+    assert!(Sb3Archive::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn sb3_duplicate_files() {
+    let mut archive = Sb3Archive::new(simple_project());
+    archive.files.insert("project.json".to_owned(), vec![]);
+    assert!(matches!(
+        archive.to_bytes(),
+        Err(Error::DuplicateFile(name)) if name == "project.json"
+    ));
+
+    // Files are keyed by their name without the folder
+    let mut out = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default();
+    for name in ["project.json", "a/apple.png", "b/apple.png"] {
+        out.start_file(name, options).unwrap();
+        out.write_all(b"{}").unwrap();
+    }
+    let bytes = out.finish().unwrap().into_inner();
+    let error = Sb3Archive::from_bytes(&bytes).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "archive contains `apple.png` more than once"
+    );
+}
+
+fn sprite_with_broadcast() -> Sprite3Archive {
+    let sprite = serde_json::json!({
+        "isStage": false,
+        "name": "Sender",
+        "variables": { "localvarid": ["score", 0] },
+        "lists": {},
+        "broadcasts": {},
+        "blocks": {
+            "a": {
+                "opcode": "event_broadcast",
+                "next": "b",
+                "parent": null,
+                "inputs": { "BROADCAST_INPUT": [1, [11, "go", "broadcastid"]] },
+                "fields": {},
+                "shadow": false,
+                "topLevel": true,
+                "x": 0,
+                "y": 0
+            },
+            "b": {
+                "opcode": "data_setvariableto",
+                "next": null,
+                "parent": "a",
+                "inputs": { "VALUE": [1, [10, "0"]] },
+                "fields": { "VARIABLE": ["lives", "unknownvarid"] },
+                "shadow": false,
+                "topLevel": false
+            }
+        },
+        "comments": {},
+        "currentCostume": 0,
+        "costumes": [],
+        "sounds": [],
+        "volume": 100,
+        "layerOrder": 1,
+        "visible": true,
+        "x": 0,
+        "y": 0,
+        "size": 100,
+        "direction": 90,
+        "draggable": false,
+        "rotationStyle": "all around"
+    });
+    Sprite3Archive::new(serde_json::from_value(sprite).unwrap())
+}
+
+#[test]
+fn sprite3_round_trip() {
+    let mut archive = Sb3Archive::new(simple_project());
+    archive.files.insert(
+        "83c36d806dc92327b9e7049a565c6bff.wav".to_owned(),
+        b"RIFF".to_vec(),
+    );
+    archive
+        .files
+        .insert("nobody_uses_me.png".to_owned(), vec![0]);
+
+    let sprite3 = archive.export_sprite("Sprite1").unwrap();
+    assert_eq!(
+        sprite3.files.keys().collect::<Vec<_>>(),
+        vec!["83c36d806dc92327b9e7049a565c6bff.wav"]
+    );
+    assert_eq!(sprite3.missing_assets().len(), 2);
+
+    let sprite3_after = Sprite3Archive::from_bytes(&sprite3.to_bytes().unwrap()).unwrap();
+    assert_eq!(sprite3, sprite3_after);
+    assert!(archive.export_sprite("Stage").is_none());
+}
+
+#[test]
+fn sprite3_import_remaps_conflicts() {
+    let mut archive = Sb3Archive::new(simple_project());
+    let sprite3 = archive.export_sprite("Sprite1").unwrap();
+    let import = archive.import_sprite(sprite3);
+
+    assert_eq!(import.name, "Sprite2");
+    assert_eq!(import.remapped_ids.len(), 6);
+
+    let SpriteOrStage::Sprite(imported) = archive.project.targets.last().unwrap() else {
+        panic!("imported target should be a sprite")
+    };
+    assert_eq!(imported.target.name, "Sprite2");
+    for (id, block) in &imported.target.blocks.0 {
+        assert!(!import.remapped_ids.contains_key(id));
+        let Block::Normal(block) = block else {
+            continue;
+        };
+        for linked in [&block.next, &block.parent].into_iter().flatten() {
+            assert!(imported.target.blocks.0.contains_key(linked));
+        }
+    }
+    // The global variable is still referenced by its Id
+    assert!(imported
+        .target
+        .blocks
+        .0
+        .values()
+        .any(|b| b
+            .data_references()
+            .iter()
+            .any(|(kind, name, id)| *kind == DataKind::Variable
+                && name == "score"
+                && *id == "31*#$v.#k`Yll6(`.hFu")));
+}
+
+#[test]
+fn sprite3_import_fixes_up_data() {
+    let mut archive = Sb3Archive::new(simple_project());
+    let import = archive.import_sprite(sprite_with_broadcast());
+
+    assert_eq!(import.name, "Sender");
+    assert!(import
+        .warnings
+        .contains(&ImportWarning::UndeclaredBroadcast {
+            name: "go".to_owned(),
+            id: "broadcastid".to_owned()
+        }));
+    assert!(import.warnings.contains(&ImportWarning::RenamedLocal {
+        kind: DataKind::Variable,
+        from: "score".to_owned(),
+        to: "Sender: score".to_owned()
+    }));
+    assert!(import.warnings.contains(&ImportWarning::DeclaredLocal {
+        kind: DataKind::Variable,
+        name: "lives".to_owned(),
+        id: "unknownvarid".to_owned()
+    }));
+
+    let target = archive.project.targets.last().unwrap().target();
+    assert_eq!(target.variables.0["localvarid"].name, "Sender: score");
+    assert_eq!(target.variables.0["unknownvarid"].name, "lives");
+}
+
+#[test]
+fn sb3_prune() {
+    let mut archive = Sb3Archive::new(simple_project());
+    for reference in archive.asset_references() {
+        archive.files.insert(reference.file_name, vec![]);
+    }
+    archive.files.insert("a.txt".to_owned(), vec![]);
+
+    let files = archive.files.clone();
+    archive.prune(PruneOptions::default());
+    assert_eq!(archive.files, files);
+
+    archive.prune(PruneOptions::all());
+    assert!(archive.missing_assets().is_empty());
+    assert_eq!(archive.unreferenced_files(), ["a.txt"]);
+    let files: Vec<&str> = archive.files.keys().map(String::as_str).collect();
+    // The costume each target wears, and the file that was never referenced
+    assert_eq!(
+        files,
+        [
+            "3826a4091a33e4d26f87a2fac7cf796b.svg",
+            "a.txt",
+            "bcf454acf82e4504149f7ffe07081dbc.svg",
+            "cd21514d0531fdffb22204e0ec5ed84a.svg",
+        ]
+    );
+}
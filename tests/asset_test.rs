@@ -0,0 +1,82 @@
+use sb_sbity::{
+    archive::AssetKind,
+    asset::{Asset, AssetError, Costume, DataFormat, Sound},
+};
+
+const SVG: &[u8] = br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="2" height="2"></svg>"#;
+
+#[test]
+fn asset_from_bytes() {
+    let costume = Costume::from_bytes("dot", SVG, None).unwrap();
+    let id = &costume.asset.asset_id;
+    assert_eq!(id.len(), 32);
+    assert_eq!(costume.asset.name, "dot");
+    assert_eq!(costume.asset.data_format, "svg");
+    assert_eq!(costume.asset.md5ext, Some(format!("{id}.svg")));
+
+    let asset = Asset::from_bytes("abc", b"ID3abc", None).unwrap();
+    assert_eq!(asset.data_format, "mp3");
+    // The format is given for files it cannot be detected from
+    let asset = Asset::from_bytes("abc", b"abc", Some(DataFormat::Wav)).unwrap();
+    assert_eq!(asset.asset_id, "900150983cd24fb0d6963f7d28e17f72");
+    assert_eq!(
+        asset.md5ext.as_deref(),
+        Some("900150983cd24fb0d6963f7d28e17f72.wav")
+    );
+
+    let detect = |bytes: &[u8]| DataFormat::detect(bytes);
+    assert_eq!(detect(b"\x89PNG\r\n\x1a\n...."), Some(DataFormat::Png));
+    assert_eq!(detect(&[0xff, 0xd8, 0xff, 0xe0]), Some(DataFormat::Jpg));
+    assert_eq!(detect(b"GIF89a"), Some(DataFormat::Gif));
+    assert_eq!(detect(b"RIFF\0\0\0\0WAVEfmt "), Some(DataFormat::Wav));
+    assert_eq!(detect(&[0xff, 0xfb, 0x90, 0x00]), Some(DataFormat::Mp3));
+    assert_eq!(detect(b"\xef\xbb\xbf  <svg></svg>"), Some(DataFormat::Svg));
+    assert_eq!(detect(b"abc"), None);
+}
+
+#[test]
+fn asset_from_bytes_errors() {
+    assert_eq!(
+        Costume::from_bytes("abc", b"abc", None),
+        Err(AssetError::UnknownFormat)
+    );
+    assert_eq!(
+        Costume::from_bytes("dot", SVG, Some(DataFormat::Png)),
+        Err(AssetError::FormatMismatch {
+            expected: DataFormat::Png,
+            found: DataFormat::Svg
+        })
+    );
+    let error = Sound::from_bytes("dot", SVG, None).unwrap_err();
+    assert_eq!(
+        error,
+        AssetError::WrongKind {
+            format: DataFormat::Svg,
+            kind: AssetKind::Sound
+        }
+    );
+    assert_eq!(error.to_string(), "a svg file cannot be a sound");
+}
+
+#[test]
+fn asset_verify() {
+    let mut costume = Costume::from_bytes("dot", SVG, None).unwrap();
+    assert_eq!(costume.asset.verify(SVG), Ok(()));
+    assert_eq!(
+        costume.asset.verify(b"abc"),
+        Err(AssetError::HashMismatch {
+            expected: costume.asset.asset_id.clone(),
+            found: "900150983cd24fb0d6963f7d28e17f72".to_owned()
+        })
+    );
+
+    costume.asset.md5ext = Some(format!("{}.png", costume.asset.asset_id));
+    assert!(matches!(
+        costume.asset.verify(SVG),
+        Err(AssetError::Md5extMismatch { .. })
+    ));
+    // Without md5ext the file is named after the Id anyway
+    costume.asset.md5ext = None;
+    assert_eq!(costume.asset.verify(SVG), Ok(()));
+}
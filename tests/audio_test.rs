@@ -0,0 +1,155 @@
+use sb_sbity::{
+    archive::Sb3Archive,
+    asset::{AssetError, Sound},
+    audio::{AudioError, SoundInfo},
+    project::Project,
+};
+
+fn chunk(id: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_vec();
+    chunk.extend((data.len() as u32).to_le_bytes());
+    chunk.extend(data);
+    if data.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let body: Vec<u8> = chunks.concat();
+    let mut wav = b"RIFF".to_vec();
+    wav.extend((body.len() as u32 + 4).to_le_bytes());
+    wav.extend(b"WAVE");
+    wav.extend(body);
+    wav
+}
+
+fn fmt(format_tag: u16, channels: u16, rate: u32, block_align: u16, extra: &[u8]) -> Vec<u8> {
+    let mut fmt = vec![];
+    fmt.extend(format_tag.to_le_bytes());
+    fmt.extend(channels.to_le_bytes());
+    fmt.extend(rate.to_le_bytes());
+    fmt.extend((rate * block_align as u32).to_le_bytes());
+    fmt.extend(block_align.to_le_bytes());
+    fmt.extend(16u16.to_le_bytes());
+    fmt.extend(extra);
+    chunk(b"fmt ", &fmt)
+}
+
+/// MPEG 1 layer III frames at 128 kbps and 44100 Hz, 417 bytes each.
+fn mp3(frames: usize, xing: bool) -> Vec<u8> {
+    let mut mp3 = b"ID3\x04\0\0\0\0\0\x05tag..".to_vec();
+    for i in 0..frames {
+        let mut frame = vec![0; 417];
+        frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+        if xing && i == 0 {
+            frame[36..40].copy_from_slice(b"Xing");
+        }
+        mp3.extend(frame);
+    }
+    mp3.extend(b"TAG");
+    mp3
+}
+
+#[test]
+fn wav_info() {
+    // 16 bits stereo PCM, 4 bytes per sample
+    let pcm = wav(&[
+        fmt(1, 2, 22050, 4, &[]),
+        chunk(b"LIST", b"odd"),
+        chunk(b"data", &[0; 400]),
+    ]);
+    assert_eq!(
+        SoundInfo::from_bytes(&pcm),
+        Ok(SoundInfo {
+            rate: 22050,
+            sample_count: 100
+        })
+    );
+
+    // Mono IMA ADPCM of 256 bytes blocks holding 505 samples
+    let adpcm_fmt = fmt(0x11, 1, 11025, 256, &[2, 0, 0xf9, 0x01]);
+    let blocks = wav(&[adpcm_fmt.clone(), chunk(b"data", &[0; 256 * 3 + 14])]);
+    assert_eq!(
+        SoundInfo::from_wav(&blocks).map(|i| i.sample_count),
+        Ok(505 * 3 + 1 + 10 * 2)
+    );
+    let fact = wav(&[
+        adpcm_fmt,
+        chunk(b"fact", &1500u32.to_le_bytes()),
+        chunk(b"data", &[0; 256 * 3]),
+    ]);
+    assert_eq!(SoundInfo::from_wav(&fact).map(|i| i.sample_count), Ok(1500));
+
+    let sound = Sound::from_bytes("beep", &pcm, None).unwrap();
+    assert_eq!((sound.rate, sound.sample_count), (22050, 100));
+
+    assert_eq!(
+        SoundInfo::from_bytes(&wav(&[fmt(0x55, 1, 8000, 1, &[]), chunk(b"data", &[])])),
+        Err(AudioError::UnsupportedEncoding { format_tag: 0x55 })
+    );
+    assert_eq!(
+        Sound::from_bytes("beep", &wav(&[fmt(1, 1, 8000, 1, &[])]), None),
+        Err(AssetError::Audio(AudioError::MissingChunk {
+            chunk: "data"
+        }))
+    );
+}
+
+#[test]
+fn mp3_info() {
+    let info = |mp3: &[u8]| SoundInfo::from_mp3(mp3).unwrap();
+    assert_eq!(
+        info(&mp3(3, false)),
+        SoundInfo {
+            rate: 44100,
+            sample_count: 3 * 1152
+        }
+    );
+    // The Xing frame holds no audio
+    assert_eq!(info(&mp3(3, true)).sample_count, 2 * 1152);
+    assert_eq!(
+        SoundInfo::from_mp3(b"ID3\x04\0\0\0\0\0\0"),
+        Err(AudioError::NoFrames)
+    );
+}
+
+#[test]
+fn archive_sound_mismatches() {
+    let project: Project =
+        serde_json::from_str(include_str!("serde_test_mod/test_case/simple_project.json")).unwrap();
+    let mut archive = Sb3Archive::new(project);
+    let meow = archive.project.targets[1].target().sounds[0].clone();
+    let declared = meow.info();
+    let right = wav(&[
+        fmt(1, 1, declared.rate as u32, 2, &[]),
+        chunk(b"data", &vec![0; declared.sample_count as usize * 2]),
+    ]);
+    archive.insert_asset(&meow.asset, right);
+    assert_eq!(archive.sound_mismatches(), []);
+
+    let wrong = wav(&[fmt(1, 1, 44100, 2, &[]), chunk(b"data", &[0; 20])]);
+    archive.insert_asset(&meow.asset, wrong);
+    let mismatches = archive.sound_mismatches();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(
+        mismatches[0].actual,
+        Ok(SoundInfo {
+            rate: 44100,
+            sample_count: 10
+        })
+    );
+    assert_eq!(
+        mismatches[0].to_string(),
+        format!(
+            "sound `Meow` of `Sprite1`: says {declared} but file `{}` has 10 samples at 44100 Hz",
+            meow.asset.file_name()
+        )
+    );
+
+    archive.insert_asset(&meow.asset, b"RIFF".to_vec());
+    assert_eq!(
+        archive.sound_mismatches()[0].actual,
+        Err(AudioError::UnknownFormat)
+    );
+}
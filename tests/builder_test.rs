@@ -0,0 +1,196 @@
+use sb_sbity::{
+    block::{Block, BlockField, BlockInputValue, BlockMutationEnum, ShadowInputType, UidOrValue},
+    builder::{control, data, events, looks, motion, operator, sensing, ProjectBuilder},
+    project::Project,
+    script::InputRef,
+    target::SpriteOrStage,
+};
+
+#[test]
+fn build_script() {
+    let mut project = ProjectBuilder::new();
+    project
+        .sprite("Cat")
+        .when_flag_clicked()
+        .then(motion::goto_xy(0, 10))
+        .then(control::forever(|s| {
+            s.then(motion::move_steps(10))
+                .then(control::if_then(sensing::touching("_edge_"), |s| {
+                    s.then(motion::turn_right(operator::random(90, 270)))
+                }))
+        }));
+    let project = project.build();
+    assert_eq!(project.validate(), vec![]);
+
+    let cat = project.targets[1].target();
+    assert_eq!(cat.name, "Cat");
+    let scripts = cat.scripts();
+    assert_eq!(scripts.len(), 1);
+    let opcodes: Vec<_> = scripts[0]
+        .walk()
+        .unwrap()
+        .iter()
+        .map(|b| b.opcode().unwrap())
+        .collect();
+    assert_eq!(
+        opcodes,
+        [
+            "event_whenflagclicked",
+            "motion_gotoxy",
+            "control_forever",
+            "motion_movesteps",
+            "control_if",
+            "sensing_touchingobject",
+            "sensing_touchingobjectmenu",
+            "motion_turnright",
+            "operator_random",
+        ]
+    );
+
+    let goto = scripts[0].stack().nth(1).unwrap().unwrap();
+    let x = &goto.normal().unwrap().inputs.0["X"];
+    assert_eq!(x.shadow, ShadowInputType::Shadow);
+    let Some(InputRef::Value(BlockInputValue::Number { value })) = goto.input("Y").unwrap() else {
+        panic!("Y should be a number")
+    };
+    assert_eq!(*value, 10.into());
+
+    let walk = scripts[0].walk().unwrap();
+    let menu = walk[6].normal().unwrap();
+    assert!(menu.shadow);
+    assert!(!menu.top_level);
+    let turn = walk[7].normal().unwrap();
+    assert_eq!(
+        turn.inputs.0["DEGREES"].shadow,
+        ShadowInputType::ShadowObscured
+    );
+}
+
+fn sorted(mut names: Vec<&str>) -> Vec<&str> {
+    names.sort();
+    names
+}
+
+#[test]
+fn build_declares_data() {
+    let mut project = ProjectBuilder::new();
+    project.stage().variable("score", 0);
+    let cat = project.sprite("Cat");
+    cat.variable("speed", 5).list("path", ["a", "b"]);
+    cat.when_flag_clicked()
+        .then(data::set_variable_to("score", 0))
+        .then(data::change_variable_by("speed", data::variable("score")))
+        .then(data::add_to_list(data::variable("lives"), "path"))
+        .then(events::broadcast("start"));
+    project
+        .stage()
+        .when_broadcast_received("start")
+        .then(looks::say(data::variable("score")));
+    let project = project.build();
+    assert_eq!(project.validate(), vec![]);
+
+    let stage = project.targets[0].target();
+    let cat = project.targets[1].target();
+    assert_eq!(
+        sorted(stage.variables.values().map(|v| v.name.as_str()).collect()),
+        ["lives", "score"]
+    );
+    assert_eq!(
+        sorted(cat.variables.values().map(|v| v.name.as_str()).collect()),
+        ["speed"]
+    );
+    assert_eq!(cat.lists.len(), 1);
+    assert!(stage.lists.is_empty());
+    assert_eq!(stage.broadcasts.len(), 1);
+
+    let score_id = stage
+        .variables
+        .iter()
+        .find(|(_, v)| v.name == "score")
+        .unwrap()
+        .0;
+    let set = cat
+        .blocks
+        .values()
+        .find_map(|b| match b {
+            Block::Normal(b) if b.opcode == "data_setvariableto" => Some(b),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        set.fields.0["VARIABLE"],
+        BlockField::WithId {
+            value: "score".into(),
+            id: Some(score_id.clone())
+        }
+    );
+
+    let broadcast_id = stage.broadcasts.keys().next().unwrap();
+    let hat = stage.scripts()[0].top();
+    assert_eq!(
+        hat.normal().unwrap().fields.0["BROADCAST_OPTION"],
+        BlockField::WithId {
+            value: "start".into(),
+            id: Some(broadcast_id.clone())
+        }
+    );
+}
+
+#[test]
+fn build_round_trip() {
+    let mut project = ProjectBuilder::new();
+    let cat = project.sprite("Cat");
+    cat.position(10, -20).size(50);
+    cat.when_key_pressed("space")
+        .at(100, 100)
+        .then(looks::say(operator::join("x: ", motion::x_position())))
+        .then(control::repeat(3, |s| s.then(looks::next_costume())))
+        .then(control::stop("all"));
+    project.sprite("Dog").when_this_sprite_clicked();
+    let project = project.build();
+    assert_eq!(project.validate(), vec![]);
+    assert_eq!(project.targets.len(), 3);
+    let SpriteOrStage::Sprite(cat) = &project.targets[1] else {
+        panic!("Cat should be a sprite")
+    };
+    assert_eq!(cat.x, 10.into());
+    assert_eq!(cat.target.costumes.len(), 1);
+
+    let top = cat.target.scripts()[0].top();
+    assert_eq!(top.normal().unwrap().x, Some(100.into()));
+    let say = top.next().unwrap().unwrap();
+    let message = &say.normal().unwrap().inputs.0["MESSAGE"];
+    assert_eq!(message.shadow, ShadowInputType::ShadowObscured);
+    assert!(matches!(message.inputs[0], Some(UidOrValue::Uid(_))));
+
+    let json = serde_json::to_string(&project).unwrap();
+    let read: Project = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, project);
+}
+
+#[test]
+fn build_stop_hasnext() {
+    let mut project = ProjectBuilder::new();
+    let cat = project.sprite("Cat");
+    for option in ["all", "other scripts in sprite", "other scripts in stage"] {
+        cat.when_flag_clicked().then(control::stop(option));
+    }
+    let project = project.build();
+    let SpriteOrStage::Sprite(cat) = &project.targets[1] else {
+        panic!("Cat should be a sprite")
+    };
+    let hasnext: Vec<bool> = cat
+        .target
+        .scripts()
+        .iter()
+        .map(|script| {
+            let stop = script.top().next().unwrap().unwrap();
+            let mutation = stop.normal().unwrap().mutation.as_ref().unwrap();
+            match mutation.mutation_enum {
+                BlockMutationEnum::ControlStop { hasnext } => hasnext,
+                _ => panic!("stop should have a ControlStop mutation"),
+            }
+        })
+        .collect();
+    assert_eq!(hasnext, vec![false, true, true]);
+}
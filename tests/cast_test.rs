@@ -0,0 +1,156 @@
+//! Conformance tables, expected results are what scratch-vm gives.
+
+use sb_sbity::value::{Number, Value, ValueWithBool};
+use std::cmp::Ordering;
+
+fn int(n: i64) -> ValueWithBool {
+    ValueWithBool::Number(Number::Int(n))
+}
+
+fn float(n: f64) -> ValueWithBool {
+    ValueWithBool::Number(Number::Float(n))
+}
+
+fn text(t: &str) -> ValueWithBool {
+    ValueWithBool::Text(t.to_owned())
+}
+
+fn boolean(b: bool) -> ValueWithBool {
+    ValueWithBool::Bool(b)
+}
+
+#[test]
+fn cast_conformance() {
+    // value, to_number, to_bool, to_scratch_string
+    let table = [
+        (text(""), 0.0, false, ""),
+        (text("  "), 0.0, true, "  "),
+        (text("0"), 0.0, false, "0"),
+        (text("FALSE"), 0.0, false, "FALSE"),
+        (text("abc"), 0.0, true, "abc"),
+        (text("12"), 12.0, true, "12"),
+        (text(" 12 "), 12.0, true, " 12 "),
+        (text("1.5e3"), 1500.0, true, "1.5e3"),
+        (text(".5"), 0.5, true, ".5"),
+        (text("5."), 5.0, true, "5."),
+        (text("-0x10"), 0.0, true, "-0x10"),
+        (text("0x1F"), 31.0, true, "0x1F"),
+        (text("0b101"), 5.0, true, "0b101"),
+        (text("Infinity"), f64::INFINITY, true, "Infinity"),
+        (text("-Infinity"), f64::NEG_INFINITY, true, "-Infinity"),
+        (text("inf"), 0.0, true, "inf"),
+        (text("NaN"), 0.0, true, "NaN"),
+        (text("1_000"), 0.0, true, "1_000"),
+        (text("--1"), 0.0, true, "--1"),
+        (int(0), 0.0, false, "0"),
+        (int(-7), -7.0, true, "-7"),
+        (float(0.1 + 0.2), 0.1 + 0.2, true, "0.30000000000000004"),
+        (float(1e21), 1e21, true, "1e+21"),
+        (float(1.5e-7), 1.5e-7, true, "1.5e-7"),
+        (float(0.000001), 0.000001, true, "0.000001"),
+        (
+            float(123456789012345680000.0),
+            123456789012345680000.0,
+            true,
+            "123456789012345680000",
+        ),
+        (float(f64::NAN), 0.0, false, "NaN"),
+        (float(-f64::INFINITY), f64::NEG_INFINITY, true, "-Infinity"),
+        (boolean(true), 1.0, true, "true"),
+        (boolean(false), 0.0, false, "false"),
+    ];
+    for (value, number, boolean, string) in table {
+        assert_eq!(value.to_number(), number, "to_number of {value:?}");
+        assert_eq!(value.to_bool(), boolean, "to_bool of {value:?}");
+        assert_eq!(
+            value.to_scratch_string(),
+            string,
+            "to_scratch_string of {value:?}"
+        );
+    }
+
+    assert!(Value::Text("1".to_owned()).is_int());
+    assert!(!Value::Text("1.0".to_owned()).is_int());
+    assert!(!Value::Number(Number::Float(1.5)).is_int());
+    assert_eq!(Value::Text("0x10".to_owned()).to_number(), 16.0);
+}
+
+#[test]
+fn cast_compare_conformance() {
+    use Ordering::*;
+
+    let table = [
+        (int(1), text("1.0"), Equal),
+        (text("abc"), text("ABC"), Equal),
+        (text("10"), text("9"), Greater),
+        (text("a"), text("b"), Less),
+        (text("10"), text("9a"), Less),
+        (text(""), int(0), Less),
+        (text(" "), int(0), Less),
+        (int(0), text(""), Greater),
+        (text("Infinity"), float(f64::INFINITY), Equal),
+        (text("-Infinity"), text("Infinity"), Less),
+        (boolean(true), text("true"), Equal),
+        (boolean(true), int(1), Equal),
+        (text("0x10"), int(16), Equal),
+    ];
+    for (a, b, order) in table {
+        assert_eq!(a.scratch_compare(&b), order, "{a:?} compared to {b:?}");
+        assert_eq!(a.scratch_eq(&b), order == Equal, "{a:?} = {b:?}");
+    }
+}
+
+#[test]
+fn cast_operator_conformance() {
+    assert_eq!(
+        text("0.1").scratch_add(&text("0.2")),
+        Number::Float(0.1 + 0.2)
+    );
+    assert_eq!(text("2").scratch_mul(&int(3)), Number::Int(6));
+    assert_eq!(int(7).scratch_sub(&text("")), Number::Int(7));
+    assert_eq!(int(1).scratch_div(&int(0)), Number::Float(f64::INFINITY));
+    assert!(int(0).scratch_div(&int(0)).to_float().is_nan());
+    assert_eq!(int(-7).scratch_mod(&int(3)), Number::Int(2));
+    assert_eq!(int(7).scratch_mod(&int(-3)), Number::Int(-2));
+    assert_eq!(float(2.5).scratch_round(), Number::Int(3));
+    assert_eq!(float(-2.5).scratch_round(), Number::Int(-2));
+
+    let mathop = [
+        ("abs", -3.0, 3.0),
+        ("floor", 2.7, 2.0),
+        ("ceiling", 2.2, 3.0),
+        ("sqrt", 16.0, 4.0),
+        ("sin", 180.0, 0.0),
+        ("cos", 90.0, 0.0),
+        ("tan", 90.0, f64::INFINITY),
+        ("tan", -90.0, f64::NEG_INFINITY),
+        ("tan", 45.0, 1.0),
+        ("asin", 1.0, 90.0),
+        ("atan", 1.0, 45.0),
+        ("ln", 1.0, 0.0),
+        ("log", 100.0, 2.0),
+        ("log", 1000.0, 2.9999999999999996),
+        ("e ^", 0.0, 1.0),
+        ("10 ^", 3.0, 1000.0),
+        ("unknown", 3.0, 0.0),
+    ];
+    for (operator, value, expected) in mathop {
+        assert_eq!(
+            float(value).mathop(operator).to_float(),
+            expected,
+            "{operator} of {value}"
+        );
+    }
+
+    assert_eq!(text("ab").join(&boolean(true)), "abtrue");
+    assert_eq!(float(0.5).join(&int(1)), "0.51");
+    // 😀 is two UTF-16 code units
+    assert_eq!(text("a😀b").length(), 4);
+    assert_eq!(text("a😀b").letter_of(&int(4)), "b");
+    assert_eq!(text("a😀b").letter_of(&int(2)), "\u{FFFD}");
+    assert_eq!(text("abc").letter_of(&float(1.9)), "a");
+    assert_eq!(text("abc").letter_of(&int(0)), "");
+    assert_eq!(text("abc").letter_of(&int(4)), "");
+    assert!(text("Hello").contains(&text("ELL")));
+    assert!(!text("Hello").contains(&text("x")));
+}
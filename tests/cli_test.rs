@@ -0,0 +1,127 @@
+#![cfg(feature = "cli")]
+
+use sb_sbity::{archive::Sb3Archive, project::Project};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const TEST_CASE: &str = "tests/serde_test_mod/test_case";
+
+fn sbity(args: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sbity"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn test_case(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(TEST_CASE)
+        .join(name)
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sbity_test_{}_{name}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn cli_validate_exit_code() {
+    let output = sbity(&["validate".as_ref(), &test_case("simple_project.json")]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = sbity(&["validate".as_ref(), &test_case("orbit_project.json")]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("/monitors/5/spriteName"));
+
+    let output = sbity(&["validate".as_ref(), &test_case("does_not_exist.json")]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_fmt_and_diff() {
+    let dir = temp_dir("fmt");
+    let original = test_case("simple_project.json");
+    let formatted = dir.join("formatted.json");
+    let output = sbity(&["fmt".as_ref(), &original, "-o".as_ref(), &formatted]);
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = sbity(&["diff".as_ref(), &original, &formatted]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    let mut project: Project = serde_json::from_slice(&fs::read(&formatted).unwrap()).unwrap();
+    project.targets[1].target_mut().costumes[1].asset.name = "walk".to_owned();
+    let changed = dir.join("changed.json");
+    fs::write(&changed, serde_json::to_vec(&project).unwrap()).unwrap();
+
+    let output = sbity(&["diff".as_ref(), &original, &changed]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Sprite1: ~ costume costume2 renamed to walk\n"
+    );
+    let output = sbity(&["diff".as_ref(), &original, &changed, "--json".as_ref()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "~ /targets/1/costumes/1/name: \"costume2\" -> \"walk\"\n"
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_pack_and_extract() {
+    let dir = temp_dir("pack");
+    let project_json = dir.join("project.json");
+    fs::copy(test_case("simple_project.json"), &project_json).unwrap();
+    let sb3 = dir.join("project.sb3");
+
+    let output = sbity(&["pack".as_ref(), &project_json, "-o".as_ref(), &sb3]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let project: Project = serde_json::from_slice(&fs::read(&project_json).unwrap()).unwrap();
+    for reference in Sb3Archive::new(project).asset_references() {
+        fs::write(dir.join(&reference.file_name), reference.asset_name).unwrap();
+    }
+    let output = sbity(&["pack".as_ref(), &project_json, "-o".as_ref(), &sb3]);
+    assert_eq!(output.status.code(), Some(0));
+
+    // The asset files are placeholders, not sounds
+    let output = sbity(&["validate".as_ref(), &sb3]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("sound `pop` of `Stage`: file `"));
+    assert!(stdout
+        .lines()
+        .all(|l| l.ends_with("could not be read: not a WAV or MP3 file")));
+    let output = sbity(&["info".as_ref(), &sb3]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("apple (sprite)"));
+
+    let extracted = dir.join("extracted");
+    let extracted_json = dir.join("extracted.json");
+    let output = sbity(&[
+        "extract".as_ref(),
+        &sb3,
+        "-o".as_ref(),
+        &extracted_json,
+        "--assets".as_ref(),
+        &extracted,
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    let output = sbity(&["diff".as_ref(), &project_json, &extracted_json]);
+    assert_eq!(output.status.code(), Some(0));
+    let archive = Sb3Archive::open(&sb3).unwrap();
+    for (name, data) in &archive.files {
+        assert_eq!(&fs::read(extracted.join(name)).unwrap(), data);
+    }
+
+    let output = sbity(&["extract".as_ref(), &project_json]);
+    assert_eq!(output.status.code(), Some(2));
+    fs::remove_dir_all(dir).unwrap();
+}
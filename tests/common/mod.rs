@@ -0,0 +1,11 @@
+//! Shared by the tests
+
+use sb_sbity::project::Project;
+
+/// The project of `simple_project.json`, a stage and two sprites.
+pub fn simple_project() -> Project {
+    serde_json::from_str(include_str!(
+        "../serde_test_mod/test_case/simple_project.json"
+    ))
+    .unwrap()
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    archive::AssetKind,
+    block::DataKind,
+    builder::{motion, ProjectBuilder},
+    diff::{diff, Change, ChangeKind, Diff},
+};
+
+#[test]
+fn diff_ignores_ids_and_order() {
+    let build = || {
+        let mut project = ProjectBuilder::new();
+        project.stage().variable("score", 0).list("items", ["a"]);
+        let cat = project.sprite("Cat");
+        cat.when_flag_clicked().then(motion::move_steps(10));
+        cat.when_this_sprite_clicked().then(motion::turn_right(15));
+        project.build()
+    };
+    let old = build();
+    let mut new = build();
+    assert_ne!(old, new);
+    assert!(diff(&old, &new).is_empty());
+
+    // Scripts are matched by what they do, wherever they are in the blocks map
+    let blocks = &mut new.targets[1].target_mut().blocks;
+    blocks.0.reverse();
+    assert!(diff(&old, &new).is_empty());
+
+    let project = simple_project();
+    assert_eq!(diff(&project, &project), Diff::default());
+}
+
+#[test]
+fn diff_changes() {
+    let old = simple_project();
+    let mut new = old.clone();
+    let stage = new.targets[0].target_mut();
+    stage.variables.0.values_mut().next().unwrap().name = "points".to_owned();
+    new.add_scratchblocks("Stage", "when green flag clicked\nset [points v] to [0]\n")
+        .unwrap();
+    let sprite = new.targets[1].target_mut();
+    sprite.costumes[1].asset.name = "walk".to_owned();
+    sprite.costumes[0].asset.asset_id = "0123456789abcdef0123456789abcdef".to_owned();
+    new.targets.remove(2);
+    new.monitors[0].x = 100;
+
+    let changes = diff(&old, &new);
+    let stage = Some("Stage".to_owned());
+    assert_eq!(
+        changes.changes[..2],
+        [
+            Change {
+                target: stage.clone(),
+                kind: ChangeKind::DataRenamed {
+                    kind: DataKind::Variable,
+                    old: "score".to_owned(),
+                    new: "points".to_owned(),
+                },
+            },
+            Change {
+                target: stage,
+                kind: ChangeKind::ScriptAdded {
+                    script: "when green flag clicked\nset [points v] to [0]\n".to_owned(),
+                },
+            },
+        ]
+    );
+    assert!(changes.changes.contains(&Change {
+        target: Some("Sprite1".to_owned()),
+        kind: ChangeKind::AssetRenamed {
+            kind: AssetKind::Costume,
+            asset_id: old.targets[1].target().costumes[1].asset.asset_id.clone(),
+            old: "costume2".to_owned(),
+            new: "walk".to_owned(),
+        },
+    }));
+
+    let text = changes.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "Stage: ~ variable score renamed to points",
+            "Stage: + script",
+            "    when green flag clicked",
+            "    set [points v] to [0]",
+            "Sprite1: ~ costume costume1 changed from bcf454acf82e4504149f7ffe07081dbc to 0123456789abcdef0123456789abcdef",
+            "Sprite1: ~ costume costume2 renamed to walk",
+            "- sprite apple",
+            "Stage: ~ monitor 31*#$v.#k`Yll6(`.hFu moved from (5, 5) to (100, 5)",
+        ]
+    );
+
+    let json = serde_json::to_value(&changes).unwrap();
+    assert_eq!(json["changes"][0]["change"], "data_renamed");
+    assert_eq!(json["changes"][0]["kind"], "variable");
+    assert_eq!(serde_json::from_value::<Diff>(json).unwrap(), changes);
+}
+
+#[test]
+fn diff_modified_script() {
+    let build = |steps: i32| {
+        let mut project = ProjectBuilder::new();
+        project
+            .sprite("Cat")
+            .when_flag_clicked()
+            .then(motion::goto_xy(0, 0))
+            .then(motion::move_steps(steps));
+        project.build()
+    };
+    let changes = diff(&build(10), &build(20));
+    assert_eq!(
+        changes.to_string(),
+        "\
+Cat: ~ script
+    when green flag clicked
+    go to x: (0) y: (0)
+  - move (10) steps
+  + move (20) steps
+"
+    );
+}
@@ -0,0 +1,78 @@
+use sb_sbity::{
+    builder::{BlockBuilder, ProjectBuilder},
+    extension::Extension,
+    project::Project,
+    validate::{Diagnostic, DiagnosticKind},
+};
+
+#[test]
+fn extension_ids() {
+    let extensions: Vec<Extension> =
+        serde_json::from_str(r#"["pen", "videoSensing", "text2speech", "lmsutils"]"#).unwrap();
+    assert_eq!(
+        extensions,
+        [
+            Extension::Pen,
+            Extension::VideoSensing,
+            Extension::TextToSpeech,
+            Extension::Custom("lmsutils".to_owned()),
+        ]
+    );
+    assert_eq!(
+        serde_json::to_string(&extensions).unwrap(),
+        r#"["pen","videoSensing","text2speech","lmsutils"]"#
+    );
+
+    assert_eq!(Extension::from_opcode("pen_clear"), Some(Extension::Pen));
+    assert_eq!(
+        Extension::from_opcode("videoSensing_whenMotionGreaterThan"),
+        Some(Extension::VideoSensing)
+    );
+    assert_eq!(Extension::from_opcode("motion_movesteps"), None);
+    assert_eq!(Extension::from_opcode("colour_picker"), None);
+    assert_eq!(Extension::from_opcode("note"), None);
+    assert_eq!(
+        Extension::from_opcode("lmsutils_getclipboard"),
+        Some(Extension::Custom("lmsutils".to_owned()))
+    );
+}
+
+#[test]
+fn missing_extensions() {
+    let mut project = ProjectBuilder::new();
+    project.sprite("Pen");
+    let mut project = project.build();
+    project
+        .add_scratchblocks("Pen", "when green flag clicked\nerase all\npen down\n")
+        .unwrap();
+    assert_eq!(project.missing_extensions(), [Extension::Pen]);
+    assert_eq!(
+        project.validate(),
+        [Diagnostic {
+            path: "/extensions".to_owned(),
+            kind: DiagnosticKind::MissingExtension {
+                extension: Extension::Pen
+            },
+        }]
+    );
+    assert_eq!(
+        project.validate()[0].to_string(),
+        "/extensions: extension `pen` is used by blocks but not loaded"
+    );
+
+    project.add_missing_extensions();
+    assert_eq!(project.validate(), []);
+
+    // Projects built already load their extensions
+    let mut built = ProjectBuilder::new();
+    built
+        .sprite("Pen")
+        .when_flag_clicked()
+        .then(BlockBuilder::new("pen_clear"));
+    assert_eq!(built.build().extensions, [Extension::Pen]);
+
+    let json = serde_json::to_value(&project).unwrap();
+    assert_eq!(json["extensions"], serde_json::json!(["pen"]));
+    let project: Project = serde_json::from_value(json).unwrap();
+    assert_eq!(project.extensions, [Extension::Pen]);
+}
@@ -0,0 +1,187 @@
+use flate2::{write::ZlibEncoder, Compression};
+use sb_sbity::{
+    asset::{Costume, DataFormat},
+    image::{png_visible_bounds, Bounds, Centering, ImageError, ImageSize},
+    value::Number,
+};
+use std::io::Write;
+
+fn chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+    chunk.extend(kind);
+    chunk.extend(data);
+    // The CRC is not checked
+    chunk.extend([0; 4]);
+    chunk
+}
+
+/// An 8 bits RGBA PNG, every scanline with the filter of its row modulo 5.
+fn png(width: u32, height: u32, visible: impl Fn(u32, u32) -> bool) -> Vec<u8> {
+    let mut raw = vec![];
+    let mut previous = vec![0u8; width as usize * 4];
+    for y in 0..height {
+        let row: Vec<u8> = (0..width)
+            .flat_map(|x| [x as u8, y as u8, 200, if visible(x, y) { 255 } else { 0 }])
+            .collect();
+        let filter = (y % 5) as u8;
+        raw.push(filter);
+        for i in 0..row.len() {
+            let left = if i >= 4 { row[i - 4] } else { 0 };
+            let up = previous[i];
+            let up_left = if i >= 4 { previous[i - 4] } else { 0 };
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                _ => {
+                    let p = left as i16 + up as i16 - up_left as i16;
+                    let (pa, pb, pc) = (
+                        (p - left as i16).abs(),
+                        (p - up as i16).abs(),
+                        (p - up_left as i16).abs(),
+                    );
+                    if pa <= pb && pa <= pc {
+                        left
+                    } else if pb <= pc {
+                        up
+                    } else {
+                        up_left
+                    }
+                }
+            };
+            raw.push(row[i].wrapping_sub(predicted));
+        }
+        previous = row;
+    }
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(&raw).unwrap();
+
+    let mut header = width.to_be_bytes().to_vec();
+    header.extend(height.to_be_bytes());
+    header.extend([8, 6, 0, 0, 0]);
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png.extend(chunk(b"IHDR", &header));
+    png.extend(chunk(b"IDAT", &encoder.finish().unwrap()));
+    png.extend(chunk(b"IEND", &[]));
+    png
+}
+
+#[test]
+fn image_sizes() {
+    let size = |bytes: &[u8]| ImageSize::from_bytes(bytes);
+    let svg = br#"<?xml version="1.0"?>
+<!-- <svg width="1" -->
+<svg xmlns="http://www.w3.org/2000/svg" stroke-width="3" width="100px" height='50' viewBox="0, 0 96.5 40"></svg>"#;
+    assert_eq!(
+        size(svg),
+        Ok(ImageSize {
+            width: 96.5,
+            height: 40.
+        })
+    );
+    assert_eq!(
+        size(br#"<svg width="100px" height='50'>"#),
+        Ok(ImageSize {
+            width: 100.,
+            height: 50.
+        })
+    );
+    assert_eq!(
+        size(br#"<svg width="100%" height="100%">"#),
+        Err(ImageError::NoSize)
+    );
+
+    assert_eq!(
+        size(&png(30, 20, |_, _| true)),
+        Ok(ImageSize {
+            width: 30.,
+            height: 20.
+        })
+    );
+    let jpeg = [
+        &[0xff, 0xd8, 0xff, 0xe0, 0, 4, 0, 0][..],
+        &[0xff, 0xc0, 0, 11, 8, 0, 120, 1, 64, 3],
+    ]
+    .concat();
+    assert_eq!(
+        size(&jpeg),
+        Ok(ImageSize {
+            width: 320.,
+            height: 120.
+        })
+    );
+    assert_eq!(
+        size(b"GIF89a\x10\x00\x08\x00"),
+        Ok(ImageSize {
+            width: 16.,
+            height: 8.
+        })
+    );
+}
+
+#[test]
+fn png_visible_pixels() {
+    let ring = |x: u32, y: u32| (3..9).contains(&x) && (2..5).contains(&y) && x != 5;
+    assert_eq!(
+        png_visible_bounds(&png(12, 10, ring)),
+        Ok(Some(Bounds {
+            left: 3,
+            top: 2,
+            right: 9,
+            bottom: 5
+        }))
+    );
+    assert_eq!(png_visible_bounds(&png(4, 4, |_, _| false)), Ok(None));
+
+    // A huge header does not make it inflate or allocate without bound
+    let mut huge = png(4, 4, |_, _| true);
+    huge[16..24].copy_from_slice(&[0xff; 8]);
+    assert!(png_visible_bounds(&huge).is_err());
+    // Nor does a 16 bit RGBA header 0 pixels high but 2^31 - 1 wide, without any data
+    let mut empty = b"\x89PNG\r\n\x1a\n".to_vec();
+    empty.extend(chunk(
+        b"IHDR",
+        &[0x7f, 0xff, 0xff, 0xff, 0, 0, 0, 0, 16, 6, 0, 0, 0],
+    ));
+    empty.extend(chunk(b"IEND", &[]));
+    assert_eq!(empty.len(), 45);
+    assert_eq!(png_visible_bounds(&empty), Err(ImageError::InvalidData));
+    let centered = Costume::default().center_from_bytes(&empty, Centering::VisibleBounds);
+    assert_eq!(centered, Err(ImageError::InvalidData));
+
+    let mut costume = Costume::default();
+    costume
+        .center_from_bytes(&png(12, 10, ring), Centering::VisibleBounds)
+        .unwrap();
+    assert_eq!(costume.rotation_center_x, Number::Int(6));
+    assert_eq!(costume.rotation_center_y, Number::Float(3.5));
+    assert_eq!(costume.bitmap_resolution, Some(2));
+}
+
+#[test]
+fn costume_centered() {
+    let costume = Costume::from_bytes("ring", &png(12, 10, |_, _| false), None).unwrap();
+    assert_eq!(costume.rotation_center_x, Number::Int(6));
+    assert_eq!(costume.rotation_center_y, Number::Int(5));
+    assert_eq!(costume.bitmap_resolution, Some(2));
+
+    let svg = br#"<svg viewBox="-10 -10 25 40"></svg>"#;
+    let mut costume = Costume::from_bytes("box", svg, None).unwrap();
+    assert_eq!(costume.rotation_center_x, Number::Float(12.5));
+    assert_eq!(costume.rotation_center_y, Number::Int(20));
+    assert_eq!(costume.bitmap_resolution, Some(1));
+    // Only PNG files have visible bounds
+    costume
+        .center_from_bytes(svg, Centering::VisibleBounds)
+        .unwrap();
+    assert_eq!(costume.rotation_center_y, Number::Int(20));
+
+    // Images whose size cannot be read are left uncentered
+    let costume = Costume::from_bytes("full", br#"<svg width="100%">"#, None).unwrap();
+    assert_eq!(costume.rotation_center_x, Number::Int(0));
+    assert_eq!(costume.bitmap_resolution, Some(1));
+    let costume = Costume::from_bytes("raw", b"abc", Some(DataFormat::Png)).unwrap();
+    assert_eq!(costume.rotation_center_y, Number::Int(0));
+    assert_eq!(costume.bitmap_resolution, Some(2));
+}
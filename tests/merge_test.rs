@@ -0,0 +1,137 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    block::Block,
+    builder::{data, motion, ProjectBuilder},
+    diff::diff,
+    merge::{merge, Conflict, ConflictKind, Item},
+    project::Project,
+    value::Value,
+};
+
+/// Set the opcode of every block of the sprite with the opcode.
+fn set_opcode(project: &mut Project, sprite: usize, from: &str, to: &str) {
+    for block in project.targets[sprite].target_mut().blocks.0.values_mut() {
+        match block {
+            Block::Normal(b) if b.opcode == from => b.opcode = to.to_owned(),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn merge_independent_edits() {
+    let base = simple_project();
+    let mut ours = base.clone();
+    ours.add_scratchblocks("Stage", "when green flag clicked\nset [score v] to [0]\n")
+        .unwrap();
+    // Values are not edits
+    let stage = ours.targets[0].target_mut();
+    stage.variables.0.values_mut().next().unwrap().value = Value::from(10).into();
+
+    let mut theirs = base.clone();
+    theirs.targets[1].target_mut().costumes[1].asset.name = "walk".to_owned();
+    theirs.targets[0].target_mut().variables.0[0].value = Value::from(20).into();
+    theirs.targets.remove(2);
+
+    let merged = merge(&base, &ours, &theirs).unwrap();
+    assert_eq!(merged.validate(), vec![]);
+    assert_eq!(
+        diff(&base, &merged).to_string(),
+        "\
+Stage: + script
+    when green flag clicked
+    set [score v] to [0]
+Sprite1: ~ costume costume2 renamed to walk
+- sprite apple
+"
+    );
+    assert_eq!(
+        merged.targets[0].target().variables.0[0].value,
+        Value::from(10).into()
+    );
+
+    assert_eq!(merge(&base, &ours, &ours).unwrap(), ours);
+    assert!(diff(&merge(&base, &base, &theirs).unwrap(), &theirs).is_empty());
+}
+
+#[test]
+fn merge_conflicts() {
+    let mut project = ProjectBuilder::new();
+    let cat = project.sprite("Cat");
+    cat.when_flag_clicked().then(motion::move_steps(10));
+    cat.when_this_sprite_clicked().then(motion::turn_right(15));
+    project.sprite("Dog");
+    let base = project.build();
+
+    let mut ours = base.clone();
+    set_opcode(&mut ours, 1, "motion_movesteps", "motion_changexby");
+    ours.targets.remove(2);
+    let mut theirs = base.clone();
+    set_opcode(&mut theirs, 1, "motion_movesteps", "motion_changeyby");
+    set_opcode(&mut theirs, 1, "motion_turnright", "motion_turnleft");
+    theirs.targets[2].target_mut().volume = 50.into();
+
+    let conflicts = merge(&base, &ours, &theirs).unwrap_err().conflicts;
+    assert_eq!(conflicts.len(), 2);
+    assert!(matches!(
+        &conflicts[0],
+        Conflict {
+            item: Item::Script { hat, .. },
+            kind: ConflictKind::BothChanged,
+            ..
+        } if hat == "when green flag clicked"
+    ));
+    assert_eq!(
+        conflicts[1],
+        Conflict {
+            target: Some("Dog".to_owned()),
+            item: Item::Target,
+            kind: ConflictKind::ChangedAndRemoved,
+        }
+    );
+    assert_eq!(
+        conflicts[0].to_string(),
+        "Cat: script `when green flag clicked` changed on both sides"
+    );
+
+    // Moving a script on one side is not a conflict with editing it on the other
+    let mut moved = base.clone();
+    for block in moved.targets[1].target_mut().blocks.0.values_mut() {
+        if let Block::Normal(b) = block {
+            b.x = b.x.map(|_| 300.into());
+        }
+    }
+    let merged = merge(&base, &moved, &theirs).unwrap();
+    assert!(diff(&merged, &theirs).is_empty());
+}
+
+#[test]
+fn merge_breaks_references() {
+    let mut project = ProjectBuilder::new();
+    let cat = project.sprite("Cat");
+    cat.variable("score", 0);
+    cat.when_flag_clicked()
+        .then(data::set_variable_to("score", 0));
+    let base = project.build();
+
+    let mut ours = base.clone();
+    let cat = ours.targets[1].target_mut();
+    cat.blocks.0.clear();
+    cat.variables.0.clear();
+    let mut theirs = base.clone();
+    theirs
+        .add_scratchblocks("Cat", "when this sprite clicked\nchange [score v] by (1)\n")
+        .unwrap();
+
+    let conflicts = merge(&base, &ours, &theirs).unwrap_err().conflicts;
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].target, Some("Cat".to_owned()));
+    assert_eq!(conflicts[0].kind, ConflictKind::Merged);
+    let Item::Diagnostic { path, message } = &conflicts[0].item else {
+        panic!("the conflict should be a diagnostic")
+    };
+    assert!(path.starts_with("/targets/1/blocks/") && path.ends_with("/fields/VARIABLE"));
+    assert!(message.starts_with("Variable `score`"));
+}
@@ -0,0 +1,69 @@
+use sb_sbity::{
+    block::Block,
+    opcode::{Category, KnownOpcode, Shape},
+    project::Project,
+    value::OpCode,
+};
+
+#[test]
+fn opcode_conversion() {
+    for op in KnownOpcode::ALL {
+        let s: OpCode = op.clone().into();
+        assert_eq!(&KnownOpcode::from(s), op);
+    }
+    assert_eq!(
+        KnownOpcode::from("motion_gotoxyy"),
+        KnownOpcode::Unknown("motion_gotoxyy".to_owned())
+    );
+    assert_eq!(
+        OpCode::from(KnownOpcode::Unknown("motion_gotoxyy".to_owned())),
+        "motion_gotoxyy"
+    );
+}
+
+#[test]
+fn opcode_catalog() {
+    let op = KnownOpcode::from("control_if_else");
+    assert_eq!(op, KnownOpcode::ControlIfElse);
+    assert_eq!(op.category(), Some(Category::Control));
+    assert_eq!(op.shape(), Some(Shape::C));
+    assert_eq!(op.inputs(), ["CONDITION", "SUBSTACK", "SUBSTACK2"]);
+
+    let op = KnownOpcode::from("pen_menu_colorParam");
+    assert_eq!(op.category(), Some(Category::Pen));
+    assert_eq!(op.fields(), ["colorParam"]);
+
+    let op = KnownOpcode::from("motion_gotoxyy");
+    assert_eq!(op.category(), Some(Category::Motion));
+    assert_eq!(op.shape(), None);
+    assert_eq!(KnownOpcode::from("foo").category(), None);
+}
+
+#[test]
+fn opcode_catalog_covers_test_projects() {
+    for json in [
+        include_str!("serde_test_mod/test_case/simple_project.json"),
+        include_str!("serde_test_mod/test_case/orbit_project.json"),
+        include_str!("serde_test_mod/test_case/slitcherio.json"),
+    ] {
+        let project: Project = serde_json::from_str(json).unwrap();
+        for target in &project.targets {
+            for block in target.target().blocks.0.values() {
+                let Block::Normal(block) = block else {
+                    continue;
+                };
+                let op = block.known_opcode();
+                assert!(op.is_known(), "{} is not in the catalog", block.opcode);
+                if op == KnownOpcode::ProceduresPrototype || op == KnownOpcode::ProceduresCall {
+                    continue;
+                }
+                for input in block.inputs.0.keys() {
+                    assert!(op.inputs().contains(&input.as_str()), "{op} {input}");
+                }
+                for field in block.fields.0.keys() {
+                    assert!(op.fields().contains(&field.as_str()), "{op} {field}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    block::DataKind, monitor::Parameter, project::Project, rename::RenameError, variable::Variable,
+};
+
+const SCORE: &str = "31*#$v.#k`Yll6(`.hFu";
+
+fn declare_local(project: &mut Project, target: usize, id: &str, name: &str) {
+    let variable = Variable {
+        name: name.to_owned(),
+        ..Default::default()
+    };
+    (project.targets[target].target_mut().variables.0).insert(id.to_owned(), variable);
+}
+
+#[test]
+fn rename_everywhere() {
+    let mut project = simple_project();
+    project
+        .add_scratchblocks(
+            "Stage",
+            "when I receive [go v]\nwait (score) seconds\nbroadcast [go v]\n",
+        )
+        .unwrap();
+    let go = project.targets[0].target().to_scratchblocks().unwrap();
+
+    project.rename_variable(SCORE, "points").unwrap();
+    let broadcast = project.targets[0]
+        .target()
+        .broadcasts
+        .0
+        .keys()
+        .next()
+        .unwrap();
+    project
+        .rename_broadcast(&broadcast.clone(), "start")
+        .unwrap();
+
+    assert_eq!(project.validate(), vec![]);
+    assert_eq!(
+        project.targets[0].target().variables.0[SCORE].name,
+        "points"
+    );
+    assert_eq!(
+        project.monitors[0].params,
+        Parameter::Variable("points".to_owned())
+    );
+    let stage = project.targets[0].target().to_scratchblocks().unwrap();
+    let renamed = go.replace("(score)", "(points)").replace("go v", "start v");
+    assert_eq!(stage, renamed);
+    let apple = project.targets[2].target().to_scratchblocks().unwrap();
+    assert!(apple.contains("change [points v] by (1)"));
+    assert!(!apple.contains("score"));
+}
+
+#[test]
+fn rename_in_scope() {
+    let mut project = simple_project();
+    declare_local(&mut project, 1, "lives", "lives");
+    declare_local(&mut project, 2, "apples", "apples");
+    // A local variable with the Id of the global one hides it in the sprite
+    declare_local(&mut project, 2, SCORE, "score");
+    project
+        .add_scratchblocks("Stage", "when I receive [go v]\nbroadcast [stop v]\n")
+        .unwrap();
+
+    assert_eq!(
+        project.rename_variable("nothing", "x"),
+        Err(RenameError::NotFound {
+            kind: DataKind::Variable,
+            id: "nothing".to_owned()
+        })
+    );
+    assert_eq!(
+        project.rename_list(SCORE, "x"),
+        Err(RenameError::NotFound {
+            kind: DataKind::List,
+            id: SCORE.to_owned()
+        })
+    );
+    assert_eq!(
+        project.rename_variable(SCORE, ""),
+        Err(RenameError::EmptyName)
+    );
+    let taken = project.rename_variable(SCORE, "lives").unwrap_err();
+    assert_eq!(
+        taken.to_string(),
+        "Sprite1 already has a variable named `lives`"
+    );
+    let taken = project.rename_variable("lives", "score").unwrap_err();
+    assert_eq!(
+        taken.to_string(),
+        "Stage already has a variable named `score`"
+    );
+    let stop = project.targets[0]
+        .target()
+        .broadcasts
+        .0
+        .get_index(1)
+        .unwrap();
+    let taken = project.rename_broadcast(&stop.0.clone(), "GO").unwrap_err();
+    assert_eq!(
+        taken.to_string(),
+        "Stage already has a broadcast named `GO`"
+    );
+
+    // Locals of other sprites are not in scope
+    project.rename_variable("lives", "apples").unwrap();
+    project.rename_variable(SCORE, "points").unwrap();
+    let apple = project.targets[2].target();
+    assert_eq!(apple.variables.0[SCORE].name, "score");
+    assert!(apple
+        .to_scratchblocks()
+        .unwrap()
+        .contains("change [score v]"));
+}
@@ -0,0 +1,250 @@
+use sb_sbity::{
+    block::Block,
+    builder::{control, data, events, looks, motion, operator, sensing, ProjectBuilder},
+    project::Project,
+    scratchblocks,
+    string_hashmap::StringHashMap,
+};
+
+fn procedural_project() -> Project {
+    let blocks: StringHashMap<Block> = serde_json::from_str(include_str!(
+        "serde_test_mod/test_case/procedural_block_testcase.json"
+    ))
+    .unwrap();
+    let mut project = ProjectBuilder::new().build();
+    project.targets[0].target_mut().blocks = blocks;
+    project
+}
+
+#[test]
+fn scratchblocks_custom_blocks() {
+    let project = procedural_project();
+    let target = project.targets[0].target();
+    assert_eq!(
+        target.to_scratchblocks().unwrap(),
+        "\
+when green flag clicked
+simple
+1 num (mouse x)
+1 bool <<> and <>>
+text between <[3] = [50]> label text ((1) * (2)) label text
+
+define simple
+move (10) steps
+go to (random position v)
+glide (1) secs to (x position)
+
+define 1 num (num)
+move (num) steps
+
+define 1 bool <bool>
+move <bool> steps
+
+define text between <boolean> label text (number or text) label text
+move <boolean> steps
+go to (number or text)
+"
+    );
+
+    // Without the target, calls can't be told apart from unknown blocks
+    let flag = target
+        .scripts()
+        .into_iter()
+        .find(|s| s.top().opcode() == Some("event_whenflagclicked"))
+        .unwrap();
+    let text = scratchblocks::render_script(flag).unwrap();
+    assert!(text.contains("\nsimple :: custom\n"));
+}
+
+#[test]
+fn scratchblocks_script() {
+    let mut project = ProjectBuilder::new();
+    project.stage().variable("timer", 0).list("items", ["a"]);
+    project
+        .sprite("Cat")
+        .when_flag_clicked()
+        .then(motion::goto_xy(0, operator::random(-10, 10.5)))
+        .then(control::forever(|s| {
+            s.then(control::if_else(
+                operator::and(
+                    sensing::touching("_edge_"),
+                    operator::not(operator::lt(data::variable("timer"), 3)),
+                ),
+                |s| s.then(data::add_to_list("[x] v", "items")),
+                |s| {
+                    s.then(looks::say(operator::join("(hi)", data::list("items"))))
+                        .then(control::stop("this script"))
+                },
+            ))
+        }));
+    project
+        .stage()
+        .when_broadcast_received("go")
+        .then(events::broadcast("go"));
+    let project = project.build();
+
+    assert_eq!(
+        project.targets[1].target().to_scratchblocks().unwrap(),
+        "\
+when green flag clicked
+go to x: (0) y: (pick random (-10) to (10.5))
+forever
+    if <<touching (edge v)?> and <not <(timer :: variables) < [3]>>> then
+        add [\\[x\\] \\v] to [items v]
+    else
+        say (join [\\(hi\\)] (items :: list))
+        stop [this script v]
+    end
+end
+"
+    );
+    assert_eq!(
+        project.targets[0].target().to_scratchblocks().unwrap(),
+        "when I receive [go v]\nbroadcast (go v)\n"
+    );
+}
+
+#[test]
+fn scratchblocks_parse_round_trip() {
+    let project = procedural_project();
+    let text = project.targets[0].target().to_scratchblocks().unwrap();
+    let mut project = ProjectBuilder::new().build();
+    project.add_scratchblocks("Stage", &text).unwrap();
+    let stage = project.targets[0].target();
+    assert_eq!(stage.to_scratchblocks().unwrap(), text);
+    assert!(project.validate().is_empty());
+
+    let text = "\
+when green flag clicked
+set [score v] to [0]
+repeat until <(score) > [10]>
+    change [score v] by (pick random (1) to (3))
+    if <key (space v) pressed?> then
+        add (join [hi ] (score)) to [log v]
+    else
+        broadcast (tick v) and wait
+    end
+    change [pitch v] effect by (10)
+    change [color v] effect by (25)
+    say ([abs v] of (score))
+    jump [5] <mouse down?> :: custom
+end
+stop [all v]
+
+when I receive [tick v]
+go to (mouse-pointer v)
+define jump (height) <fast> // run without screen refresh
+change y by (height)
+";
+    let mut project = ProjectBuilder::new().build();
+    project.add_scratchblocks("Stage", text).unwrap();
+    let stage = project.targets[0].target();
+    let names = |names: Vec<&String>| names.into_iter().cloned().collect::<Vec<_>>();
+    assert_eq!(
+        names(stage.variables.0.values().map(|v| &v.name).collect()),
+        ["score"]
+    );
+    assert_eq!(
+        names(stage.lists.0.values().map(|l| &l.name).collect()),
+        ["log"]
+    );
+    assert_eq!(
+        names(stage.broadcasts.0.values().map(|b| &b.name).collect()),
+        ["tick"]
+    );
+    // `define` starts its own script
+    assert_eq!(stage.scripts().len(), 3);
+    let opcodes: Vec<_> = stage
+        .blocks
+        .0
+        .values()
+        .filter_map(|b| match b {
+            Block::Normal(b) => Some(b.opcode.as_str()),
+            Block::VarList(_) => None,
+        })
+        .collect();
+    for opcode in [
+        "sound_changeeffectby",
+        "looks_changeeffectby",
+        "operator_mathop",
+    ] {
+        assert!(opcodes.contains(&opcode), "{opcode}");
+    }
+    assert!(project.validate().is_empty());
+
+    let stage = project.targets[0].target();
+    let rendered = stage.to_scratchblocks().unwrap();
+    let expected = text
+        .replace(" :: custom", "")
+        .replace("\ndefine", "\n\ndefine");
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn scratchblocks_parse_in_sprite() {
+    let mut project = ProjectBuilder::new();
+    project.stage().variable("score", 0);
+    project.sprite("Cat").variable("speed", 1);
+    let mut project = project.build();
+    project
+        .add_scratchblocks(
+            "Cat",
+            "when green flag clicked\nchange [score v] by (speed)\nbroadcast [go v]\nadd [x] to [log v]\n",
+        )
+        .unwrap();
+    assert_eq!(project.validate(), vec![]);
+
+    let [stage, cat] = [0, 1].map(|i| project.targets[i].target());
+    // Globals are found and what is declared nowhere is declared in the stage
+    assert_eq!(stage.variables.len(), 1);
+    assert_eq!(stage.lists.len(), 1);
+    assert_eq!(stage.broadcasts.len(), 1);
+    assert_eq!(cat.variables.len(), 1);
+    assert!(cat.lists.is_empty() && cat.broadcasts.is_empty());
+}
+
+#[test]
+fn scratchblocks_parse_errors() {
+    let mut project = ProjectBuilder::new().build();
+    let error = |text: &str| {
+        let e = project
+            .clone()
+            .add_scratchblocks("Stage", text)
+            .unwrap_err();
+        (e.line, e.column, e.message)
+    };
+    assert_eq!(
+        error("when green flag clicked\nmove (10 steps"),
+        (2, 6, "missing `)`".to_owned())
+    );
+    assert_eq!(
+        error("when green flag clicked\n  fly (10) steps"),
+        (2, 3, "unknown block `fly (10) steps`".to_owned())
+    );
+    assert_eq!(
+        error("say [hi]\nend"),
+        (2, 1, "`end` without a C block to close".to_owned())
+    );
+    assert_eq!(
+        error("say <jump>"),
+        (1, 6, "unknown block `jump`".to_owned())
+    );
+    assert_eq!(
+        error(":: custom"),
+        (1, 1, "expected the text of the custom block".to_owned())
+    );
+    assert_eq!(
+        error("say (:: custom)"),
+        (1, 6, "expected the text of the custom block".to_owned())
+    );
+    let e = project
+        .clone()
+        .add_scratchblocks("Dog", "say [hi]")
+        .unwrap_err();
+    assert_eq!(e.to_string(), "no target is named `Dog`");
+    let e = project
+        .add_scratchblocks("Stage", "if <> then\nelse\nelse")
+        .unwrap_err();
+    assert_eq!(e.to_string(), "line 3, column 1: `else` outside of an if");
+    assert!(project.targets[0].target().blocks.0.is_empty());
+}
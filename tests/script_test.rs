@@ -0,0 +1,123 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    block::Block,
+    script::{self, Error, InputRef},
+    string_hashmap::StringHashMap,
+};
+
+fn opcodes<'a>(
+    blocks: impl IntoIterator<Item = Result<script::BlockRef<'a>, Error>>,
+) -> Vec<&'a str> {
+    blocks
+        .into_iter()
+        .map(|b| b.unwrap().opcode().unwrap())
+        .collect()
+}
+
+#[test]
+fn script_tree() {
+    let project = simple_project();
+    let apple = project.targets[2].target();
+    let scripts = apple.scripts();
+    assert_eq!(scripts.len(), 1);
+
+    let script = scripts[0];
+    assert_eq!(
+        opcodes(script.stack()),
+        ["event_whenflagclicked", "control_forever"]
+    );
+
+    let forever = script.stack().last().unwrap().unwrap();
+    let substack = forever.substack("SUBSTACK").unwrap().unwrap();
+    assert_eq!(
+        opcodes(substack.stack()),
+        [
+            "motion_changeyby",
+            "motion_turnright",
+            "control_if",
+            "control_if"
+        ]
+    );
+
+    let control_if = substack.stack().nth(2).unwrap().unwrap();
+    let Some(InputRef::Block(condition)) = control_if.input("CONDITION").unwrap() else {
+        panic!("condition should be a block")
+    };
+    assert_eq!(condition.opcode(), Some("sensing_touchingobject"));
+    assert_eq!(
+        opcodes(condition.parents()),
+        [
+            "control_if",
+            "motion_turnright",
+            "motion_changeyby",
+            "control_forever",
+            "event_whenflagclicked"
+        ]
+    );
+
+    let walked = script.walk().unwrap();
+    assert_eq!(walked.len(), apple.blocks.0.len());
+    assert_eq!(walked[0], script.top());
+}
+
+fn blocks(json: serde_json::Value) -> StringHashMap<Block> {
+    serde_json::from_value(json).unwrap()
+}
+
+fn block(next: Option<&str>, parent: Option<&str>, top_level: bool) -> serde_json::Value {
+    serde_json::json!({
+        "opcode": "motion_ifonedgebounce",
+        "next": next,
+        "parent": parent,
+        "inputs": {},
+        "fields": {},
+        "shadow": false,
+        "topLevel": top_level,
+        "x": 0,
+        "y": 0
+    })
+}
+
+#[test]
+fn script_dangling() {
+    let blocks = blocks(serde_json::json!({
+        "a": block(Some("b"), None, true),
+        "b": block(Some("nothing"), Some("a"), false),
+    }));
+    let scripts = script::scripts(&blocks);
+    let results = scripts[0].stack().collect::<Vec<_>>();
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results[2].clone().unwrap_err(),
+        Error::Dangling {
+            from: "b".to_owned(),
+            to: "nothing".to_owned()
+        }
+    );
+    assert!(scripts[0].walk().is_err());
+}
+
+#[test]
+fn script_cycle() {
+    let blocks = blocks(serde_json::json!({
+        "a": block(Some("b"), Some("b"), true),
+        "b": block(Some("a"), Some("a"), false),
+    }));
+    let scripts = script::scripts(&blocks);
+    let results = scripts[0].stack().collect::<Vec<_>>();
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results[2].clone().unwrap_err(),
+        Error::Cycle { id: "a".to_owned() }
+    );
+    assert_eq!(
+        scripts[0].top().parents().last().unwrap().unwrap_err(),
+        Error::Cycle { id: "a".to_owned() }
+    );
+    assert_eq!(
+        scripts[0].walk().unwrap_err(),
+        Error::Cycle { id: "a".to_owned() }
+    );
+}
@@ -42,6 +42,22 @@ test_json! {
                 13,
                 "a list",
                 "gkuehr3r8y87ayrgjdfb"
+            ]"#,
+        block_input_value_variable_with_position =>
+            r#"[
+                12,
+                "a variable",
+                "gkuehr3r8y87ayrgjdfb",
+                45.324234,
+                90.349283473
+            ]"#,
+        block_input_value_list_with_position =>
+            r#"[
+                13,
+                "a list",
+                "gkuehr3r8y87ayrgjdfb",
+                45.324234,
+                90.349283473
             ]"#
     }
 
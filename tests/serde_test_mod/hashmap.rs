@@ -9,3 +9,49 @@ test_json! {
         }"#
     }
 }
+
+#[test]
+fn hash_map_keeps_order() {
+    let json =
+        r#"{"o49834ihkgjafsdkfjh3":802,"{6B[NX:abWD|;$w~~Yl?":23,"{osdhgo324tg7gbbwd34":16}"#;
+    let map = serde_json::from_str::<StringHashMap<i32>>(json).unwrap();
+    assert_eq!(serde_json::to_string(&map).unwrap(), json);
+}
+
+#[test]
+fn hash_map_sort_keys() {
+    let mut map = serde_json::from_str::<StringHashMap<i32>>(r#"{"c":3,"a":1,"b":2}"#).unwrap();
+    map.sort_keys();
+    assert_eq!(
+        serde_json::to_string(&map).unwrap(),
+        r#"{"a":1,"b":2,"c":3}"#
+    );
+}
+
+#[test]
+fn blocks_keep_order() {
+    let json = include_str!("test_case/general_block_testcase.json");
+    let blocks = serde_json::from_str::<StringHashMap<Block>>(json).unwrap();
+    let json_after = serde_json::to_string(&blocks).unwrap();
+    let blocks_after = serde_json::from_str::<StringHashMap<Block>>(&json_after).unwrap();
+
+    let positions = blocks_after
+        .keys()
+        .map(|k| json.find(&format!("\"{k}\": {{")).unwrap())
+        .collect::<Vec<_>>();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    assert!(blocks.keys().eq(blocks_after.keys()));
+}
+
+#[test]
+fn project_sort_keys() {
+    let mut project =
+        serde_json::from_str::<Project>(include_str!("test_case/simple_project.json")).unwrap();
+    project.sort_keys();
+    for target in &project.targets {
+        let keys = target.target().blocks.keys().collect::<Vec<_>>();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+    }
+}
@@ -27,3 +27,53 @@ test_json! {
         simple_project => include_str!("test_case/simple_project.json")
     }
 }
+
+/// Add an unknown key to every object that is a struct of this crate.
+fn add_unknown_keys(project: &mut Json) {
+    let unknown = |v: &mut Json| {
+        v.as_object_mut().unwrap().insert(
+            "unknownKey".to_owned(),
+            serde_json::json!({"b": [1], "a": null}),
+        );
+    };
+    unknown(project);
+    unknown(&mut project["meta"]);
+    for target in project["targets"].as_array_mut().unwrap() {
+        unknown(target);
+        for costume in target["costumes"].as_array_mut().unwrap() {
+            unknown(costume);
+        }
+        for sound in target["sounds"].as_array_mut().unwrap() {
+            unknown(sound);
+        }
+        for comment in target["comments"].as_object_mut().unwrap().values_mut() {
+            unknown(comment);
+        }
+        for block in target["blocks"].as_object_mut().unwrap().values_mut() {
+            if block.is_object() {
+                unknown(block);
+            }
+        }
+    }
+    for monitor in project["monitors"].as_array_mut().unwrap() {
+        unknown(monitor);
+    }
+}
+
+#[test]
+fn project_keeps_unknown_keys() {
+    for json in [
+        include_str!("test_case/slitcherio.json"),
+        include_str!("test_case/orbit_project.json"),
+    ] {
+        let mut json = serde_json::from_str::<Json>(json).unwrap();
+        add_unknown_keys(&mut json);
+
+        let project = serde_json::from_value::<Project>(json.clone()).unwrap();
+        assert_eq!(
+            project.extra.0["unknownKey"],
+            serde_json::json!({"b": [1], "a": null})
+        );
+        assert_eq!(serde_json::to_value(&project).unwrap(), json);
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    project::Project,
+    target::{SpriteOrStage, TextToSpeechLanguage},
+    validate::{Diagnostic, DiagnosticKind},
+};
+
+fn set_language(project: &mut Project, language: Option<TextToSpeechLanguage>) {
+    let SpriteOrStage::Stage(stage) = &mut project.targets[0] else {
+        panic!()
+    };
+    stage.text_to_speech_language = language;
+}
+
+#[test]
+fn text_to_speech_language_serde() {
+    let mut project = simple_project();
+    let json = serde_json::to_value(&project).unwrap();
+    assert_eq!(
+        json["targets"][0]["textToSpeechLanguage"],
+        serde_json::Value::Null
+    );
+
+    let languages: Vec<TextToSpeechLanguage> =
+        serde_json::from_str(r#"["ja", "zh-cn", "es-419", "klingon"]"#).unwrap();
+    assert_eq!(
+        languages,
+        [
+            TextToSpeechLanguage::Japanese,
+            TextToSpeechLanguage::ChineseMandarin,
+            TextToSpeechLanguage::LatinAmericanSpanish,
+            TextToSpeechLanguage::Other("klingon".to_owned()),
+        ]
+    );
+
+    set_language(
+        &mut project,
+        Some(TextToSpeechLanguage::PortugueseBrazilian),
+    );
+    let json = serde_json::to_value(&project).unwrap();
+    assert_eq!(json["targets"][0]["textToSpeechLanguage"], "pt-br");
+    let back: Project = serde_json::from_value(json).unwrap();
+    assert_eq!(back, project);
+}
+
+#[test]
+fn unknown_text_to_speech_language() {
+    let mut project = simple_project();
+    set_language(&mut project, Some(TextToSpeechLanguage::Welsh));
+    project
+        .add_scratchblocks(
+            "Sprite1",
+            "when green flag clicked\nset language to [nl v]\nset language to [xx v]\n",
+        )
+        .unwrap();
+    project.add_missing_extensions();
+    let diagnostics = project.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].path.ends_with("/fields/languages"));
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::UnknownTextToSpeechLanguage {
+            language: "xx".to_owned()
+        }
+    );
+
+    set_language(
+        &mut project,
+        Some(TextToSpeechLanguage::Other("welsh".to_owned())),
+    );
+    assert_eq!(
+        project.validate()[0],
+        Diagnostic {
+            path: "/targets/0/textToSpeechLanguage".to_owned(),
+            kind: DiagnosticKind::UnknownTextToSpeechLanguage {
+                language: "welsh".to_owned()
+            },
+        }
+    );
+    assert_eq!(
+        project.validate()[0].to_string(),
+        "/targets/0/textToSpeechLanguage: unknown Text to Speech language `welsh`"
+    );
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    block::{Block, BlockField, BlockMutationEnum},
+    diff::diff,
+    project::Project,
+    uid::{self, IdStyle},
+    value::Value,
+    variable::Variable,
+};
+
+#[test]
+fn remap_ids_short() {
+    assert_eq!(uid::short(0), "!");
+    assert_eq!(uid::short(uid::SOUP.len() - 1), "9");
+    assert_eq!(uid::short(uid::SOUP.len()), "!!");
+
+    let old = simple_project();
+    let mut new = old.clone();
+    new.remap_ids(IdStyle::Short);
+    // Only monitors are matched by Id
+    assert_eq!(
+        diff(&old, &new).to_string(),
+        "Stage: - monitor 31*#$v.#k`Yll6(`.hFu\nStage: + monitor !\n"
+    );
+    assert_eq!(new.validate(), vec![]);
+
+    let stage = new.targets[0].target();
+    assert_eq!(stage.variables.0.keys().collect::<Vec<_>>(), ["!"]);
+    assert_eq!(new.monitors[0].id, "!");
+    let apple = new.targets[2].target();
+    assert!(apple.blocks.0.keys().all(|id| id.len() == 1));
+
+    let mut again = old.clone();
+    again.remap_ids(IdStyle::Short);
+    assert_eq!(again, new);
+}
+
+#[test]
+fn remap_ids_random() {
+    let mut project = simple_project();
+    let global = project.targets[0]
+        .target()
+        .variables
+        .0
+        .keys()
+        .next()
+        .unwrap()
+        .clone();
+    let sprite = project.targets[1].target_mut();
+    // Local variable with the Id of the global one
+    sprite.variables.0.insert(
+        global.clone(),
+        Variable {
+            name: "local".to_owned(),
+            ..Default::default()
+        },
+    );
+    project
+        .add_scratchblocks(
+            "Sprite1",
+            "\
+define jump (height)
+change y by (height)
+
+when green flag clicked
+jump (10)
+set [local v] to [1]
+",
+        )
+        .unwrap();
+    let scripts = project.targets[1].target().to_scratchblocks().unwrap();
+
+    project.remap_ids(IdStyle::Random);
+    assert_eq!(project.validate(), vec![]);
+    let stage = project.targets[0].target();
+    let sprite = project.targets[1].target();
+    assert_eq!(sprite.to_scratchblocks().unwrap(), scripts);
+    let local = sprite.variables.0.keys().next().unwrap();
+    assert!(!stage.variables.0.contains_key(local));
+    assert!(!sprite.blocks.0.contains_key(&global));
+
+    let mut argumentids = vec![];
+    for block in sprite.blocks.0.values() {
+        let Block::Normal(block) = block else {
+            continue;
+        };
+        if let Some(BlockField::WithId { id: Some(id), .. }) = block.fields.0.get("VARIABLE") {
+            assert_eq!(id, local);
+        }
+        if let Some(mutation) = &block.mutation {
+            if let BlockMutationEnum::ProceduresPrototype {
+                argumentids: ids, ..
+            }
+            | BlockMutationEnum::ProceduresCall {
+                argumentids: ids, ..
+            } = &mutation.mutation_enum
+            {
+                assert!(ids.iter().all(|id| block.inputs.0.contains_key(id)));
+                argumentids.push(ids.clone());
+            }
+        }
+    }
+    assert_eq!(argumentids.len(), 2);
+    assert_eq!(argumentids[0], argumentids[1]);
+    assert_eq!(argumentids[0][0].len(), uid::LENGTH);
+}
+
+#[test]
+fn remap_ids_keeps_numeric_names() {
+    let mut project = simple_project();
+    project
+        .add_scratchblocks("Stage", "when green flag clicked\nset [score v] to [0]\n")
+        .unwrap();
+    fn field(project: &mut Project) -> &mut BlockField {
+        (project.targets[0].target_mut().blocks.0.values_mut())
+            .find_map(|b| match b {
+                Block::Normal(b) => b.fields.0.get_mut("VARIABLE"),
+                _ => None,
+            })
+            .unwrap()
+    }
+    // A variable named `5` is stored as a number by Scratch
+    let BlockField::WithId { value, .. } = field(&mut project) else {
+        panic!("the field should have an Id")
+    };
+    *value = Value::from(5);
+
+    project.remap_ids(IdStyle::Short);
+    let BlockField::WithId { value, id } = field(&mut project) else {
+        panic!("the field should have an Id")
+    };
+    assert_eq!(*value, Value::from(5));
+    assert_eq!(id.as_deref(), Some("!"));
+}
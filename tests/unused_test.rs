@@ -0,0 +1,185 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    block::{Block, DataKind},
+    builder::ProjectBuilder,
+    comment::Comment,
+    unused::{PruneOptions, Unused, UnusedKind},
+    variable::Variable,
+};
+
+#[test]
+fn unused_simple_project() {
+    let unused = simple_project().unused();
+    let lines: Vec<String> = unused.iter().map(Unused::to_string).collect();
+    assert_eq!(
+        lines,
+        [
+            "Stage: sound `pop` (`83a9787d4cb6f3b7632b4ddfebf74367`) is never played",
+            "Sprite1: costume `costume2` (`0fb9be3e8397c983338cb71dc84d0b25`) is never switched to",
+            "Sprite1: sound `Meow` (`83c36d806dc92327b9e7049a565c6bff`) is never played",
+            "apple: sound `pop` (`83a9787d4cb6f3b7632b4ddfebf74367`) is never played",
+        ]
+    );
+}
+
+#[test]
+fn unused_scripts_and_data() {
+    let mut project = ProjectBuilder::new();
+    project
+        .sprite("Cat")
+        .variable("score", 0)
+        .variable("lives", 0);
+    let mut project = project.build();
+    project
+        .add_scratchblocks(
+            "Cat",
+            "\
+when green flag clicked
+set [score v] to [0]
+change [lives v] by (1)
+say (lives)
+broadcast [go v]
+jump (10)
+
+when I receive [stop v]
+stop [all v]
+
+define jump (height)
+change y by (height)
+jump (height)
+
+define spin
+turn right (15) degrees
+spin
+
+move (10) steps
+",
+        )
+        .unwrap();
+    let stage = project.targets[0].target();
+    let cat = project.targets[1].target();
+    let id = |name: &str| {
+        let (id, _) = (cat.variables.0.iter().map(|(i, v)| (i, &v.name)))
+            .chain(stage.broadcasts.0.iter().map(|(i, b)| (i, &b.name)))
+            .find(|(_, n)| *n == name)
+            .unwrap();
+        id.clone()
+    };
+    let top = |opcode: &str| {
+        let script = cat.scripts().into_iter().find(|s| {
+            s.top().opcode() == Some(opcode)
+                && (opcode != "procedures_definition"
+                    || s.walk()
+                        .unwrap()
+                        .iter()
+                        .any(|b| b.opcode() == Some("motion_turnright")))
+        });
+        script.unwrap().id().clone()
+    };
+
+    let unused = project.unused();
+    let targets: Vec<&str> = unused.iter().map(|u| u.target.as_str()).collect();
+    // Broadcasts are declared in the stage
+    assert_eq!(targets, ["Stage", "Stage", "Cat", "Cat", "Cat"]);
+    let kinds: Vec<UnusedKind> = unused.into_iter().map(|u| u.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            UnusedKind::UnreceivedBroadcast {
+                id: id("go"),
+                name: "go".to_owned(),
+            },
+            UnusedKind::UnsentBroadcast {
+                id: id("stop"),
+                name: "stop".to_owned(),
+            },
+            UnusedKind::FloatingScript {
+                id: top("motion_movesteps")
+            },
+            // Only calls itself
+            UnusedKind::UncalledProcedure {
+                id: top("procedures_definition"),
+                proccode: "spin".to_owned(),
+            },
+            UnusedKind::UnreadData {
+                kind: DataKind::Variable,
+                id: id("score"),
+                name: "score".to_owned(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn prune_project() {
+    let mut project = simple_project();
+    let apple = project.targets[2].target_mut();
+    let Some(Block::Normal(block)) = apple
+        .blocks
+        .0
+        .values()
+        .find(|b| !b.data_references().is_empty())
+    else {
+        panic!()
+    };
+    let mut orphan = block.clone();
+    orphan.parent = Some("gone".to_owned());
+    orphan.next = Some("orphan2".to_owned());
+    orphan.top_level = false;
+    let mut orphan2 = orphan.clone();
+    orphan2.parent = Some("orphan".to_owned());
+    orphan2.next = None;
+    apple
+        .blocks
+        .0
+        .insert("orphan".to_owned(), Block::Normal(orphan));
+    apple
+        .blocks
+        .0
+        .insert("orphan2".to_owned(), Block::Normal(orphan2));
+    let comment = Comment {
+        block_id: Some("orphan".to_owned()),
+        text: "lost".to_owned(),
+        ..Default::default()
+    };
+    apple.comments.0.insert("comment".to_owned(), comment);
+    let variable = Variable {
+        name: "nothing".to_owned(),
+        ..Default::default()
+    };
+    apple.variables.0.insert("nothing".to_owned(), variable);
+
+    let mut pruned = project.clone();
+    pruned.prune(PruneOptions::default());
+    let apple = pruned.targets[2].target();
+    assert_eq!(apple.blocks, simple_project().targets[2].target().blocks);
+    assert!(apple.comments.0.contains_key("comment"));
+    assert!(apple.variables.0.contains_key("nothing"));
+
+    project.prune(PruneOptions::all());
+    assert_eq!(project.validate(), vec![]);
+    assert_eq!(project.unused(), vec![]);
+    let apple = project.targets[2].target();
+    assert!(apple.comments.0.is_empty());
+    assert!(apple.variables.0.is_empty());
+    // The global variable is still used by apple
+    assert_eq!(project.targets[0].target().variables.0.len(), 1);
+    let sprite = project.targets[1].target();
+    assert_eq!(sprite.costumes.len(), 1);
+    assert!(sprite.sounds.is_empty());
+}
+
+#[test]
+fn prune_keeps_a_costume() {
+    let mut project = simple_project();
+    let sprite = project.targets[1].target_mut();
+    // Worn costume out of range, so no costume is used
+    sprite.current_costume = 99;
+    let first = sprite.costumes[0].clone();
+    project.prune(PruneOptions::all());
+    let sprite = project.targets[1].target();
+    assert_eq!(sprite.costumes, [first]);
+    assert_eq!(sprite.current_costume, 0);
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::simple_project;
+use sb_sbity::{
+    block::{Block, DataKind},
+    project::Project,
+    target::SpriteOrStage,
+    validate::DiagnosticKind,
+};
+
+fn kinds(project: &Project) -> Vec<DiagnosticKind> {
+    project.validate().into_iter().map(|d| d.kind).collect()
+}
+
+#[test]
+fn validate_test_projects() {
+    assert_eq!(kinds(&simple_project()), []);
+
+    let slitcherio: Project =
+        serde_json::from_str(include_str!("serde_test_mod/test_case/slitcherio.json")).unwrap();
+    assert_eq!(kinds(&slitcherio), []);
+
+    // Has a monitor of a deleted sprite
+    let orbit: Project =
+        serde_json::from_str(include_str!("serde_test_mod/test_case/orbit_project.json")).unwrap();
+    let diagnostics = orbit.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].path, "/monitors/5/spriteName");
+    assert_eq!(
+        diagnostics[0].kind,
+        DiagnosticKind::MonitorMissingSprite {
+            sprite_name: "score".to_owned()
+        }
+    );
+}
+
+#[test]
+fn validate_block_links() {
+    let mut project = simple_project();
+    let blocks = &mut project.targets[1].target_mut().blocks.0;
+    let Some(Block::Normal(when_key)) = blocks.get_mut("p=pS%`3y,[up*d^b}A+.") else {
+        panic!()
+    };
+    when_key.x = None;
+    when_key.parent = Some("g(`uOQT(5d)P)P3/TQfL".to_owned());
+    let Some(Block::Normal(change_x)) = blocks.get_mut("g(`uOQT(5d)P)P3/TQfL") else {
+        panic!()
+    };
+    change_x.parent = None;
+    change_x.next = Some("nothing".to_owned());
+
+    let diagnostics = project.validate();
+    let paths = diagnostics
+        .iter()
+        .map(|d| (d.path.as_str(), &d.kind))
+        .collect::<Vec<_>>();
+    assert!(paths.contains(&(
+        "/targets/1/blocks/p=pS%`3y,[up*d^b}A+./topLevel",
+        &DiagnosticKind::TopLevelWithParent
+    )));
+    assert!(paths.contains(&(
+        "/targets/1/blocks/p=pS%`3y,[up*d^b}A+./x",
+        &DiagnosticKind::TopLevelMissingPosition
+    )));
+    assert!(paths.contains(&(
+        "/targets/1/blocks/p=pS%`3y,[up*d^b}A+./next",
+        &DiagnosticKind::NextParentMismatch {
+            next: "g(`uOQT(5d)P)P3/TQfL".to_owned(),
+            next_parent: None
+        }
+    )));
+    assert!(paths.contains(&(
+        "/targets/1/blocks/g(`uOQT(5d)P)P3~1TQfL/next",
+        &DiagnosticKind::DanglingBlock {
+            id: "nothing".to_owned()
+        }
+    )));
+    assert!(paths.contains(&(
+        "/targets/1/blocks/g(`uOQT(5d)P)P3~1TQfL/topLevel",
+        &DiagnosticKind::NotTopLevelWithoutParent
+    )));
+}
+
+#[test]
+fn validate_targets_and_references() {
+    let mut project = simple_project();
+    project.targets[0].target_mut().variables.0.clear();
+    project.targets[2].target_mut().current_costume = 3;
+    project.targets[2].target_mut().name = "Sprite1".to_owned();
+    let SpriteOrStage::Stage(stage) = project.targets[0].clone() else {
+        panic!()
+    };
+    project.targets.push(SpriteOrStage::Stage(stage));
+
+    let diagnostics = project.validate();
+    let undeclared = diagnostics
+        .iter()
+        .find(|d| matches!(d.kind, DiagnosticKind::UndeclaredReference { .. }))
+        .unwrap();
+    assert!(undeclared.path.starts_with("/targets/1/blocks/TAG2EUn"));
+    assert!(undeclared.path.ends_with("/fields/VARIABLE"));
+
+    let kinds = kinds(&project);
+    assert!(kinds.contains(&DiagnosticKind::StageCount { count: 2 }));
+    assert!(kinds.contains(&DiagnosticKind::DuplicateTargetName {
+        name: "Sprite1".to_owned()
+    }));
+    assert!(kinds.contains(&DiagnosticKind::CostumeOutOfRange {
+        current_costume: 3,
+        costume_count: 1
+    }));
+    assert!(kinds.contains(&DiagnosticKind::UndeclaredReference {
+        kind: DataKind::Variable,
+        name: "score".to_owned(),
+        id: "31*#$v.#k`Yll6(`.hFu".to_owned()
+    }));
+    assert!(kinds.contains(&DiagnosticKind::MonitorMissingData {
+        kind: DataKind::Variable,
+        name: "score".to_owned()
+    }));
+}
+
+#[test]
+fn validate_monitor_ids() {
+    let mut project = simple_project();
+    project.monitors[0].id = "renamed".to_owned();
+    let diagnostics = project.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].path, "/monitors/0/id");
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "/monitors/0/id: Variable `score` exists but not with Id `renamed`"
+    );
+}
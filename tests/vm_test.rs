@@ -0,0 +1,251 @@
+use sb_sbity::{
+    block::Block,
+    builder::{control, data, events, looks, motion, ProjectBuilder},
+    string_hashmap::StringHashMap,
+    value::{Number, ValueWithBool},
+    vm::{Vm, WARP_ITERATIONS},
+};
+
+fn number(n: i64) -> ValueWithBool {
+    ValueWithBool::Number(Number::Int(n))
+}
+
+fn text(t: &str) -> ValueWithBool {
+    ValueWithBool::Text(t.to_owned())
+}
+
+#[test]
+fn vm_loops_and_waits() {
+    let mut project = ProjectBuilder::new();
+    project.stage().variable("i", 0).list("log", ["start"]);
+    let cat = project.sprite("Cat");
+    cat.when_flag_clicked()
+        .then(data::set_variable_to("i", 0))
+        .then(control::repeat(10, |s| {
+            s.then(data::change_variable_by("i", 1))
+                .then(motion::change_x_by(2))
+        }))
+        .then(data::add_to_list("done", "log"));
+    cat.variable("waited", 0);
+    cat.when_flag_clicked()
+        .then(control::wait(1))
+        .then(data::set_variable_to("waited", 1))
+        .then(looks::say("hi"));
+    let mut vm = Vm::new(project.build());
+
+    vm.step();
+    assert!(!vm.is_running());
+    vm.green_flag();
+    vm.step_frames(5);
+    assert_eq!(vm.variable("Stage", "i"), Some(&number(5)));
+    assert_eq!(vm.sprite("Cat").unwrap().x, Number::Int(10));
+
+    vm.step_frames(25);
+    assert_eq!(vm.variable("Stage", "i"), Some(&number(10)));
+    assert_eq!(vm.variable("Cat", "waited"), Some(&number(0)));
+    assert_eq!(
+        vm.list("Stage", "log").unwrap(),
+        [text("start"), text("done")]
+    );
+
+    vm.step();
+    assert_eq!(vm.variable("Cat", "waited"), Some(&number(1)));
+    assert_eq!(vm.bubble("Cat"), Some("hi"));
+    assert!(vm.run(10));
+    assert!(vm.unsupported_opcodes().is_empty());
+}
+
+#[test]
+fn vm_broadcasts_and_clones() {
+    let mut project = ProjectBuilder::new();
+    project
+        .stage()
+        .variable("clones", 0)
+        .variable("went", 0)
+        .variable("after", 0);
+    project
+        .stage()
+        .when_broadcast_received("go")
+        .then(control::wait(0.5))
+        .then(data::set_variable_to("went", 1));
+    let cat = project.sprite("Cat");
+    cat.when_flag_clicked()
+        .then(control::repeat(3, |s| {
+            s.then(control::create_clone_of("_myself_"))
+        }))
+        .then(events::broadcast_and_wait("go"))
+        .then(data::set_variable_to("after", data::variable("went")));
+    cat.when_i_start_as_a_clone()
+        .then(data::change_variable_by("clones", 1))
+        .then(motion::goto_xy(data::variable("clones"), 0));
+    let mut vm = Vm::new(project.build());
+
+    vm.green_flag();
+    assert!(vm.run(100));
+    assert_eq!(vm.variable("Stage", "clones"), Some(&number(3)));
+    assert_eq!(vm.variable("Stage", "after"), Some(&number(1)));
+    let clones = vm.clones("Cat");
+    assert_eq!(clones.len(), 3);
+    // Every clone goes right behind the sprite it was made from, so the first one is at the back
+    let xs: Vec<_> = clones.iter().map(|c| c.x).collect();
+    assert_eq!(xs, [Number::Int(1), Number::Int(2), Number::Int(3)]);
+    assert_eq!(vm.sprite("Cat").unwrap().x, Number::Int(0));
+
+    vm.green_flag();
+    assert!(vm.clones("Cat").is_empty());
+}
+
+/// `define count (n)`: if n > 0, change total by n and count (n - 1).
+fn count_blocks(warp: bool) -> StringHashMap<Block> {
+    let warp = warp.to_string();
+    serde_json::from_value(serde_json::json!({
+        "hat": {
+            "opcode": "event_whenflagclicked", "next": "call", "parent": null,
+            "inputs": {}, "fields": {}, "shadow": false, "topLevel": true, "x": 0, "y": 0
+        },
+        "call": {
+            "opcode": "procedures_call", "next": null, "parent": "hat",
+            "inputs": {"arg": [1, [10, "5"]]}, "fields": {}, "shadow": false, "topLevel": false,
+            "mutation": {
+                "tagName": "mutation", "children": [], "proccode": "count %s",
+                "argumentids": "[\"arg\"]", "warp": warp
+            }
+        },
+        "define": {
+            "opcode": "procedures_definition", "next": "if", "parent": null,
+            "inputs": {"custom_block": [1, "prototype"]}, "fields": {},
+            "shadow": false, "topLevel": true, "x": 0, "y": 200
+        },
+        "prototype": {
+            "opcode": "procedures_prototype", "next": null, "parent": "define",
+            "inputs": {"arg": [1, "prototype n"]}, "fields": {}, "shadow": true, "topLevel": false,
+            "mutation": {
+                "tagName": "mutation", "children": [], "proccode": "count %s",
+                "argumentids": "[\"arg\"]", "argumentnames": "[\"n\"]",
+                "argumentdefaults": "[\"\"]", "warp": warp
+            }
+        },
+        "prototype n": {
+            "opcode": "argument_reporter_string_number", "next": null, "parent": "prototype",
+            "inputs": {}, "fields": {"VALUE": ["n", null]}, "shadow": true, "topLevel": false
+        },
+        "if": {
+            "opcode": "control_if", "next": null, "parent": "define",
+            "inputs": {"CONDITION": [2, "gt"], "SUBSTACK": [2, "change"]}, "fields": {},
+            "shadow": false, "topLevel": false
+        },
+        "gt": {
+            "opcode": "operator_gt", "next": null, "parent": "if",
+            "inputs": {"OPERAND1": [3, "gt n", [10, ""]], "OPERAND2": [1, [10, "0"]]},
+            "fields": {}, "shadow": false, "topLevel": false
+        },
+        "gt n": {
+            "opcode": "argument_reporter_string_number", "next": null, "parent": "gt",
+            "inputs": {}, "fields": {"VALUE": ["n", null]}, "shadow": false, "topLevel": false
+        },
+        "change": {
+            "opcode": "data_changevariableby", "next": "recurse", "parent": "if",
+            "inputs": {"VALUE": [3, "change n", [4, "1"]]},
+            "fields": {"VARIABLE": ["total", "not the id of total"]},
+            "shadow": false, "topLevel": false
+        },
+        "change n": {
+            "opcode": "argument_reporter_string_number", "next": null, "parent": "change",
+            "inputs": {}, "fields": {"VALUE": ["n", null]}, "shadow": false, "topLevel": false
+        },
+        "recurse": {
+            "opcode": "procedures_call", "next": null, "parent": "change",
+            "inputs": {"arg": [3, "subtract", [10, ""]]}, "fields": {},
+            "shadow": false, "topLevel": false,
+            "mutation": {
+                "tagName": "mutation", "children": [], "proccode": "count %s",
+                "argumentids": "[\"arg\"]", "warp": warp
+            }
+        },
+        "subtract": {
+            "opcode": "operator_subtract", "next": null, "parent": "recurse",
+            "inputs": {"NUM1": [3, "subtract n", [4, ""]], "NUM2": [1, [4, "1"]]},
+            "fields": {}, "shadow": false, "topLevel": false
+        },
+        "subtract n": {
+            "opcode": "argument_reporter_string_number", "next": null, "parent": "subtract",
+            "inputs": {}, "fields": {"VALUE": ["n", null]}, "shadow": false, "topLevel": false
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn vm_procedures() {
+    for warp in [false, true] {
+        let mut project = ProjectBuilder::new();
+        project.stage().variable("total", 0);
+        project.sprite("Cat");
+        let mut project = project.build();
+        project.targets[1].target_mut().blocks = count_blocks(warp);
+
+        let mut vm = Vm::new(project);
+        vm.green_flag();
+        vm.step();
+        // Recursion yields once per call unless it runs without screen refresh
+        let expected = if warp { 15 } else { 5 };
+        assert_eq!(vm.variable("Stage", "total"), Some(&number(expected)));
+        assert!(vm.run(10));
+        assert_eq!(vm.variable("Stage", "total"), Some(&number(15)));
+        assert!(vm.unsupported_opcodes().is_empty());
+    }
+}
+
+#[test]
+fn vm_warp_yields() {
+    let mut project = ProjectBuilder::new();
+    project.sprite("Cat");
+    let mut project = project.build();
+    project
+        .add_scratchblocks(
+            "Cat",
+            "\
+when green flag clicked
+spin
+
+define spin // run without screen refresh
+forever
+change x by (1)
+end
+",
+        )
+        .unwrap();
+
+    let mut vm = Vm::new(project);
+    vm.green_flag();
+    vm.step();
+    let x = |vm: &Vm| vm.sprite("Cat").unwrap().x;
+    assert_eq!(x(&vm), Number::Int(WARP_ITERATIONS as i64));
+    assert!(!vm.run(2));
+    assert_eq!(x(&vm), Number::Int(3 * WARP_ITERATIONS as i64));
+}
+
+#[test]
+fn vm_infinite_layers() {
+    let mut project = ProjectBuilder::new();
+    project.sprite("Dog");
+    project.sprite("Cat").variable("done", 0);
+    let mut project = project.build();
+    project
+        .add_scratchblocks(
+            "Cat",
+            "\
+when green flag clicked
+go [forward v] (Infinity) layers
+go [backward v] (-Infinity) layers
+go [backward v] (Infinity) layers
+change [done v] by (1)
+",
+        )
+        .unwrap();
+
+    let mut vm = Vm::new(project);
+    vm.green_flag();
+    assert!(vm.run(10));
+    assert_eq!(vm.variable("Cat", "done"), Some(&number(1)));
+}